@@ -0,0 +1,70 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod pagination;
+pub mod queries;
+pub mod routes;
+
+use anyhow::{Context, Result};
+use processor::utils::database::new_db_pool;
+use routes::ApiContext;
+use serde::{Deserialize, Serialize};
+use server_framework::RunnableConfig;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexerApiConfig {
+    pub postgres_connection_string: String,
+    // Size of the pool for reads
+    pub db_pool_size: Option<u32>,
+    // Port the REST API listens on. This is separate from the health check port.
+    #[serde(default = "IndexerApiConfig::default_api_port")]
+    pub api_port: u16,
+    // Page size used when a request doesn't specify a limit
+    #[serde(default = "IndexerApiConfig::default_page_size")]
+    pub default_page_size: i64,
+    // Requests asking for more rows than this are clamped
+    #[serde(default = "IndexerApiConfig::default_max_page_size")]
+    pub max_page_size: i64,
+}
+
+impl IndexerApiConfig {
+    pub const fn default_api_port() -> u16 {
+        8080
+    }
+
+    pub const fn default_page_size() -> i64 {
+        25
+    }
+
+    pub const fn default_max_page_size() -> i64 {
+        100
+    }
+}
+
+#[async_trait::async_trait]
+impl RunnableConfig for IndexerApiConfig {
+    async fn run(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.default_page_size >= 1 && self.default_page_size <= self.max_page_size,
+            "default_page_size must be between 1 and max_page_size"
+        );
+        let pool = new_db_pool(&self.postgres_connection_string, self.db_pool_size)
+            .await
+            .context("Failed to create connection pool")?;
+        let context = ApiContext {
+            pool,
+            default_page_size: self.default_page_size,
+            max_page_size: self.max_page_size,
+        };
+        tracing::info!(port = self.api_port, "[Indexer API] Starting server");
+        warp::serve(routes::routes(context))
+            .run(([0, 0, 0, 0], self.api_port))
+            .await;
+        Ok(())
+    }
+
+    fn get_server_name(&self) -> String {
+        "idxapi".to_string()
+    }
+}
@@ -0,0 +1,15 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Parser;
+use indexer_api::IndexerApiConfig;
+use server_framework::ServerArgs;
+
+#[allow(clippy::needless_return)]
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = ServerArgs::parse();
+    args.run::<IndexerApiConfig>(tokio::runtime::Handle::current())
+        .await
+}
@@ -0,0 +1,136 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+const CURSOR_SEPARATOR: char = ':';
+
+/// Query parameters shared by all paginated endpoints.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageParams {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Cursors point at the last row of the previous page. `keys` are the values of the columns the
+/// endpoint sorts by, e.g. the transaction version and event index for activities, encoded as
+/// `key[:key...]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    pub keys: Vec<String>,
+}
+
+impl Cursor {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys }
+    }
+
+    /// Fails if the cursor doesn't have exactly `num_keys` keys, which means it was issued by a
+    /// different endpoint.
+    pub fn expect_keys(&self, num_keys: usize) -> anyhow::Result<&[String]> {
+        anyhow::ensure!(
+            self.keys.len() == num_keys,
+            "Cursor should have {} key(s), got {}",
+            num_keys,
+            self.keys.len()
+        );
+        Ok(&self.keys)
+    }
+}
+
+/// Parses a transaction version key of a cursor
+pub fn parse_version(key: &str) -> anyhow::Result<i64> {
+    let version = key
+        .parse::<i64>()
+        .with_context(|| format!("Invalid cursor version {}", key))?;
+    anyhow::ensure!(version >= 0, "Cursor version must not be negative");
+    Ok(version)
+}
+
+impl FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let keys: Vec<String> = s
+            .split(CURSOR_SEPARATOR)
+            .map(|key| key.to_string())
+            .collect();
+        anyhow::ensure!(
+            keys.iter().all(|key| !key.is_empty()),
+            "Cursor {} has an empty key",
+            s
+        );
+        Ok(Self { keys })
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keys.join(&CURSOR_SEPARATOR.to_string()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Queries fetch one row more than `limit` so that we know whether there's a next page
+    /// without a separate count query.
+    pub fn from_rows(mut rows: Vec<T>, limit: i64, cursor_of: impl Fn(&T) -> Cursor) -> Self {
+        let limit = limit as usize;
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|row| cursor_of(row).to_string())
+        } else {
+            None
+        };
+        Self {
+            data: rows,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(vec!["12345".to_string(), "7".to_string()]);
+        assert_eq!(cursor.to_string(), "12345:7");
+        assert_eq!("12345:7".parse::<Cursor>().unwrap(), cursor);
+
+        let cursor = "99".parse::<Cursor>().unwrap();
+        assert_eq!(cursor, Cursor::new(vec!["99".to_string()]));
+        assert!(cursor.expect_keys(1).is_ok());
+        assert!(cursor.expect_keys(2).is_err());
+    }
+
+    #[test]
+    fn test_invalid_cursors() {
+        assert!("".parse::<Cursor>().is_err());
+        assert!("10::0x1".parse::<Cursor>().is_err());
+        assert!(parse_version("abc").is_err());
+        assert!(parse_version("-1").is_err());
+        assert_eq!(parse_version("10").unwrap(), 10);
+    }
+
+    #[test]
+    fn test_page_from_rows() {
+        let cursor_of = |v: &i64| Cursor::new(vec![v.to_string()]);
+        let page = Page::from_rows(vec![5, 4, 3], 2, cursor_of);
+        assert_eq!(page.data, vec![5, 4]);
+        assert_eq!(page.next_cursor, Some("4".to_string()));
+
+        let page = Page::from_rows(vec![5, 4], 2, cursor_of);
+        assert_eq!(page.data, vec![5, 4]);
+        assert_eq!(page.next_cursor, None);
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keyset paginated reads over the tables written by the processors. Every query fetches
+//! `limit + 1` rows, see `Page::from_rows`. History is ordered by transaction version descending,
+//! and current state by primary key since the last transaction version of a row changes with it.
+
+use crate::pagination::{Cursor, Page};
+use ahash::AHashMap;
+use bigdecimal::{BigDecimal, Zero};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use processor::{
+    db::common::models::{
+        ans_models::address_display_names::AddressDisplayNameQuery,
+        fungible_asset_models::{
+            v2_fungible_asset_activities::FungibleAssetActivity,
+            v2_fungible_asset_balances::CurrentUnifiedFungibleAssetBalanceQuery,
+        },
+        token_v2_models::{
+            v2_token_activities::TokenActivityV2, v2_token_ownerships::CurrentTokenOwnershipV2,
+        },
+        user_transactions_models::user_transactions::UserTransaction,
+    },
+    schema::{
+        account_transactions, current_fungible_asset_balances, current_token_ownerships_v2,
+        fungible_asset_activities, token_activities_v2, user_transactions,
+    },
+    utils::database::ArcDbPool,
+};
+use serde::Serialize;
+
+/// Cursor of an activity row, (transaction_version, event_index)
+pub type ActivityCursor = (i64, i64);
+/// Cursor of a balance row, its storage_id
+pub type BalanceCursor = String;
+/// Cursor of an ownership row, (token_data_id, property_version_v1, storage_id)
+pub type TokenOwnershipCursor = (String, BigDecimal, String);
+
+#[derive(Debug, Serialize)]
+pub struct DisplayNameResponse {
+    pub address: String,
+    // The address's primary name, if it has an active one
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountTransactionResponse {
+    pub transaction_version: i64,
+    // Only set for user transactions
+    pub user_transaction: Option<UserTransaction>,
+}
+
+pub async fn get_account_transactions(
+    pool: &ArcDbPool,
+    address: &str,
+    cursor: Option<i64>,
+    limit: i64,
+) -> anyhow::Result<Page<AccountTransactionResponse>> {
+    let mut conn = pool.get().await?;
+    let mut query = account_transactions::table
+        .select(account_transactions::transaction_version)
+        .filter(account_transactions::account_address.eq(address))
+        .into_boxed();
+    if let Some(version) = cursor {
+        query = query.filter(account_transactions::transaction_version.lt(version));
+    }
+    let versions: Vec<i64> = query
+        .order(account_transactions::transaction_version.desc())
+        .limit(limit + 1)
+        .load(&mut conn)
+        .await?;
+    let page = Page::from_rows(versions, limit, |version| {
+        Cursor::new(vec![version.to_string()])
+    });
+
+    let mut user_txns: AHashMap<i64, UserTransaction> = user_transactions::table
+        .select(UserTransaction::as_select())
+        .filter(user_transactions::version.eq_any(page.data.clone()))
+        .load::<UserTransaction>(&mut conn)
+        .await?
+        .into_iter()
+        .map(|txn| (txn.version, txn))
+        .collect();
+    Ok(Page {
+        data: page
+            .data
+            .into_iter()
+            .map(|transaction_version| AccountTransactionResponse {
+                transaction_version,
+                user_transaction: user_txns.remove(&transaction_version),
+            })
+            .collect(),
+        next_cursor: page.next_cursor,
+    })
+}
+
+pub async fn get_fungible_asset_activities(
+    pool: &ArcDbPool,
+    address: &str,
+    cursor: Option<ActivityCursor>,
+    limit: i64,
+) -> anyhow::Result<Page<FungibleAssetActivity>> {
+    use fungible_asset_activities::dsl::*;
+
+    let mut conn = pool.get().await?;
+    let mut query = fungible_asset_activities
+        .select(FungibleAssetActivity::as_select())
+        .filter(owner_address.eq(address))
+        .into_boxed();
+    if let Some((version, index)) = cursor {
+        query = query.filter(
+            transaction_version
+                .lt(version)
+                .or(transaction_version.eq(version).and(event_index.lt(index))),
+        );
+    }
+    let rows = query
+        .order((transaction_version.desc(), event_index.desc()))
+        .limit(limit + 1)
+        .load::<FungibleAssetActivity>(&mut conn)
+        .await?;
+    Ok(Page::from_rows(rows, limit, |row| {
+        Cursor::new(vec![
+            row.transaction_version.to_string(),
+            row.event_index.to_string(),
+        ])
+    }))
+}
+
+/// Token activities where the account is either the sender or the receiver
+pub async fn get_token_activities(
+    pool: &ArcDbPool,
+    address: &str,
+    cursor: Option<ActivityCursor>,
+    limit: i64,
+) -> anyhow::Result<Page<TokenActivityV2>> {
+    use token_activities_v2::dsl::*;
+
+    let mut conn = pool.get().await?;
+    let mut query = token_activities_v2
+        .select(TokenActivityV2::as_select())
+        .filter(from_address.eq(address).or(to_address.eq(address)))
+        .into_boxed();
+    if let Some((version, index)) = cursor {
+        query = query.filter(
+            transaction_version
+                .lt(version)
+                .or(transaction_version.eq(version).and(event_index.lt(index))),
+        );
+    }
+    let rows = query
+        .order((transaction_version.desc(), event_index.desc()))
+        .limit(limit + 1)
+        .load::<TokenActivityV2>(&mut conn)
+        .await?;
+    Ok(Page::from_rows(rows, limit, |row| {
+        Cursor::new(vec![
+            row.transaction_version.to_string(),
+            row.event_index.to_string(),
+        ])
+    }))
+}
+
+/// Non-zero fungible asset balances, by storage id
+pub async fn get_fungible_asset_balances(
+    pool: &ArcDbPool,
+    address: &str,
+    cursor: Option<BalanceCursor>,
+    limit: i64,
+) -> anyhow::Result<Page<CurrentUnifiedFungibleAssetBalanceQuery>> {
+    use current_fungible_asset_balances::dsl::*;
+
+    let mut conn = pool.get().await?;
+    let mut query = current_fungible_asset_balances
+        .select(CurrentUnifiedFungibleAssetBalanceQuery::as_select())
+        .filter(owner_address.eq(address))
+        .filter(amount.gt(BigDecimal::zero()))
+        .into_boxed();
+    if let Some(last_storage_id) = cursor {
+        query = query.filter(storage_id.gt(last_storage_id));
+    }
+    let rows = query
+        .order(storage_id)
+        .limit(limit + 1)
+        .load::<CurrentUnifiedFungibleAssetBalanceQuery>(&mut conn)
+        .await?;
+    Ok(Page::from_rows(rows, limit, |row| {
+        Cursor::new(vec![row.storage_id.clone()])
+    }))
+}
+
+/// Tokens currently owned by the account, by primary key
+pub async fn get_token_ownerships(
+    pool: &ArcDbPool,
+    address: &str,
+    cursor: Option<TokenOwnershipCursor>,
+    limit: i64,
+) -> anyhow::Result<Page<CurrentTokenOwnershipV2>> {
+    use current_token_ownerships_v2::dsl::*;
+
+    let mut conn = pool.get().await?;
+    let mut query = current_token_ownerships_v2
+        .select(CurrentTokenOwnershipV2::as_select())
+        .filter(owner_address.eq(address))
+        .filter(amount.gt(BigDecimal::zero()))
+        .into_boxed();
+    if let Some((last_token_data_id, last_property_version, last_storage_id)) = cursor {
+        query = query.filter(
+            token_data_id
+                .gt(last_token_data_id.clone())
+                .or(token_data_id.eq(last_token_data_id).and(
+                    property_version_v1
+                        .gt(last_property_version.clone())
+                        .or(property_version_v1
+                            .eq(last_property_version)
+                            .and(storage_id.gt(last_storage_id))),
+                )),
+        );
+    }
+    let rows = query
+        .order((token_data_id, property_version_v1, storage_id))
+        .limit(limit + 1)
+        .load::<CurrentTokenOwnershipV2>(&mut conn)
+        .await?;
+    Ok(Page::from_rows(rows, limit, |row| {
+        Cursor::new(vec![
+            row.token_data_id.clone(),
+            row.property_version_v1.to_string(),
+            row.storage_id.clone(),
+        ])
+    }))
+}
+
+/// Display names of many addresses at once, in the order they were requested
+pub async fn get_display_names(
+    pool: &ArcDbPool,
+    addresses: Vec<String>,
+) -> anyhow::Result<Vec<DisplayNameResponse>> {
+    let mut conn = pool.get().await?;
+    let mut names = AddressDisplayNameQuery::resolve(&addresses, &mut conn).await?;
+    Ok(addresses
+        .into_iter()
+        .map(|address| DisplayNameResponse {
+            name: names.remove(&address),
+            address,
+        })
+        .collect())
+}
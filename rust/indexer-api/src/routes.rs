@@ -0,0 +1,278 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    pagination::{parse_version, Cursor, PageParams},
+    queries,
+};
+use anyhow::Context;
+use bigdecimal::BigDecimal;
+use processor::utils::{database::ArcDbPool, util::standardize_address};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use warp::{
+    http::StatusCode,
+    reply::{Reply, Response},
+    Filter, Rejection,
+};
+
+#[derive(Clone)]
+pub struct ApiContext {
+    pub pool: ArcDbPool,
+    pub default_page_size: i64,
+    pub max_page_size: i64,
+}
+
+impl ApiContext {
+    fn page_limit(&self, params: &PageParams) -> i64 {
+        params
+            .limit
+            .unwrap_or(self.default_page_size)
+            .clamp(1, self.max_page_size)
+    }
+}
+
+/// Query parameters of the name resolution endpoint
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayNamesParams {
+    // Comma separated addresses
+    pub addresses: String,
+}
+
+#[derive(Debug)]
+enum ApiError {
+    BadRequest(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+/// Account endpoints are scoped to a single account and paginated with `?cursor=...&limit=...`,
+/// where the cursor is the `next_cursor` returned with the previous page. Names of up to a page
+/// of addresses are resolved at once with `/v1/names?addresses=...`.
+pub fn routes(
+    context: ApiContext,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone {
+    let context = warp::any().map(move || context.clone());
+    let account = warp::get().and(warp::path!("v1" / "accounts" / String / ..));
+
+    let transactions_route = account
+        .clone()
+        .and(warp::path!("transactions"))
+        .and(warp::query::<PageParams>())
+        .and(context.clone())
+        .then(account_transactions);
+    let fungible_asset_activities_route = account
+        .clone()
+        .and(warp::path!("fungible_asset_activities"))
+        .and(warp::query::<PageParams>())
+        .and(context.clone())
+        .then(fungible_asset_activities);
+    let token_activities_route = account
+        .clone()
+        .and(warp::path!("token_activities"))
+        .and(warp::query::<PageParams>())
+        .and(context.clone())
+        .then(token_activities);
+    let balances_route = account
+        .clone()
+        .and(warp::path!("balances"))
+        .and(warp::query::<PageParams>())
+        .and(context.clone())
+        .then(balances);
+    let tokens_route = account
+        .and(warp::path!("tokens"))
+        .and(warp::query::<PageParams>())
+        .and(context.clone())
+        .then(tokens);
+    let names_route = warp::get()
+        .and(warp::path!("v1" / "names"))
+        .and(warp::query::<DisplayNamesParams>())
+        .and(context)
+        .then(display_names);
+
+    transactions_route
+        .or(fungible_asset_activities_route)
+        .unify()
+        .or(token_activities_route)
+        .unify()
+        .or(balances_route)
+        .unify()
+        .or(tokens_route)
+        .unify()
+        .or(names_route)
+        .unify()
+}
+
+async fn account_transactions(
+    address: String,
+    params: PageParams,
+    context: ApiContext,
+) -> Response {
+    into_response(
+        async {
+            let cursor = parse_cursor(&params, |cursor| {
+                let keys = cursor.expect_keys(1)?;
+                parse_version(&keys[0])
+            })?;
+            queries::get_account_transactions(
+                &context.pool,
+                &standardize_address(&address),
+                cursor,
+                context.page_limit(&params),
+            )
+            .await
+            .map_err(ApiError::Internal)
+        }
+        .await,
+    )
+}
+
+async fn fungible_asset_activities(
+    address: String,
+    params: PageParams,
+    context: ApiContext,
+) -> Response {
+    into_response(
+        async {
+            let cursor = parse_cursor(&params, parse_activity_cursor)?;
+            queries::get_fungible_asset_activities(
+                &context.pool,
+                &standardize_address(&address),
+                cursor,
+                context.page_limit(&params),
+            )
+            .await
+            .map_err(ApiError::Internal)
+        }
+        .await,
+    )
+}
+
+async fn token_activities(address: String, params: PageParams, context: ApiContext) -> Response {
+    into_response(
+        async {
+            let cursor = parse_cursor(&params, parse_activity_cursor)?;
+            queries::get_token_activities(
+                &context.pool,
+                &standardize_address(&address),
+                cursor,
+                context.page_limit(&params),
+            )
+            .await
+            .map_err(ApiError::Internal)
+        }
+        .await,
+    )
+}
+
+async fn balances(address: String, params: PageParams, context: ApiContext) -> Response {
+    into_response(
+        async {
+            let cursor = parse_cursor(&params, |cursor| {
+                let keys = cursor.expect_keys(1)?;
+                Ok(keys[0].clone())
+            })?;
+            queries::get_fungible_asset_balances(
+                &context.pool,
+                &standardize_address(&address),
+                cursor,
+                context.page_limit(&params),
+            )
+            .await
+            .map_err(ApiError::Internal)
+        }
+        .await,
+    )
+}
+
+async fn tokens(address: String, params: PageParams, context: ApiContext) -> Response {
+    into_response(
+        async {
+            let cursor = parse_cursor(&params, |cursor| {
+                let keys = cursor.expect_keys(3)?;
+                let property_version =
+                    BigDecimal::from_str(&keys[1]).context("Invalid property version in cursor")?;
+                Ok((keys[0].clone(), property_version, keys[2].clone()))
+            })?;
+            queries::get_token_ownerships(
+                &context.pool,
+                &standardize_address(&address),
+                cursor,
+                context.page_limit(&params),
+            )
+            .await
+            .map_err(ApiError::Internal)
+        }
+        .await,
+    )
+}
+
+async fn display_names(params: DisplayNamesParams, context: ApiContext) -> Response {
+    into_response(
+        async {
+            let mut addresses = vec![];
+            for address in params.addresses.split(',').map(str::trim) {
+                if address.is_empty() {
+                    continue;
+                }
+                let address = standardize_address(address);
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+            if addresses.len() as i64 > context.max_page_size {
+                return Err(ApiError::BadRequest(anyhow::anyhow!(
+                    "At most {} addresses can be resolved at once",
+                    context.max_page_size
+                )));
+            }
+            queries::get_display_names(&context.pool, addresses)
+                .await
+                .map_err(ApiError::Internal)
+        }
+        .await,
+    )
+}
+
+fn parse_activity_cursor(cursor: &Cursor) -> anyhow::Result<queries::ActivityCursor> {
+    let keys = cursor.expect_keys(2)?;
+    let event_index = keys[1]
+        .parse::<i64>()
+        .context("Invalid event index in cursor")?;
+    Ok((parse_version(&keys[0])?, event_index))
+}
+
+/// Parses the cursor query param, if any, into the typed cursor of the endpoint
+fn parse_cursor<T>(
+    params: &PageParams,
+    parse: impl Fn(&Cursor) -> anyhow::Result<T>,
+) -> Result<Option<T>, ApiError> {
+    params
+        .cursor
+        .as_deref()
+        .map(|cursor| cursor.parse::<Cursor>().and_then(|cursor| parse(&cursor)))
+        .transpose()
+        .map_err(ApiError::BadRequest)
+}
+
+fn into_response<T: Serialize>(result: Result<T, ApiError>) -> Response {
+    match result {
+        Ok(body) => warp::reply::json(&body).into_response(),
+        Err(ApiError::BadRequest(err)) => error_response(StatusCode::BAD_REQUEST, err.to_string()),
+        Err(ApiError::Internal(err)) => {
+            tracing::error!(error = ?err, "[Indexer API] Failed to serve request");
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        },
+    }
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": message })),
+        status,
+    )
+    .into_response()
+}
@@ -22,7 +22,11 @@ mod test {
     };
     use assert_json_diff::assert_json_eq;
     use diesel::pg::PgConnection;
-    use processor::processors::token_v2_processor::TokenV2ProcessorConfig;
+    use processor::processors::{
+        events_processor::EventsProcessorConfig,
+        fungible_asset_processor::FungibleAssetProcessorConfig,
+        token_v2_processor::TokenV2ProcessorConfig,
+    };
     use std::{collections::HashMap, fs, sync::Arc};
 
     #[tokio::test]
@@ -159,16 +163,22 @@ mod test {
     fn get_processor_configs() -> Vec<TestProcessorConfig> {
         vec![
             TestProcessorConfig {
-                config: processor::processors::ProcessorConfig::EventsProcessor,
+                config: processor::processors::ProcessorConfig::EventsProcessor(
+                    EventsProcessorConfig::default(),
+                ),
             },
             TestProcessorConfig {
-                config: processor::processors::ProcessorConfig::FungibleAssetProcessor,
+                config: processor::processors::ProcessorConfig::FungibleAssetProcessor(
+                    FungibleAssetProcessorConfig::default(),
+                ),
             },
             TestProcessorConfig {
                 config: processor::processors::ProcessorConfig::TokenV2Processor(
                     TokenV2ProcessorConfig {
                         query_retries: 3,
                         query_retry_delay_ms: 1000,
+                        webhook: None,
+                        spam_classifier: None,
                     },
                 ),
             },
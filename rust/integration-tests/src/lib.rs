@@ -2,10 +2,16 @@ use anyhow::Context;
 use aptos_protos::transaction::v1::Transaction;
 use diesel::{pg::PgConnection, sql_query, Connection, RunQueryDsl};
 use processor::{
-    processors::{ProcessorConfig, ProcessorTrait},
-    utils::database::{new_db_pool, run_pending_migrations},
-    worker::build_processor_for_testing,
+    processors::{Processor, ProcessorConfig, ProcessorTrait},
+    utils::{
+        batch_transaction,
+        database::{new_db_pool, run_pending_migrations, ArcDbPool},
+        table_checkpoints::Batch,
+        write_context::{self, WriteContext, WriteMode},
+    },
+    worker::{build_processor_for_testing, concurrent_processing_tasks},
 };
+use std::sync::Arc;
 use testcontainers::{
     core::{IntoContainerPort, WaitFor},
     runners::AsyncRunner,
@@ -21,6 +27,8 @@ mod scenarios_tests;
 pub struct TestContext {
     pub transaction_batches: Vec<Transaction>,
     postgres_container: ContainerAsync<GenericImage>,
+    transactional_batch_writes: bool,
+    concurrent_batches: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -52,9 +60,24 @@ impl TestContext {
         Ok(TestContext {
             transaction_batches,
             postgres_container,
+            transactional_batch_writes: false,
+            concurrent_batches: 1,
         })
     }
 
+    /// Writes each batch in a single transaction, like the worker with `transactional_batch_writes`
+    pub fn with_transactional_batch_writes(mut self) -> Self {
+        self.transactional_batch_writes = true;
+        self
+    }
+
+    /// Processes up to `concurrent_batches` transactions at the same time, like the worker's
+    /// processing tasks, unless the worker would process them one at a time
+    pub fn with_concurrent_batches(mut self, concurrent_batches: usize) -> Self {
+        self.concurrent_batches = concurrent_batches;
+        self
+    }
+
     async fn create_schema(&self) -> anyhow::Result<()> {
         let db_url = self.get_db_url().await;
         let mut conn = PgConnection::establish(&db_url)
@@ -110,23 +133,40 @@ impl TestContext {
         let db_pool = new_db_pool(&db_url, None).await.unwrap();
 
         self.create_schema().await?;
-        let processor =
-            build_processor_for_testing(processor_config.config.clone(), db_pool.clone());
+        let processor = Arc::new(build_processor_for_testing(
+            processor_config.config.clone(),
+            db_pool.clone(),
+        ));
+        let concurrent_batches = concurrent_processing_tasks(
+            Some(self.concurrent_batches),
+            false,
+            self.transactional_batch_writes,
+        );
 
         let mut last_version = None;
 
-        for txn in transactions.iter() {
-            let version = txn.version;
-            processor
-                .process_transactions(vec![txn.clone()], version, version, None)
-                .await?;
-            // }
+        for txns in transactions.chunks(concurrent_batches.max(1)) {
+            let mut batches = tokio::task::JoinSet::new();
+            for txn in txns.iter().cloned() {
+                batches.spawn(process_batch(
+                    processor.clone(),
+                    db_pool.clone(),
+                    txn,
+                    self.transactional_batch_writes,
+                ));
+            }
+            while let Some(result) = batches.join_next().await {
+                result??;
+            }
 
-            last_version = Some(version);
+            for txn in txns {
+                let version = txn.version;
+                last_version = Some(version);
 
-            // For DiffTest, run verification after each transaction
-            if matches!(test_type, TestType::Diff(_)) {
-                test_type.run_verification(&mut conn, &version.to_string(), &verification_f)?;
+                // For DiffTest, run verification after each transaction
+                if matches!(test_type, TestType::Diff(_)) {
+                    test_type.run_verification(&mut conn, &version.to_string(), &verification_f)?;
+                }
             }
         }
         // For ScenarioTest, use the last transaction version if needed
@@ -148,6 +188,40 @@ impl TestContext {
     }
 }
 
+/// Processes a single transaction as its own batch, the same way the worker does: in a single
+/// transaction when transactional batch writes are enabled
+async fn process_batch(
+    processor: Arc<Processor>,
+    db_pool: ArcDbPool,
+    txn: Transaction,
+    transactional_batch_writes: bool,
+) -> anyhow::Result<()> {
+    let version = txn.version;
+    batch_transaction::run(
+        transactional_batch_writes,
+        db_pool,
+        processor.name(),
+        version,
+        version,
+        None,
+        |connection| {
+            let batch = Batch {
+                processor_name: processor.name(),
+                start_version: version,
+                end_version: version,
+            };
+            let write_context = WriteContext::new(processor.name(), WriteMode::default())
+                .with_batch(batch, connection);
+            write_context::scope(
+                write_context,
+                processor.process_transactions(vec![txn], version, version, None),
+            )
+        },
+    )
+    .await?;
+    Ok(())
+}
+
 trait TestStrategy {
     fn verify(
         &self,
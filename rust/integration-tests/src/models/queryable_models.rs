@@ -28,6 +28,7 @@ pub struct Event {
     pub inserted_at: chrono::NaiveDateTime,
     pub event_index: i64,
     pub indexed_type: String,
+    pub decoded_data: Option<serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize, Queryable)]
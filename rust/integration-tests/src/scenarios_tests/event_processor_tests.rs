@@ -12,7 +12,7 @@ mod test {
         query_dsl::methods::{FilterDsl, SelectDsl},
         BoolExpressionMethods, ExpressionMethods, QueryResult, RunQueryDsl,
     };
-    use processor::schema::events::dsl::*;
+    use processor::{processors::events_processor::EventsProcessorConfig, schema::events::dsl::*};
 
     const FA_WITHDRAW_EVENT: &str = "0x1::fungible_asset::Withdraw";
     const FA_DEPOSIT_EVENT: &str = "0x1::fungible_asset::Deposit";
@@ -27,7 +27,9 @@ mod test {
             .await
             .unwrap();
         let processor_config = TestProcessorConfig {
-            config: processor::processors::ProcessorConfig::EventsProcessor,
+            config: processor::processors::ProcessorConfig::EventsProcessor(
+                EventsProcessorConfig::default(),
+            ),
         };
         let expected_transaction = test_context.transaction_batches[0].clone();
         let test_type = TestType::Scenario(ScenarioTest);
@@ -87,7 +89,9 @@ mod test {
             .unwrap();
         let expected_transaction = test_context.transaction_batches[0].clone();
         let processor_config = TestProcessorConfig {
-            config: processor::processors::ProcessorConfig::EventsProcessor,
+            config: processor::processors::ProcessorConfig::EventsProcessor(
+                EventsProcessorConfig::default(),
+            ),
         };
         let test_type = TestType::Scenario(ScenarioTest);
         assert!(test_context
@@ -128,7 +132,9 @@ mod test {
             .unwrap();
         let expected_transaction = test_context.transaction_batches[0].clone();
         let processor_config = TestProcessorConfig {
-            config: processor::processors::ProcessorConfig::EventsProcessor,
+            config: processor::processors::ProcessorConfig::EventsProcessor(
+                EventsProcessorConfig::default(),
+            ),
         };
         let test_type = TestType::Scenario(ScenarioTest);
         assert!(test_context
@@ -1 +1,2 @@
 pub mod event_processor_tests;
+pub mod nft_points_processor_tests;
@@ -0,0 +1,217 @@
+#[allow(clippy::needless_return)]
+#[cfg(test)]
+mod test {
+    use crate::{ScenarioTest, TestContext, TestProcessorConfig, TestType};
+    use aptos_protos::{
+        transaction::v1::{
+            transaction::TxnData, transaction_payload::Payload, EntryFunctionPayload, Transaction,
+            TransactionInfo, TransactionPayload, UserTransaction, UserTransactionRequest,
+        },
+        util::timestamp::Timestamp,
+    };
+    use bigdecimal::BigDecimal;
+    use diesel::{pg::PgConnection, QueryDsl, QueryResult, RunQueryDsl};
+    use processor::{
+        processors::nft_points_processor::NftPointsProcessorConfig,
+        schema::current_nft_points::dsl::*,
+    };
+
+    const POINTS_CONTRACT: &str = "0x7::points::add_points";
+    const OWNER: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+    // Test Case: Validate that current_nft_points sums every nft_points row of an owner.
+    // - Two batches award quest points to the same owner and a third awards another point type.
+    // - A call with unexpected arguments is skipped instead of stopping the processor.
+    #[tokio::test]
+    async fn test_nft_points_totals() {
+        let transactions = [
+            points_transaction(1, &["\"0x2\"", "\"Token #1\"", "\"10\"", "\"quest\""]),
+            points_transaction(2, &["\"0x2\"", "\"Token #2\"", "\"5\"", "\"quest\""]),
+            points_transaction(3, &["\"0x2\"", "\"Token #1\"", "\"7\"", "\"referral\""]),
+            points_transaction(4, &["\"0x2\"", "\"Token #1\""]),
+        ];
+        let test_context = TestContext::new(
+            &transactions
+                .iter()
+                .map(|txn| txn.as_slice())
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+        let processor_config = TestProcessorConfig {
+            config: processor::processors::ProcessorConfig::NftPointsProcessor(
+                NftPointsProcessorConfig {
+                    nft_points_contracts: vec![POINTS_CONTRACT.to_string()],
+                },
+            ),
+        };
+        let test_type = TestType::Scenario(ScenarioTest);
+
+        assert!(test_context
+            .run(
+                processor_config,
+                test_type,
+                move |conn: &mut PgConnection, _version: &str| {
+                    let totals = load_current_nft_points(conn).expect("Failed to load totals");
+                    assert_eq!(totals, vec![
+                        (
+                            OWNER.to_string(),
+                            "quest".to_string(),
+                            BigDecimal::from(15),
+                            2,
+                            2
+                        ),
+                        (
+                            OWNER.to_string(),
+                            "referral".to_string(),
+                            BigDecimal::from(7),
+                            1,
+                            3
+                        ),
+                    ]);
+                    Ok(())
+                }
+            )
+            .await
+            .is_ok());
+    }
+
+    // Test Case: Validate that the totals include the batch's own nft_points rows when the batch
+    // is written in a single transaction, where they aren't visible to other connections yet.
+    #[tokio::test]
+    async fn test_nft_points_totals_with_transactional_batch_writes() {
+        let transactions = [
+            points_transaction(1, &["\"0x2\"", "\"Token #1\"", "\"10\"", "\"quest\""]),
+            points_transaction(2, &["\"0x2\"", "\"Token #2\"", "\"5\"", "\"quest\""]),
+        ];
+        let test_context = TestContext::new(
+            &transactions
+                .iter()
+                .map(|txn| txn.as_slice())
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap()
+        .with_transactional_batch_writes();
+        let processor_config = TestProcessorConfig {
+            config: processor::processors::ProcessorConfig::NftPointsProcessor(
+                NftPointsProcessorConfig {
+                    nft_points_contracts: vec![POINTS_CONTRACT.to_string()],
+                },
+            ),
+        };
+        let test_type = TestType::Scenario(ScenarioTest);
+
+        assert!(test_context
+            .run(
+                processor_config,
+                test_type,
+                move |conn: &mut PgConnection, _version: &str| {
+                    let totals = load_current_nft_points(conn).expect("Failed to load totals");
+                    assert_eq!(totals, vec![(
+                        OWNER.to_string(),
+                        "quest".to_string(),
+                        BigDecimal::from(15),
+                        2,
+                        2
+                    )]);
+                    Ok(())
+                }
+            )
+            .await
+            .is_ok());
+    }
+
+    // Test Case: Validate that concurrent batches for the same owner don't undercount the totals
+    // with transactional batch writes, where each batch only sees the rows of committed batches.
+    #[tokio::test]
+    async fn test_nft_points_totals_with_concurrent_transactional_batches() {
+        let transactions = [
+            points_transaction(1, &["\"0x2\"", "\"Token #1\"", "\"10\"", "\"quest\""]),
+            points_transaction(2, &["\"0x2\"", "\"Token #2\"", "\"5\"", "\"quest\""]),
+        ];
+        let test_context = TestContext::new(
+            &transactions
+                .iter()
+                .map(|txn| txn.as_slice())
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap()
+        .with_transactional_batch_writes()
+        .with_concurrent_batches(2);
+        let processor_config = TestProcessorConfig {
+            config: processor::processors::ProcessorConfig::NftPointsProcessor(
+                NftPointsProcessorConfig {
+                    nft_points_contracts: vec![POINTS_CONTRACT.to_string()],
+                },
+            ),
+        };
+        let test_type = TestType::Scenario(ScenarioTest);
+
+        assert!(test_context
+            .run(
+                processor_config,
+                test_type,
+                move |conn: &mut PgConnection, _version: &str| {
+                    let totals = load_current_nft_points(conn).expect("Failed to load totals");
+                    assert_eq!(totals, vec![(
+                        OWNER.to_string(),
+                        "quest".to_string(),
+                        BigDecimal::from(15),
+                        2,
+                        2
+                    )]);
+                    Ok(())
+                }
+            )
+            .await
+            .is_ok());
+    }
+
+    /// A successful call to the points contract, serialized the way test transactions are stored
+    fn points_transaction(txn_version: u64, arguments: &[&str]) -> Vec<u8> {
+        let txn = Transaction {
+            version: txn_version,
+            timestamp: Some(Timestamp {
+                seconds: 1_700_000_000 + txn_version as i64,
+                nanos: 0,
+            }),
+            info: Some(TransactionInfo {
+                success: true,
+                ..TransactionInfo::default()
+            }),
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    payload: Some(TransactionPayload {
+                        payload: Some(Payload::EntryFunctionPayload(EntryFunctionPayload {
+                            entry_function_id_str: POINTS_CONTRACT.to_string(),
+                            arguments: arguments.iter().map(|arg| arg.to_string()).collect(),
+                            ..EntryFunctionPayload::default()
+                        })),
+                        ..TransactionPayload::default()
+                    }),
+                    ..UserTransactionRequest::default()
+                }),
+                ..UserTransaction::default()
+            })),
+            ..Transaction::default()
+        };
+        serde_json::to_vec(&txn).unwrap()
+    }
+
+    fn load_current_nft_points(
+        conn: &mut PgConnection,
+    ) -> QueryResult<Vec<(String, String, BigDecimal, i64, i64)>> {
+        current_nft_points
+            .select((
+                owner_address,
+                point_type,
+                total_amount,
+                num_transactions,
+                last_transaction_version,
+            ))
+            .order((owner_address, point_type))
+            .load::<(String, String, BigDecimal, i64, i64)>(conn)
+    }
+}
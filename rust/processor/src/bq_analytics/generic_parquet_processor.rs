@@ -1,6 +1,6 @@
 use super::ParquetProcessingResult;
 use crate::{
-    bq_analytics::gcs_handler::upload_parquet_to_gcs,
+    bq_analytics::object_store::{upload_parquet, ObjectStore},
     gap_detectors::ProcessingResult,
     utils::{
         counters::{PARQUET_HANDLER_CURRENT_BUFFER_SIZE, PARQUET_STRUCT_SIZE},
@@ -10,12 +10,13 @@ use crate::{
 use ahash::AHashMap;
 use allocative::Allocative;
 use anyhow::{Context, Result};
-use google_cloud_storage::client::Client as GCSClient;
 use parquet::{
+    basic::{Compression, ZstdLevel},
     file::{properties::WriterProperties, writer::SerializedFileWriter},
     record::RecordWriter,
     schema::types::Type,
 };
+use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, sync::Arc, time::Instant};
 use tokio::time::Duration;
 use tracing::{debug, error, info};
@@ -41,6 +42,45 @@ pub trait GetTimeStamp {
     fn get_timestamp(&self) -> chrono::NaiveDateTime;
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetCompression {
+    #[default]
+    Lz4,
+    Zstd,
+    Snappy,
+    Uncompressed,
+}
+
+/// Controls how the parquet files are laid out. The defaults match what the BigQuery
+/// loading scripts expect.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParquetFileConfig {
+    pub compression: ParquetCompression,
+    // Only used with zstd, defaults to the parquet crate's default level
+    pub compression_level: Option<i32>,
+    // Max rows per row group. If unset, each file is a single row group
+    pub max_row_group_size: Option<usize>,
+    // Write files under `<table>/date=YYYY-MM-DD/`, based on the transaction timestamp, so
+    // tools like DuckDB can use hive partitioning. Files never span more than one day.
+    pub partition_by_date: bool,
+}
+
+impl ParquetFileConfig {
+    fn compression(&self) -> Result<Compression> {
+        Ok(match self.compression {
+            ParquetCompression::Lz4 => Compression::LZ4,
+            ParquetCompression::Zstd => Compression::ZSTD(match self.compression_level {
+                Some(level) => ZstdLevel::try_new(level).context("Invalid zstd level")?,
+                None => ZstdLevel::default(),
+            }),
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+        })
+    }
+}
+
 /// Auto-implement this for all types that implement `Default` and `RecordWriter`
 impl<ParquetType> HasParquetSchema for ParquetType
 where
@@ -64,17 +104,20 @@ where
     pub buffer_size_bytes: usize,
 
     pub transaction_version_to_struct_count: AHashMap<i64, i64>,
-    pub bucket_name: String,
     pub bucket_root: String,
     pub gap_detector_sender: kanal::AsyncSender<ProcessingResult>,
     pub upload_interval: Duration,
     pub max_buffer_size: usize,
     pub last_upload_time: Instant,
     pub processor_name: String,
+    pub file_config: ParquetFileConfig,
 }
-fn create_new_writer(schema: Arc<Type>) -> Result<SerializedFileWriter<Vec<u8>>> {
+fn create_new_writer(
+    schema: Arc<Type>,
+    file_config: &ParquetFileConfig,
+) -> Result<SerializedFileWriter<Vec<u8>>> {
     let props = WriterProperties::builder()
-        .set_compression(parquet::basic::Compression::LZ4)
+        .set_compression(file_config.compression()?)
         .build();
     let props_arc = Arc::new(props);
 
@@ -87,7 +130,7 @@ where
     for<'a> &'a [ParquetType]: RecordWriter<ParquetType>,
 {
     fn create_new_writer(&self) -> Result<SerializedFileWriter<Vec<u8>>> {
-        create_new_writer(self.schema.clone())
+        create_new_writer(self.schema.clone(), &self.file_config)
     }
 
     fn close_writer(&mut self) -> Result<SerializedFileWriter<Vec<u8>>> {
@@ -97,23 +140,22 @@ where
     }
 
     pub fn new(
-        bucket_name: String,
         bucket_root: String,
         gap_detector_sender: kanal::AsyncSender<ProcessingResult>,
         schema: Arc<Type>,
         upload_interval: Duration,
         max_buffer_size: usize,
         processor_name: String,
+        file_config: ParquetFileConfig,
     ) -> Result<Self> {
         // had to append unique id to avoid concurrent write issues
-        let writer = create_new_writer(schema.clone())?;
+        let writer = create_new_writer(schema.clone(), &file_config)?;
 
         Ok(Self {
             writer,
             buffer: Vec::new(),
             buffer_size_bytes: 0,
             transaction_version_to_struct_count: AHashMap::new(),
-            bucket_name,
             bucket_root,
             gap_detector_sender,
             schema,
@@ -121,12 +163,13 @@ where
             max_buffer_size,
             last_upload_time: Instant::now(),
             processor_name,
+            file_config,
         })
     }
 
     pub async fn handle(
         &mut self,
-        gcs_client: &GCSClient,
+        object_store: &dyn ObjectStore,
         changes: ParquetDataGeneric<ParquetType>,
     ) -> Result<()> {
         let parquet_structs = changes.data;
@@ -138,7 +181,7 @@ where
                 self.upload_interval.as_secs(),
                 ParquetType::TABLE_NAME
             );
-            if let Err(e) = self.upload_buffer(gcs_client).await {
+            if let Err(e) = self.upload_buffer(object_store).await {
                 error!("Failed to upload buffer: {}", e);
                 return Err(e);
             }
@@ -146,6 +189,22 @@ where
         }
 
         for parquet_struct in parquet_structs {
+            if self.file_config.partition_by_date
+                && self.buffer.first().is_some_and(|first| {
+                    first.get_timestamp().date() != parquet_struct.get_timestamp().date()
+                })
+            {
+                debug!(
+                    table_name = ParquetType::TABLE_NAME,
+                    "Date partition changed, uploading to object store."
+                );
+                if let Err(e) = self.upload_buffer(object_store).await {
+                    error!("Failed to upload buffer: {}", e);
+                    return Err(e);
+                }
+                self.last_upload_time = Instant::now();
+            }
+
             let size_of_struct = allocative::size_of_unique(&parquet_struct);
             PARQUET_STRUCT_SIZE
                 .with_label_values(&[&processor_name, ParquetType::TABLE_NAME])
@@ -158,9 +217,9 @@ where
                     table_name = ParquetType::TABLE_NAME,
                     buffer_size = self.buffer_size_bytes,
                     max_buffer_size = self.max_buffer_size,
-                    "Max buffer size reached, uploading to GCS."
+                    "Max buffer size reached, uploading to object store."
                 );
-                if let Err(e) = self.upload_buffer(gcs_client).await {
+                if let Err(e) = self.upload_buffer(object_store).await {
                     error!("Failed to upload buffer: {}", e);
                     return Err(e);
                 }
@@ -175,7 +234,14 @@ where
         Ok(())
     }
 
-    async fn upload_buffer(&mut self, gcs_client: &GCSClient) -> Result<()> {
+    /// Uploads the buffer whatever its size, e.g. before the processor shuts down
+    pub async fn flush(&mut self, object_store: &dyn ObjectStore) -> Result<()> {
+        self.upload_buffer(object_store).await?;
+        self.last_upload_time = Instant::now();
+        Ok(())
+    }
+
+    async fn upload_buffer(&mut self, object_store: &dyn ObjectStore) -> Result<()> {
         // This is to cover the case when interval duration has passed but buffer is empty
         if self.buffer.is_empty() {
             debug!("Buffer is empty, skipping upload.");
@@ -209,36 +275,49 @@ where
             .context("Buffer is not empty but has no last element")?;
         let end_version = last.version();
         let last_transaction_timestamp = naive_datetime_to_timestamp(last.get_timestamp());
+        let mut object_prefix = PathBuf::from(&self.bucket_root).join(ParquetType::TABLE_NAME);
+        if self.file_config.partition_by_date {
+            let first_timestamp = self.buffer[0].get_timestamp();
+            object_prefix =
+                object_prefix.join(format!("date={}", first_timestamp.format("%Y-%m-%d")));
+        }
 
         let parquet_processed_transactions = build_parquet_processed_transactions(&self.buffer);
+        let row_count = self.buffer.len();
         let struct_buffer = std::mem::take(&mut self.buffer);
 
-        let mut row_group_writer = self
-            .writer
-            .next_row_group()
-            .context("Failed to get row group")?;
-
-        struct_buffer
-            .as_slice()
-            .write_to_row_group(&mut row_group_writer)
-            .context("Failed to write to row group")?;
-        row_group_writer
-            .close()
-            .context("Failed to close row group")?;
+        let row_group_size = self
+            .file_config
+            .max_row_group_size
+            .unwrap_or(struct_buffer.len())
+            .max(1);
+        for row_group in struct_buffer.chunks(row_group_size) {
+            let mut row_group_writer = self
+                .writer
+                .next_row_group()
+                .context("Failed to get row group")?;
+
+            row_group
+                .write_to_row_group(&mut row_group_writer)
+                .context("Failed to write to row group")?;
+            row_group_writer
+                .close()
+                .context("Failed to close row group")?;
+        }
 
         let old_writer = self.close_writer().context("Failed to close writer")?;
         let upload_buffer = old_writer
             .into_inner()
             .context("Failed to get inner buffer")?;
 
-        let bucket_root = PathBuf::from(&self.bucket_root);
-
-        upload_parquet_to_gcs(
-            gcs_client,
+        upload_parquet(
+            object_store,
             upload_buffer,
             ParquetType::TABLE_NAME,
-            &self.bucket_name,
-            &bucket_root,
+            &object_prefix,
+            start_version,
+            end_version,
+            row_count,
             self.processor_name.clone(),
         )
         .await?;
@@ -257,7 +336,7 @@ where
             table_name = ParquetType::TABLE_NAME,
             start_version = start_version,
             end_version = end_version,
-            "Uploaded parquet to GCS and sending result to gap detector."
+            "Uploaded parquet to object store and sending result to gap detector."
         );
         self.gap_detector_sender
             .send(ProcessingResult::ParquetProcessingResult(
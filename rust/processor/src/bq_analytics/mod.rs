@@ -1,28 +1,26 @@
 pub mod gcs_handler;
 pub mod generic_parquet_processor;
+pub mod object_store;
 
 use crate::{
-    bq_analytics::generic_parquet_processor::{
-        GetTimeStamp, HasParquetSchema, HasVersion, NamedTable, ParquetDataGeneric,
-        ParquetHandler as GenericParquetHandler,
+    bq_analytics::{
+        generic_parquet_processor::{
+            GetTimeStamp, HasParquetSchema, HasVersion, NamedTable, ParquetDataGeneric,
+            ParquetFileConfig, ParquetHandler as GenericParquetHandler,
+        },
+        object_store::ObjectStoreConfig,
     },
     gap_detectors::ProcessingResult,
     worker::PROCESSOR_SERVICE_TYPE,
 };
 use ahash::AHashMap;
 use allocative::Allocative;
-use google_cloud_storage::{
-    client::{Client as GCSClient, ClientConfig as GcsClientConfig},
-    http::Error as StorageError,
-};
+use google_cloud_storage::http::Error as StorageError;
 use kanal::AsyncSender;
 use parquet::record::RecordWriter;
 use serde::{Deserialize, Serialize};
-use std::{
-    fmt::{Debug, Display, Formatter, Result as FormatResult},
-    sync::Arc,
-};
-use tokio::{io, time::Duration};
+use std::fmt::{Debug, Display, Formatter, Result as FormatResult};
+use tokio::{io, sync::oneshot, time::Duration};
 use tracing::{debug, error, info};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -87,15 +85,65 @@ impl From<parquet::errors::ParquetError> for ParquetProcessorError {
     }
 }
 
+enum ParquetHandlerMessage<ParquetType> {
+    Structs(ParquetDataGeneric<ParquetType>),
+    // Uploads the buffer and reports back once it's done
+    Flush(oneshot::Sender<anyhow::Result<()>>),
+}
+
+/// Sends structs to a parquet handler loop
+pub struct ParquetHandlerSender<ParquetType> {
+    sender: AsyncSender<ParquetHandlerMessage<ParquetType>>,
+}
+
+impl<ParquetType> Clone for ParquetHandlerSender<ParquetType> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<ParquetType> ParquetHandlerSender<ParquetType> {
+    pub async fn send(
+        &self,
+        structs: ParquetDataGeneric<ParquetType>,
+    ) -> Result<(), kanal::SendError> {
+        self.sender
+            .send(ParquetHandlerMessage::Structs(structs))
+            .await
+    }
+
+    /// Uploads what the handler has buffered so far. Structs sent before are uploaded too, and
+    /// the upload has been reported to the gap detector once this returns.
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let (done_sender, done_receiver) = oneshot::channel();
+        self.sender
+            .send(ParquetHandlerMessage::Flush(done_sender))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send to parquet manager: {}", e))?;
+        done_receiver
+            .await
+            .map_err(|e| anyhow::anyhow!("Parquet manager stopped before flushing: {}", e))?
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.sender.capacity()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn create_parquet_handler_loop<ParquetType>(
     new_gap_detector_sender: AsyncSender<ProcessingResult>,
     processor_name: &str,
     bucket_name: String,
     bucket_root: String,
+    object_store_config: ObjectStoreConfig,
+    file_config: ParquetFileConfig,
     parquet_handler_response_channel_size: usize,
     max_buffer_size: usize,
     upload_interval: Duration,
-) -> AsyncSender<ParquetDataGeneric<ParquetType>>
+) -> ParquetHandlerSender<ParquetType>
 where
     ParquetType: GetTimeStamp
         + HasVersion
@@ -108,9 +156,9 @@ where
     for<'a> &'a [ParquetType]: RecordWriter<ParquetType>,
 {
     let processor_name = processor_name.to_owned();
-    let (parquet_sender, parquet_receiver) = kanal::bounded_async::<ParquetDataGeneric<ParquetType>>(
-        parquet_handler_response_channel_size,
-    );
+    let (parquet_sender, parquet_receiver) = kanal::bounded_async::<
+        ParquetHandlerMessage<ParquetType>,
+    >(parquet_handler_response_channel_size);
 
     debug!(
         processor_name = processor_name.clone(),
@@ -119,27 +167,28 @@ where
     );
 
     let mut parquet_handler = GenericParquetHandler::new(
-        bucket_name.clone(),
         bucket_root.clone(),
         new_gap_detector_sender.clone(),
         ParquetType::schema(),
         upload_interval,
         max_buffer_size,
         processor_name.clone(),
+        file_config,
     )
     .expect("Failed to create parquet manager");
 
     tokio::spawn(async move {
-        let gcs_config = GcsClientConfig::default()
-            .with_auth()
+        let object_store = object_store_config
+            .build(bucket_name)
             .await
-            .expect("Failed to create GCS client config");
-        let gcs_client = Arc::new(GCSClient::new(gcs_config));
+            .expect("Failed to create object store client");
 
         loop {
             match parquet_receiver.recv().await {
-                Ok(txn_pb_res) => {
-                    let result = parquet_handler.handle(&gcs_client, txn_pb_res).await;
+                Ok(ParquetHandlerMessage::Structs(txn_pb_res)) => {
+                    let result = parquet_handler
+                        .handle(object_store.as_ref(), txn_pb_res)
+                        .await;
 
                     match result {
                         Ok(_) => {
@@ -160,17 +209,26 @@ where
                         },
                     }
                 },
+                Ok(ParquetHandlerMessage::Flush(done_sender)) => {
+                    let result = parquet_handler.flush(object_store.as_ref()).await;
+                    // The processor may have stopped waiting, nothing else to do then
+                    let _ = done_sender.send(result);
+                },
                 Err(e) => {
-                    error!(
+                    // Every sender is gone, i.e. the processor has stopped
+                    info!(
                         processor_name = processor_name.clone(),
                         service_type = PROCESSOR_SERVICE_TYPE,
-                        "[Parquet Handler] Error receiving parquet files: {:?}",
+                        "[Parquet Handler] Channel closed, stopping parquet handler loop: {:?}",
                         e
                     );
+                    break;
                 },
             }
         }
     });
 
-    parquet_sender
+    ParquetHandlerSender {
+        sender: parquet_sender,
+    }
 }
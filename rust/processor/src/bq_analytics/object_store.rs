@@ -0,0 +1,349 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bq_analytics::{gcs_handler::GcsObjectStore, ParquetProcessorError},
+    utils::counters::PARQUET_BUFFER_SIZE,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path as ObjectPath,
+    ObjectStore as _, PutPayload,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{debug, error, info};
+use url::Url;
+
+const MAX_RETRIES: usize = 3;
+const INITIAL_DELAY_MS: u64 = 500;
+const TIMEOUT_SECONDS: u64 = 300;
+
+/// Selects where the parquet processors upload their files. Defaults to GCS so existing
+/// configs keep working.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectStoreConfig {
+    #[default]
+    Gcs,
+    S3(S3ObjectStoreConfig),
+    LocalFileSystem(LocalObjectStoreConfig),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct S3ObjectStoreConfig {
+    // e.g. https://s3.us-west-2.amazonaws.com or http://localhost:9000 for MinIO
+    pub endpoint: Url,
+    pub region: String,
+    // Falls back to the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY environment variables
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalObjectStoreConfig {
+    // Directory that plays the role of the bucket
+    pub path: PathBuf,
+}
+
+impl ObjectStoreConfig {
+    pub async fn build(&self, bucket_name: String) -> Result<Arc<dyn ObjectStore>> {
+        Ok(match self {
+            ObjectStoreConfig::Gcs => Arc::new(GcsObjectStore::new(bucket_name).await?),
+            ObjectStoreConfig::S3(config) => {
+                Arc::new(S3ObjectStore::new(config.clone(), bucket_name)?)
+            },
+            ObjectStoreConfig::LocalFileSystem(config) => {
+                Arc::new(LocalObjectStore::new(config.path.clone()))
+            },
+        })
+    }
+}
+
+/// Minimal interface the parquet handlers need from a storage backend.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Writes `data` to `object_name`, replacing any existing object with the same name.
+    async fn put_object(
+        &self,
+        object_name: &str,
+        data: Vec<u8>,
+    ) -> Result<(), ParquetProcessorError>;
+}
+
+pub struct LocalObjectStore {
+    root: PathBuf,
+}
+
+impl LocalObjectStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalObjectStore {
+    async fn put_object(
+        &self,
+        object_name: &str,
+        data: Vec<u8>,
+    ) -> Result<(), ParquetProcessorError> {
+        let path = self.root.join(object_name);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Write to a temporary file first so readers never see a partially written object
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+}
+
+/// S3-compatible backend (AWS, MinIO, ...) using path-style requests, so custom endpoints
+/// don't need per-bucket DNS.
+pub struct S3ObjectStore {
+    store: AmazonS3,
+}
+
+impl S3ObjectStore {
+    pub fn new(config: S3ObjectStoreConfig, bucket_name: String) -> Result<Self> {
+        let access_key_id = match config.access_key_id {
+            Some(key) => key,
+            None => std::env::var("AWS_ACCESS_KEY_ID").context("Missing S3 access key id")?,
+        };
+        let secret_access_key = match config.secret_access_key {
+            Some(key) => key,
+            None => {
+                std::env::var("AWS_SECRET_ACCESS_KEY").context("Missing S3 secret access key")?
+            },
+        };
+        let store = AmazonS3Builder::new()
+            .with_endpoint(config.endpoint.as_str().trim_end_matches('/'))
+            .with_region(config.region)
+            .with_bucket_name(bucket_name)
+            .with_access_key_id(access_key_id)
+            .with_secret_access_key(secret_access_key)
+            .with_virtual_hosted_style_request(false)
+            .with_allow_http(config.endpoint.scheme() == "http")
+            .build()
+            .context("Failed to build S3 client")?;
+        Ok(Self { store })
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put_object(
+        &self,
+        object_name: &str,
+        data: Vec<u8>,
+    ) -> Result<(), ParquetProcessorError> {
+        let location = ObjectPath::parse(object_name.trim_start_matches('/')).map_err(|e| {
+            ParquetProcessorError::Other(format!("Invalid S3 object name {}: {}", object_name, e))
+        })?;
+        self.store
+            .put(&location, PutPayload::from(data))
+            .await
+            .map_err(|e| ParquetProcessorError::Other(format!("S3 upload failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Describes a single uploaded parquet file. Written next to the file once it's been uploaded,
+/// so the presence of a manifest means the parquet file is complete.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ParquetManifest {
+    pub table_name: String,
+    pub file_name: String,
+    pub start_version: i64,
+    pub end_version: i64,
+    pub row_count: usize,
+    pub size_in_bytes: usize,
+    pub content_sha256: String,
+    pub processor_name: String,
+}
+
+/// Uploads a parquet file covering [start_version, end_version] along with its manifest under
+/// `object_prefix` (the bucket root joined with the table name and partition, if any),
+/// retrying with exponential backoff. Object names are derived from the version range and the
+/// content hash, so retrying the same upload overwrites the same objects instead of creating
+/// duplicates.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_parquet(
+    object_store: &dyn ObjectStore,
+    buffer: Vec<u8>,
+    table_name: &str,
+    object_prefix: &Path,
+    start_version: i64,
+    end_version: i64,
+    row_count: usize,
+    processor_name: String,
+) -> Result<ParquetManifest, ParquetProcessorError> {
+    if buffer.is_empty() {
+        error!("The file is empty and has no data to upload.",);
+        return Err(ParquetProcessorError::Other(
+            "The file is empty and has no data to upload.".to_string(),
+        ));
+    }
+
+    let content_sha256 = hex::encode(Sha256::digest(&buffer));
+    let file_name = object_name_to_string(&generate_parquet_file_path(
+        object_prefix,
+        start_version,
+        end_version,
+        &content_sha256,
+    ))?;
+    let manifest_name = object_name_to_string(&generate_manifest_file_path(
+        object_prefix,
+        start_version,
+        end_version,
+        &content_sha256,
+    ))?;
+    let manifest = ParquetManifest {
+        table_name: table_name.to_string(),
+        file_name: file_name.clone(),
+        start_version,
+        end_version,
+        row_count,
+        size_in_bytes: buffer.len(),
+        content_sha256,
+        processor_name: processor_name.clone(),
+    };
+    let manifest_buffer = serde_json::to_vec_pretty(&manifest).map_err(|e| {
+        ParquetProcessorError::Other(format!("Failed to serialize manifest: {}", e))
+    })?;
+
+    PARQUET_BUFFER_SIZE
+        .with_label_values(&[&processor_name, table_name])
+        .set(buffer.len() as i64);
+
+    put_object_with_retries(object_store, &file_name, buffer, table_name).await?;
+    put_object_with_retries(object_store, &manifest_name, manifest_buffer, table_name).await?;
+    Ok(manifest)
+}
+
+async fn put_object_with_retries(
+    object_store: &dyn ObjectStore,
+    file_name: &str,
+    buffer: Vec<u8>,
+    table_name: &str,
+) -> Result<(), ParquetProcessorError> {
+    let mut retry_count = 0;
+    let mut delay = INITIAL_DELAY_MS;
+
+    loop {
+        let upload_result = timeout(
+            Duration::from_secs(TIMEOUT_SECONDS),
+            object_store.put_object(file_name, buffer.clone()),
+        )
+        .await;
+
+        match upload_result {
+            Ok(Ok(())) => {
+                info!(
+                    table_name = table_name,
+                    file_name = file_name,
+                    "File uploaded successfully to object store",
+                );
+                return Ok(());
+            },
+            Ok(Err(e)) => {
+                error!("Failed to upload file to object store: {}", e);
+                if retry_count >= MAX_RETRIES {
+                    return Err(e);
+                }
+            },
+            Err(e) => {
+                error!("Upload timed out: {}", e);
+                if retry_count >= MAX_RETRIES {
+                    return Err(ParquetProcessorError::TimeoutError(e));
+                }
+            },
+        }
+
+        retry_count += 1;
+        sleep(Duration::from_millis(delay)).await;
+        delay *= 2;
+        debug!("Retrying upload operation. Retry count: {}", retry_count);
+    }
+}
+
+fn object_name_to_string(path: &Path) -> Result<String, ParquetProcessorError> {
+    path.to_str().map(|s| s.to_owned()).ok_or_else(|| {
+        ParquetProcessorError::Other(format!("Object name is not valid UTF-8: {:?}", path))
+    })
+}
+
+/// Versions are zero padded so that listing a table's prefix returns files in version order.
+/// Only the first 16 hex characters of the hash are used, which is plenty to tell apart two
+/// files covering the same range.
+fn parquet_file_stem(start_version: i64, end_version: i64, content_sha256: &str) -> String {
+    format!(
+        "{:020}_{:020}_{}",
+        start_version,
+        end_version,
+        &content_sha256[..16.min(content_sha256.len())]
+    )
+}
+
+fn generate_parquet_file_path(
+    object_prefix: &Path,
+    start_version: i64,
+    end_version: i64,
+    content_sha256: &str,
+) -> PathBuf {
+    object_prefix.join(format!(
+        "{}.parquet",
+        parquet_file_stem(start_version, end_version, content_sha256)
+    ))
+}
+
+fn generate_manifest_file_path(
+    object_prefix: &Path,
+    start_version: i64,
+    end_version: i64,
+    content_sha256: &str,
+) -> PathBuf {
+    object_prefix.join("manifests").join(format!(
+        "{}.json",
+        parquet_file_stem(start_version, end_version, content_sha256)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parquet_file_names_encode_version_range_and_hash() {
+        let hash = hex::encode(Sha256::digest(b"parquet"));
+        let prefix = Path::new("root/transactions");
+
+        let file = generate_parquet_file_path(prefix, 100, 2000, &hash);
+        assert_eq!(
+            file.to_str().unwrap(),
+            format!(
+                "root/transactions/00000000000000000100_00000000000000002000_{}.parquet",
+                &hash[..16]
+            )
+        );
+        let manifest = generate_manifest_file_path(prefix, 100, 2000, &hash);
+        assert_eq!(
+            manifest.to_str().unwrap(),
+            format!(
+                "root/transactions/manifests/00000000000000000100_00000000000000002000_{}.json",
+                &hash[..16]
+            )
+        );
+    }
+}
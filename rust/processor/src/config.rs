@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    gap_detectors::DEFAULT_GAP_DETECTION_BATCH_SIZE, processors::ProcessorConfig,
-    transaction_filter::TransactionFilter, worker::Worker,
+    gap_detectors::{gap_repair::GapRepairConfig, DEFAULT_GAP_DETECTION_BATCH_SIZE},
+    local_stream::LocalFileStreamConfig,
+    processors::ProcessorConfig,
+    transaction_filter::TransactionFilter,
+    utils::dry_run::DryRunConfig,
+    worker::Worker,
 };
 use ahash::AHashMap;
 use anyhow::{Context, Result};
@@ -19,6 +23,10 @@ pub const QUERY_DEFAULT_RETRY_DELAY_MS: u64 = 500;
 #[serde(deny_unknown_fields)]
 pub struct IndexerGrpcProcessorConfig {
     pub processor_config: ProcessorConfig,
+    // Extra processors that share this processor's transaction stream. Each one tracks its own
+    // processor_status and gap detector.
+    #[serde(default)]
+    pub additional_processor_configs: Vec<ProcessorConfig>,
     pub postgres_connection_string: String,
     // TODO: Add TLS support.
     pub indexer_grpc_data_service_address: Url,
@@ -39,6 +47,8 @@ pub struct IndexerGrpcProcessorConfig {
     // Maximum number of batches "missing" before we assume we have an issue with gaps and abort
     #[serde(default = "IndexerGrpcProcessorConfig::default_gap_detection_batch_size")]
     pub parquet_gap_detection_batch_size: u64,
+    // If set, gaps are re-fetched from the data service and reprocessed instead of only reported
+    pub gap_repair: Option<GapRepairConfig>,
     // Number of protobuff transactions to send per chunk to the processor tasks
     #[serde(default = "IndexerGrpcProcessorConfig::default_pb_channel_txn_chunk_size")]
     pub pb_channel_txn_chunk_size: usize,
@@ -52,9 +62,24 @@ pub struct IndexerGrpcProcessorConfig {
 
     #[serde(default)]
     pub transaction_filter: TransactionFilter,
+    // Where to read transactions from. Defaults to the GRPC data service
+    #[serde(default)]
+    pub transaction_source: TransactionSourceConfig,
     // String vector for deprecated tables to skip db writes
     #[serde(default)]
     pub deprecated_tables: HashSet<String>,
+    // If set, rows are captured and reported instead of written. Requires ending_version.
+    #[serde(default)]
+    pub dry_run: Option<DryRunConfig>,
+    // If set, re-processes [starting_version, ending_version] writing only the backfilled tables
+    #[serde(default)]
+    pub backfill: Option<BackfillConfig>,
+    // If set, Postgres processors record each table's progress in processor_table_status
+    #[serde(default)]
+    pub enable_table_checkpoints: bool,
+    // If set, all writes of a batch, and processor_status, are committed in a single transaction
+    #[serde(default)]
+    pub transactional_batch_writes: bool,
 }
 
 impl IndexerGrpcProcessorConfig {
@@ -87,6 +112,7 @@ impl RunnableConfig for IndexerGrpcProcessorConfig {
     async fn run(&self) -> Result<()> {
         let mut worker = Worker::new(
             self.processor_config.clone(),
+            self.additional_processor_configs.clone(),
             self.postgres_connection_string.clone(),
             self.indexer_grpc_data_service_address.clone(),
             self.grpc_http2_config.clone(),
@@ -97,12 +123,18 @@ impl RunnableConfig for IndexerGrpcProcessorConfig {
             self.db_pool_size,
             self.gap_detection_batch_size,
             self.parquet_gap_detection_batch_size,
+            self.gap_repair.clone(),
             self.pb_channel_txn_chunk_size,
             self.per_table_chunk_sizes.clone(),
             self.enable_verbose_logging,
             self.transaction_filter.clone(),
             self.grpc_response_item_timeout_in_secs,
             self.deprecated_tables.clone(),
+            self.transaction_source.clone(),
+            self.dry_run.clone(),
+            self.backfill.clone(),
+            self.enable_table_checkpoints,
+            self.transactional_batch_writes,
         )
         .await
         .context("Failed to build worker")?;
@@ -122,6 +154,28 @@ impl RunnableConfig for IndexerGrpcProcessorConfig {
     }
 }
 
+/// Source of the transaction batches fed to the processor tasks.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionSourceConfig {
+    /// Stream transactions from `indexer_grpc_data_service_address`.
+    #[default]
+    Grpc,
+    /// Replay transactions archived in a local directory, e.g. for offline backfills.
+    LocalFiles(LocalFileStreamConfig),
+}
+
+/// Re-runs the processor over a version range while only writing the given tables. Progress is
+/// tracked in backfill_status under the alias, so the live processor can keep running.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackfillConfig {
+    /// Identifies the backfill in backfill_status. Rerunning with the same alias resumes it.
+    pub backfill_alias: String,
+    /// Names of the tables to write, e.g. current_fungible_asset_balances.
+    pub tables: HashSet<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -0,0 +1,170 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::ans_lookup_v2::CurrentAnsPrimaryNameV2;
+use crate::{
+    db::common::models::token_v2_models::v2_token_utils::TokenStandard,
+    schema::address_display_names, utils::database::DbPoolConnection,
+};
+use ahash::AHashMap;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+type TokenName = String;
+type TokenStandardType = String;
+type RegisteredAddress = String;
+/// (token name, token standard) -> the address the name points at
+pub type NameTargets = AHashMap<(TokenName, TokenStandardType), Option<RegisteredAddress>>;
+
+/// Reverse resolution cache: the name an address displays as. Kept up to date by the ANS
+/// processor so that resolving many addresses is a single primary key lookup.
+#[derive(
+    Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize, PartialEq, Eq,
+)]
+#[diesel(primary_key(address))]
+#[diesel(table_name = address_display_names)]
+#[diesel(treat_none_as_null = true)]
+pub struct AddressDisplayName {
+    pub address: String,
+    pub domain: Option<String>,
+    pub subdomain: Option<String>,
+    pub token_name: Option<String>,
+    pub token_standard: Option<String>,
+    pub last_transaction_version: i64,
+}
+
+impl Ord for AddressDisplayName {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.address.cmp(&other.address)
+    }
+}
+
+impl PartialOrd for AddressDisplayName {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl AddressDisplayName {
+    /// An address displays as its primary name if the name is active and still points at the
+    /// address. v2 names take precedence over v1 names when an address has both. Names missing
+    /// from `name_targets` aren't indexed yet and are assumed to point at the address.
+    pub fn from_primary_names<'a>(
+        address: &str,
+        primary_names: impl IntoIterator<Item = &'a CurrentAnsPrimaryNameV2>,
+        name_targets: &NameTargets,
+    ) -> Self {
+        let v2 = TokenStandard::V2.to_string();
+        let mut last_transaction_version = 0;
+        let rank = |name: &CurrentAnsPrimaryNameV2| {
+            (name.token_standard == v2, name.last_transaction_version)
+        };
+        let mut display_name: Option<&CurrentAnsPrimaryNameV2> = None;
+        for primary_name in primary_names {
+            last_transaction_version =
+                last_transaction_version.max(primary_name.last_transaction_version);
+            let Some(token_name) = primary_name.token_name.as_ref() else {
+                continue;
+            };
+            let points_at_address = name_targets
+                .get(&(token_name.clone(), primary_name.token_standard.clone()))
+                .map_or(true, |target| target.as_deref() == Some(address));
+            if primary_name.is_deleted || !primary_name.is_active || !points_at_address {
+                continue;
+            }
+            if display_name.map_or(true, |current| rank(primary_name) > rank(current)) {
+                display_name = Some(primary_name);
+            }
+        }
+        Self {
+            address: address.to_string(),
+            domain: display_name.and_then(|name| name.domain.clone()),
+            subdomain: display_name.and_then(|name| name.subdomain.clone()),
+            token_name: display_name.and_then(|name| name.token_name.clone()),
+            token_standard: display_name.map(|name| name.token_standard.clone()),
+            last_transaction_version,
+        }
+    }
+}
+
+#[derive(Debug, Queryable)]
+pub struct AddressDisplayNameQuery {
+    pub address: String,
+    pub token_name: String,
+}
+
+impl AddressDisplayNameQuery {
+    /// Resolves many addresses to their display name in one query. Addresses must be
+    /// standardized; those without a display name are left out.
+    pub async fn resolve(
+        addresses: &[String],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<AHashMap<String, String>> {
+        let rows = address_display_names::table
+            .select((
+                address_display_names::address,
+                address_display_names::token_name.assume_not_null(),
+            ))
+            .filter(address_display_names::address.eq_any(addresses))
+            .filter(address_display_names::token_name.is_not_null())
+            .load::<Self>(conn)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.address, row.token_name))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn primary_name(
+        token_name: &str,
+        token_standard: &str,
+        version: i64,
+    ) -> CurrentAnsPrimaryNameV2 {
+        CurrentAnsPrimaryNameV2 {
+            registered_address: "0x1".to_string(),
+            token_standard: token_standard.to_string(),
+            domain: Some(token_name.trim_end_matches(".apt").to_string()),
+            subdomain: Some("".to_string()),
+            token_name: Some(token_name.to_string()),
+            is_deleted: false,
+            last_transaction_version: version,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_display_name_prefers_v2_names_pointing_at_the_address() {
+        let v1 = primary_name("alice.apt", "v1", 3);
+        let v2 = primary_name("bob.apt", "v2", 2);
+        let display_name =
+            AddressDisplayName::from_primary_names("0x1", [&v1, &v2], &AHashMap::new());
+        assert_eq!(display_name.token_name.as_deref(), Some("bob.apt"));
+        assert_eq!(display_name.last_transaction_version, 3);
+
+        // bob.apt now points somewhere else
+        let name_targets = NameTargets::from_iter([(
+            ("bob.apt".to_string(), "v2".to_string()),
+            Some("0x2".to_string()),
+        )]);
+        let display_name = AddressDisplayName::from_primary_names("0x1", [&v1, &v2], &name_targets);
+        assert_eq!(display_name.token_name.as_deref(), Some("alice.apt"));
+
+        let expired = CurrentAnsPrimaryNameV2 {
+            is_active: false,
+            ..v1
+        };
+        let display_name = AddressDisplayName::from_primary_names("0x1", [&expired], &name_targets);
+        assert_eq!(display_name.token_name, None);
+    }
+}
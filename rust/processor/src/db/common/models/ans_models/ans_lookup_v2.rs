@@ -7,18 +7,22 @@
 
 use super::{
     ans_lookup::{AnsLookup, AnsPrimaryName, CurrentAnsLookup, CurrentAnsPrimaryName},
-    ans_utils::{get_token_name, NameRecordV2, SetReverseLookupEvent, SubdomainExtV2},
+    ans_utils::{
+        get_lifecycle_status, get_token_name, NameRecordV2, SetReverseLookupEvent, SubdomainExtV2,
+        ANS_LIFECYCLE_ACTIVE, ANS_LIFECYCLE_IN_GRACE, SUBDOMAIN_POLICY_FOLLOWS_DOMAIN,
+    },
 };
 use crate::{
     db::common::models::token_v2_models::v2_token_utils::TokenStandard,
     schema::{
         ans_lookup_v2, ans_primary_name_v2, current_ans_lookup_v2, current_ans_primary_name_v2,
     },
-    utils::util::standardize_address,
+    utils::{database::DbPoolConnection, util::standardize_address},
 };
 use ahash::AHashMap;
 use aptos_protos::transaction::v1::{Event, WriteResource};
 use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
 use field_count::FieldCount;
 use serde::{Deserialize, Serialize};
 
@@ -28,9 +32,15 @@ type TokenStandardType = String;
 type RegisteredAddress = String;
 // PK of current_ans_lookup_v2
 type CurrentAnsLookupV2PK = (Domain, Subdomain, TokenStandardType);
+// PK of the domain's row in current_ans_lookup_v2, without the empty subdomain
+pub type DomainPK = (Domain, TokenStandardType);
 // PK of current_ans_primary_name
 type CurrentAnsPrimaryNameV2PK = (RegisteredAddress, TokenStandardType);
 
+const QUERY_CHUNK_SIZE: usize = 1000;
+// Names whose lifecycle status is advanced per batch, the rest are picked up by later batches
+const EXPIRATION_SWEEP_LIMIT: i64 = 10_000;
+
 #[derive(
     Clone,
     Default,
@@ -56,6 +66,24 @@ pub struct CurrentAnsLookupV2 {
     pub token_name: String,
     pub is_deleted: bool,
     pub subdomain_expiration_policy: Option<i64>,
+    pub lifecycle_status: String,
+    pub is_active: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
+#[diesel(table_name = current_ans_lookup_v2)]
+pub struct CurrentAnsLookupV2Query {
+    pub domain: String,
+    pub subdomain: String,
+    pub token_standard: String,
+    pub registered_address: Option<String>,
+    pub last_transaction_version: i64,
+    pub expiration_timestamp: chrono::NaiveDateTime,
+    pub token_name: Option<String>,
+    pub is_deleted: bool,
+    pub subdomain_expiration_policy: Option<i64>,
+    pub lifecycle_status: String,
+    pub is_active: bool,
 }
 
 #[derive(Clone, Default, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
@@ -98,6 +126,20 @@ pub struct CurrentAnsPrimaryNameV2 {
     pub token_name: Option<String>,
     pub is_deleted: bool,
     pub last_transaction_version: i64,
+    pub is_active: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
+#[diesel(table_name = current_ans_primary_name_v2)]
+pub struct CurrentAnsPrimaryNameV2Query {
+    pub registered_address: String,
+    pub token_standard: String,
+    pub domain: Option<String>,
+    pub subdomain: Option<String>,
+    pub token_name: Option<String>,
+    pub is_deleted: bool,
+    pub last_transaction_version: i64,
+    pub is_active: bool,
 }
 
 #[derive(Clone, Default, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
@@ -138,6 +180,47 @@ impl CurrentAnsLookupV2 {
         )
     }
 
+    pub fn domain_pk(&self) -> DomainPK {
+        (self.domain.clone(), self.token_standard.clone())
+    }
+
+    /// Subdomains whose expiration policy makes them expire with their domain, like the
+    /// `current_aptos_names` view shows them
+    pub fn follows_domain_expiration(&self) -> bool {
+        !self.subdomain.is_empty()
+            && self.subdomain_expiration_policy == Some(SUBDOMAIN_POLICY_FOLLOWS_DOMAIN)
+    }
+
+    /// When the name expires: its domain's expiration for subdomains following it, if the domain
+    /// is known, and its own otherwise
+    pub fn get_expiration_timestamp(
+        &self,
+        domain_expiration_timestamp: Option<chrono::NaiveDateTime>,
+    ) -> chrono::NaiveDateTime {
+        match domain_expiration_timestamp {
+            Some(domain_expiration_timestamp) if self.follows_domain_expiration() => {
+                domain_expiration_timestamp
+            },
+            _ => self.expiration_timestamp,
+        }
+    }
+
+    /// Sets the lifecycle status as of `now`. Only active names that aren't deleted are active.
+    pub fn set_lifecycle(
+        &mut self,
+        now: chrono::NaiveDateTime,
+        grace_period_secs: i64,
+        domain_expiration_timestamp: Option<chrono::NaiveDateTime>,
+    ) {
+        self.lifecycle_status = get_lifecycle_status(
+            self.get_expiration_timestamp(domain_expiration_timestamp),
+            now,
+            grace_period_secs,
+        )
+        .to_string();
+        self.is_active = !self.is_deleted && self.lifecycle_status == ANS_LIFECYCLE_ACTIVE;
+    }
+
     pub fn get_v2_from_v1(
         v1_current_ans_lookup: CurrentAnsLookup,
         v1_ans_lookup: AnsLookup,
@@ -153,6 +236,8 @@ impl CurrentAnsLookupV2 {
                 token_name: v1_current_ans_lookup.token_name,
                 is_deleted: v1_current_ans_lookup.is_deleted,
                 subdomain_expiration_policy: None,
+                lifecycle_status: ANS_LIFECYCLE_ACTIVE.to_string(),
+                is_active: !v1_current_ans_lookup.is_deleted,
             },
             AnsLookupV2 {
                 transaction_version: v1_ans_lookup.transaction_version,
@@ -204,6 +289,8 @@ impl CurrentAnsLookupV2 {
                     last_transaction_version: txn_version,
                     is_deleted: false,
                     subdomain_expiration_policy,
+                    lifecycle_status: ANS_LIFECYCLE_ACTIVE.to_string(),
+                    is_active: true,
                 },
                 AnsLookupV2 {
                     transaction_version: txn_version,
@@ -253,6 +340,7 @@ impl CurrentAnsPrimaryNameV2 {
                 token_name: v1_current_primary_name.token_name,
                 is_deleted: v1_current_primary_name.is_deleted,
                 last_transaction_version: v1_current_primary_name.last_transaction_version,
+                is_active: !v1_current_primary_name.is_deleted,
             },
             AnsPrimaryNameV2 {
                 transaction_version: v1_primary_name.transaction_version,
@@ -288,6 +376,7 @@ impl CurrentAnsPrimaryNameV2 {
                         token_name: None,
                         last_transaction_version: txn_version,
                         is_deleted: true,
+                        is_active: false,
                     },
                     AnsPrimaryNameV2 {
                         transaction_version: txn_version,
@@ -311,6 +400,7 @@ impl CurrentAnsPrimaryNameV2 {
                         token_name: Some(set_reverse_lookup_event.get_curr_token_name()),
                         last_transaction_version: txn_version,
                         is_deleted: false,
+                        is_active: true,
                     },
                     AnsPrimaryNameV2 {
                         transaction_version: txn_version,
@@ -328,3 +418,215 @@ impl CurrentAnsPrimaryNameV2 {
         Ok(None)
     }
 }
+
+impl CurrentAnsLookupV2Query {
+    /// Names whose lifecycle status is behind as of `now`: active names past their expiration
+    /// and names whose grace period is over. Subdomains following their domain's expiration are
+    /// left out, they move along with their domain.
+    pub async fn get_names_to_expire(
+        now: chrono::NaiveDateTime,
+        grace_period_secs: i64,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        use current_ans_lookup_v2::dsl::*;
+
+        let grace_period_start = now - chrono::Duration::seconds(grace_period_secs);
+        current_ans_lookup_v2
+            .select(Self::as_select())
+            .filter(is_deleted.eq(false))
+            .filter(
+                subdomain
+                    .eq("")
+                    .or(subdomain_expiration_policy.is_null())
+                    .or(subdomain_expiration_policy.ne(SUBDOMAIN_POLICY_FOLLOWS_DOMAIN)),
+            )
+            .filter(
+                lifecycle_status
+                    .eq(ANS_LIFECYCLE_ACTIVE)
+                    .and(expiration_timestamp.le(now))
+                    .or(lifecycle_status
+                        .eq(ANS_LIFECYCLE_IN_GRACE)
+                        .and(expiration_timestamp.le(grace_period_start))),
+            )
+            .order(expiration_timestamp)
+            .limit(EXPIRATION_SWEEP_LIMIT)
+            .load(conn)
+            .await
+    }
+
+    /// The rows of the domains themselves, i.e. without a subdomain
+    pub async fn get_domains(
+        domains: &[Domain],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut rows = vec![];
+        for domains in domains.chunks(QUERY_CHUNK_SIZE) {
+            rows.extend(
+                current_ans_lookup_v2::table
+                    .select(Self::as_select())
+                    .filter(current_ans_lookup_v2::domain.eq_any(domains))
+                    .filter(current_ans_lookup_v2::subdomain.eq(""))
+                    .load::<Self>(conn)
+                    .await?,
+            );
+        }
+        Ok(rows)
+    }
+
+    /// Subdomains of the domains that expire with their domain
+    pub async fn get_subdomains_following_domains(
+        domains: &[Domain],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut rows = vec![];
+        for domains in domains.chunks(QUERY_CHUNK_SIZE) {
+            rows.extend(
+                current_ans_lookup_v2::table
+                    .select(Self::as_select())
+                    .filter(current_ans_lookup_v2::domain.eq_any(domains))
+                    .filter(current_ans_lookup_v2::subdomain.ne(""))
+                    .filter(
+                        current_ans_lookup_v2::subdomain_expiration_policy
+                            .eq(SUBDOMAIN_POLICY_FOLLOWS_DOMAIN),
+                    )
+                    .filter(current_ans_lookup_v2::is_deleted.eq(false))
+                    .load::<Self>(conn)
+                    .await?,
+            );
+        }
+        Ok(rows)
+    }
+
+    pub async fn get_by_token_names(
+        token_names: &[String],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut rows = vec![];
+        for token_names in token_names.chunks(QUERY_CHUNK_SIZE) {
+            rows.extend(
+                current_ans_lookup_v2::table
+                    .select(Self::as_select())
+                    .filter(current_ans_lookup_v2::token_name.eq_any(token_names))
+                    .load::<Self>(conn)
+                    .await?,
+            );
+        }
+        Ok(rows)
+    }
+}
+
+impl From<CurrentAnsLookupV2Query> for CurrentAnsLookupV2 {
+    fn from(query: CurrentAnsLookupV2Query) -> Self {
+        Self {
+            domain: query.domain,
+            subdomain: query.subdomain,
+            token_standard: query.token_standard,
+            registered_address: query.registered_address,
+            last_transaction_version: query.last_transaction_version,
+            expiration_timestamp: query.expiration_timestamp,
+            token_name: query.token_name.unwrap_or_default(),
+            is_deleted: query.is_deleted,
+            subdomain_expiration_policy: query.subdomain_expiration_policy,
+            lifecycle_status: query.lifecycle_status,
+            is_active: query.is_active,
+        }
+    }
+}
+
+impl CurrentAnsPrimaryNameV2Query {
+    pub async fn get_by_token_names(
+        token_names: &[String],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut rows = vec![];
+        for token_names in token_names.chunks(QUERY_CHUNK_SIZE) {
+            rows.extend(
+                current_ans_primary_name_v2::table
+                    .select(Self::as_select())
+                    .filter(current_ans_primary_name_v2::token_name.eq_any(token_names))
+                    .filter(current_ans_primary_name_v2::is_deleted.eq(false))
+                    .load::<Self>(conn)
+                    .await?,
+            );
+        }
+        Ok(rows)
+    }
+
+    pub async fn get_by_addresses(
+        addresses: &[String],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut rows = vec![];
+        for addresses in addresses.chunks(QUERY_CHUNK_SIZE) {
+            rows.extend(
+                current_ans_primary_name_v2::table
+                    .select(Self::as_select())
+                    .filter(current_ans_primary_name_v2::registered_address.eq_any(addresses))
+                    .filter(current_ans_primary_name_v2::is_deleted.eq(false))
+                    .load::<Self>(conn)
+                    .await?,
+            );
+        }
+        Ok(rows)
+    }
+}
+
+impl From<CurrentAnsPrimaryNameV2Query> for CurrentAnsPrimaryNameV2 {
+    fn from(query: CurrentAnsPrimaryNameV2Query) -> Self {
+        Self {
+            registered_address: query.registered_address,
+            token_standard: query.token_standard,
+            domain: query.domain,
+            subdomain: query.subdomain,
+            token_name: query.token_name,
+            is_deleted: query.is_deleted,
+            last_transaction_version: query.last_transaction_version,
+            is_active: query.is_active,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::common::models::ans_models::ans_utils::ANS_LIFECYCLE_EXPIRED,
+        utils::util::parse_timestamp_secs,
+    };
+
+    fn name(subdomain: &str, subdomain_expiration_policy: Option<i64>) -> CurrentAnsLookupV2 {
+        CurrentAnsLookupV2 {
+            domain: "aptos".to_string(),
+            subdomain: subdomain.to_string(),
+            expiration_timestamp: parse_timestamp_secs(1_800_000_000, 0),
+            subdomain_expiration_policy,
+            ..CurrentAnsLookupV2::default()
+        }
+    }
+
+    #[test]
+    fn test_subdomain_follows_domain_expiration() {
+        let domain_expiration = parse_timestamp_secs(1_700_000_000, 0);
+        let now = domain_expiration + chrono::Duration::days(60);
+        let grace_period_secs = chrono::Duration::days(30).num_seconds();
+
+        let mut following = name("sub", Some(SUBDOMAIN_POLICY_FOLLOWS_DOMAIN));
+        following.set_lifecycle(now, grace_period_secs, Some(domain_expiration));
+        assert_eq!(following.lifecycle_status, ANS_LIFECYCLE_EXPIRED);
+        assert!(!following.is_active);
+
+        // Its own expiration only counts while the domain isn't known
+        following.set_lifecycle(now, grace_period_secs, None);
+        assert_eq!(following.lifecycle_status, ANS_LIFECYCLE_ACTIVE);
+
+        let mut independent = name("sub", Some(0));
+        independent.set_lifecycle(now, grace_period_secs, Some(domain_expiration));
+        assert_eq!(independent.lifecycle_status, ANS_LIFECYCLE_ACTIVE);
+        assert!(independent.is_active);
+
+        // Domains never follow anything, whatever their policy column says
+        let mut domain = name("", Some(SUBDOMAIN_POLICY_FOLLOWS_DOMAIN));
+        domain.set_lifecycle(now, grace_period_secs, Some(domain_expiration));
+        assert_eq!(domain.lifecycle_status, ANS_LIFECYCLE_ACTIVE);
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::{
+    ans_lookup_v2::CurrentAnsLookupV2,
+    ans_utils::{
+        get_token_name, V2AnsEvent, ANS_EVENT_EXPIRED, ANS_EVENT_REGISTERED, ANS_EVENT_RENEWED,
+        ANS_EVENT_TRANSFERRED,
+    },
+};
+use crate::{
+    db::common::models::token_v2_models::v2_token_utils::TokenStandard, schema::ans_name_events,
+};
+use aptos_protos::transaction::v1::Event;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle events of a name: registered, renewed, transferred (target address changed) and
+/// expired. Expired events are derived from time passing rather than from an on-chain event.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(transaction_version, domain, subdomain, token_standard, event_type))]
+#[diesel(table_name = ans_name_events)]
+#[diesel(treat_none_as_null = true)]
+pub struct AnsNameEvent {
+    pub transaction_version: i64,
+    pub domain: String,
+    pub subdomain: String,
+    pub token_standard: String,
+    pub token_name: String,
+    pub event_type: String,
+    pub registered_address: Option<String>,
+    pub expiration_timestamp: chrono::NaiveDateTime,
+    pub transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl AnsNameEvent {
+    pub fn from_event(
+        event: &Event,
+        ans_v2_contract_address: &str,
+        txn_version: i64,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Option<Self>> {
+        let (domain, subdomain, event_type, registered_address, expiration_timestamp) =
+            match V2AnsEvent::from_event(event, ans_v2_contract_address, txn_version)? {
+                Some(V2AnsEvent::RegisterNameEvent(inner)) => (
+                    inner.get_domain_trunc(),
+                    inner.get_subdomain_trunc(),
+                    ANS_EVENT_REGISTERED,
+                    inner.get_target_address(),
+                    inner.get_expiration_time(),
+                ),
+                Some(V2AnsEvent::RenewNameEvent(inner)) => (
+                    inner.get_domain_trunc(),
+                    inner.get_subdomain_trunc(),
+                    ANS_EVENT_RENEWED,
+                    inner.get_target_address(),
+                    inner.get_expiration_time(),
+                ),
+                Some(V2AnsEvent::SetTargetAddressEvent(inner)) => (
+                    inner.get_domain_trunc(),
+                    inner.get_subdomain_trunc(),
+                    ANS_EVENT_TRANSFERRED,
+                    inner.get_new_address(),
+                    inner.get_expiration_time(),
+                ),
+                _ => return Ok(None),
+            };
+        Ok(Some(Self {
+            transaction_version: txn_version,
+            token_name: get_token_name(&domain, &subdomain),
+            domain,
+            subdomain,
+            token_standard: TokenStandard::V2.to_string(),
+            event_type: event_type.to_string(),
+            registered_address,
+            expiration_timestamp,
+            transaction_timestamp: txn_timestamp,
+        }))
+    }
+
+    /// A name expiring is keyed by the version that set its expiration, so it's only recorded
+    /// once however many batches see it expired. It happens at `expiration_timestamp`, which is
+    /// the domain's for subdomains following their domain's expiration.
+    pub fn expired(
+        current_ans_lookup: &CurrentAnsLookupV2,
+        expiration_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            transaction_version: current_ans_lookup.last_transaction_version,
+            domain: current_ans_lookup.domain.clone(),
+            subdomain: current_ans_lookup.subdomain.clone(),
+            token_standard: current_ans_lookup.token_standard.clone(),
+            token_name: current_ans_lookup.token_name.clone(),
+            event_type: ANS_EVENT_EXPIRED.to_string(),
+            registered_address: current_ans_lookup.registered_address.clone(),
+            expiration_timestamp,
+            transaction_timestamp: expiration_timestamp,
+        }
+    }
+}
@@ -18,6 +18,34 @@ use serde::{Deserialize, Serialize};
 
 pub const DOMAIN_LENGTH: usize = 64;
 
+pub const ANS_LIFECYCLE_ACTIVE: &str = "active";
+pub const ANS_LIFECYCLE_IN_GRACE: &str = "in_grace";
+pub const ANS_LIFECYCLE_EXPIRED: &str = "expired";
+
+// Subdomains with this expiration policy expire with their domain instead of on their own
+pub const SUBDOMAIN_POLICY_FOLLOWS_DOMAIN: i64 = 1;
+
+pub const ANS_EVENT_REGISTERED: &str = "registered";
+pub const ANS_EVENT_RENEWED: &str = "renewed";
+pub const ANS_EVENT_TRANSFERRED: &str = "transferred";
+pub const ANS_EVENT_EXPIRED: &str = "expired";
+
+/// Where a name is in its lifecycle as of `now`, the timestamp of the latest processed
+/// transaction. After expiring, a name stays in grace for `grace_period_secs` before it's expired.
+pub fn get_lifecycle_status(
+    expiration_timestamp: chrono::NaiveDateTime,
+    now: chrono::NaiveDateTime,
+    grace_period_secs: i64,
+) -> &'static str {
+    if expiration_timestamp > now {
+        ANS_LIFECYCLE_ACTIVE
+    } else if expiration_timestamp + chrono::Duration::seconds(grace_period_secs) > now {
+        ANS_LIFECYCLE_IN_GRACE
+    } else {
+        ANS_LIFECYCLE_EXPIRED
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct OptionalString {
     vec: Vec<String>,
@@ -242,6 +270,30 @@ pub struct RenewNameEvent {
 }
 
 impl RenewNameEvent {
+    pub fn get_domain_trunc(&self) -> String {
+        truncate_str(self.domain_name.as_str(), DOMAIN_LENGTH)
+    }
+
+    pub fn get_subdomain_trunc(&self) -> String {
+        truncate_str(
+            self.subdomain_name
+                .get_string()
+                .unwrap_or_default()
+                .as_str(),
+            DOMAIN_LENGTH,
+        )
+    }
+
+    pub fn get_expiration_time(&self) -> chrono::NaiveDateTime {
+        parse_timestamp_secs(bigdecimal_to_u64(&self.expiration_time_secs), 0)
+    }
+
+    pub fn get_target_address(&self) -> Option<String> {
+        self.target_address
+            .get_string()
+            .map(|addr| standardize_address(&addr))
+    }
+
     pub fn from_event(
         event: &Event,
         ans_v2_contract_address: &str,
@@ -257,6 +309,77 @@ impl RenewNameEvent {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegisterNameEvent {
+    domain_name: String,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    expiration_time_secs: BigDecimal,
+    subdomain_name: OptionalString,
+    target_address: OptionalString,
+}
+
+impl RegisterNameEvent {
+    pub fn get_domain_trunc(&self) -> String {
+        truncate_str(self.domain_name.as_str(), DOMAIN_LENGTH)
+    }
+
+    pub fn get_subdomain_trunc(&self) -> String {
+        truncate_str(
+            self.subdomain_name
+                .get_string()
+                .unwrap_or_default()
+                .as_str(),
+            DOMAIN_LENGTH,
+        )
+    }
+
+    pub fn get_expiration_time(&self) -> chrono::NaiveDateTime {
+        parse_timestamp_secs(bigdecimal_to_u64(&self.expiration_time_secs), 0)
+    }
+
+    pub fn get_target_address(&self) -> Option<String> {
+        self.target_address
+            .get_string()
+            .map(|addr| standardize_address(&addr))
+    }
+}
+
+/// Emitted when the address a name points to changes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetTargetAddressEvent {
+    domain_name: String,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    expiration_time_secs: BigDecimal,
+    new_address: OptionalString,
+    subdomain_name: OptionalString,
+}
+
+impl SetTargetAddressEvent {
+    pub fn get_domain_trunc(&self) -> String {
+        truncate_str(self.domain_name.as_str(), DOMAIN_LENGTH)
+    }
+
+    pub fn get_subdomain_trunc(&self) -> String {
+        truncate_str(
+            self.subdomain_name
+                .get_string()
+                .unwrap_or_default()
+                .as_str(),
+            DOMAIN_LENGTH,
+        )
+    }
+
+    pub fn get_expiration_time(&self) -> chrono::NaiveDateTime {
+        parse_timestamp_secs(bigdecimal_to_u64(&self.expiration_time_secs), 0)
+    }
+
+    pub fn get_new_address(&self) -> Option<String> {
+        self.new_address
+            .get_string()
+            .map(|addr| standardize_address(&addr))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SetReverseLookupEvent {
     account_addr: String,
@@ -318,6 +441,8 @@ impl SetReverseLookupEvent {
 pub enum V2AnsEvent {
     SetReverseLookupEvent(SetReverseLookupEvent),
     RenewNameEvent(RenewNameEvent),
+    RegisterNameEvent(RegisterNameEvent),
+    SetTargetAddressEvent(SetTargetAddressEvent),
 }
 
 impl V2AnsEvent {
@@ -328,6 +453,14 @@ impl V2AnsEvent {
                 ans_v2_contract_address
             ),
             format!("{}::v2_1_domains::RenewNameEvent", ans_v2_contract_address),
+            format!(
+                "{}::v2_1_domains::RegisterNameEvent",
+                ans_v2_contract_address
+            ),
+            format!(
+                "{}::v2_1_domains::SetTargetAddressEvent",
+                ans_v2_contract_address
+            ),
         ]
         .contains(&event_type.to_string())
     }
@@ -356,6 +489,22 @@ impl V2AnsEvent {
             x if x == format!("{}::v2_1_domains::RenewNameEvent", ans_v2_contract_address) => {
                 serde_json::from_str(data).map(|inner| Some(Self::RenewNameEvent(inner)))
             },
+            x if x
+                == format!(
+                    "{}::v2_1_domains::RegisterNameEvent",
+                    ans_v2_contract_address
+                ) =>
+            {
+                serde_json::from_str(data).map(|inner| Some(Self::RegisterNameEvent(inner)))
+            },
+            x if x
+                == format!(
+                    "{}::v2_1_domains::SetTargetAddressEvent",
+                    ans_v2_contract_address
+                ) =>
+            {
+                serde_json::from_str(data).map(|inner| Some(Self::SetTargetAddressEvent(inner)))
+            },
             _ => Ok(None),
         }
         .context(format!(
@@ -366,3 +515,28 @@ impl V2AnsEvent {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_lifecycle_status() {
+        let expiration = parse_timestamp_secs(1_700_000_000, 0);
+        let day = chrono::Duration::days(1);
+        let grace_period_secs = 30 * day.num_seconds();
+
+        assert_eq!(
+            get_lifecycle_status(expiration, expiration - day, grace_period_secs),
+            ANS_LIFECYCLE_ACTIVE
+        );
+        assert_eq!(
+            get_lifecycle_status(expiration, expiration, grace_period_secs),
+            ANS_LIFECYCLE_IN_GRACE
+        );
+        assert_eq!(
+            get_lifecycle_status(expiration, expiration + day * 30, grace_period_secs),
+            ANS_LIFECYCLE_EXPIRED
+        );
+    }
+}
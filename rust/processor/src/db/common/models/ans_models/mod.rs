@@ -1,8 +1,10 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod address_display_names;
 pub mod ans_lookup;
 pub mod ans_lookup_v2;
+pub mod ans_name_events;
 pub mod ans_utils;
 
 // parquet models
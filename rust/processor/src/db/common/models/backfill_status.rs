@@ -0,0 +1,83 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+
+use crate::{schema::backfill_status, utils::database::DbPoolConnection};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+pub const BACKFILL_STATUS_IN_PROGRESS: &str = "in_progress";
+pub const BACKFILL_STATUS_COMPLETE: &str = "complete";
+
+/// A backfill re-runs a processor over [start_version, end_version]. Its progress is tracked
+/// under its alias so that the live processor's processor_status is left alone.
+#[derive(Clone, Debug)]
+pub struct BackfillJob {
+    pub backfill_alias: String,
+    pub start_version: u64,
+    pub end_version: u64,
+}
+
+#[derive(AsChangeset, Debug, Insertable)]
+#[diesel(table_name = backfill_status)]
+/// Only tracking the latest version successfully backfilled
+pub struct BackfillStatus {
+    pub backfill_alias: String,
+    pub processor: String,
+    pub status: String,
+    pub last_success_version: i64,
+    pub last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    pub backfill_start_version: i64,
+    pub backfill_end_version: i64,
+}
+
+impl BackfillStatus {
+    pub fn new(
+        backfill_job: &BackfillJob,
+        processor: &str,
+        last_success_version: u64,
+        last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    ) -> Self {
+        let status = if last_success_version >= backfill_job.end_version {
+            BACKFILL_STATUS_COMPLETE
+        } else {
+            BACKFILL_STATUS_IN_PROGRESS
+        };
+        Self {
+            backfill_alias: backfill_job.backfill_alias.clone(),
+            processor: processor.to_string(),
+            status: status.to_string(),
+            last_success_version: last_success_version as i64,
+            last_transaction_timestamp,
+            backfill_start_version: backfill_job.start_version as i64,
+            backfill_end_version: backfill_job.end_version as i64,
+        }
+    }
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = backfill_status)]
+pub struct BackfillStatusQuery {
+    pub backfill_alias: String,
+    pub processor: String,
+    pub status: String,
+    pub last_success_version: i64,
+    pub last_updated: chrono::NaiveDateTime,
+    pub last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    pub backfill_start_version: i64,
+    pub backfill_end_version: i64,
+}
+
+impl BackfillStatusQuery {
+    pub async fn get_by_alias(
+        backfill_alias: &str,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Option<Self>> {
+        backfill_status::table
+            .filter(backfill_status::backfill_alias.eq(backfill_alias))
+            .first::<Self>(conn)
+            .await
+            .optional()
+    }
+}
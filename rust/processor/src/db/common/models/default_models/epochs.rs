@@ -0,0 +1,146 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::block_metadata_transactions::BlockMetadataTransaction;
+use crate::{schema::epochs, utils::util::parse_timestamp};
+use ahash::AHashMap;
+use aptos_protos::transaction::v1::Transaction;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+/// The range of transactions and blocks in an epoch. A batch only sees part of an epoch, so
+/// rows are merged by keeping the earliest start and the latest end. The end of the current
+/// epoch moves forward as it goes on.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(epoch))]
+#[diesel(table_name = epochs)]
+pub struct Epoch {
+    pub epoch: i64,
+    pub start_version: i64,
+    pub end_version: i64,
+    pub start_timestamp: chrono::NaiveDateTime,
+    pub end_timestamp: chrono::NaiveDateTime,
+    pub start_block_height: i64,
+    pub end_block_height: i64,
+    // Highest consensus round of the epoch's blocks, rounds start over every epoch
+    pub last_round: i64,
+}
+
+impl Epoch {
+    pub fn from_transactions(
+        transactions: &[Transaction],
+        block_metadata_transactions: &[BlockMetadataTransaction],
+    ) -> Vec<Self> {
+        let mut epochs: AHashMap<i64, Self> = AHashMap::new();
+        for transaction in transactions {
+            let version = transaction.version as i64;
+            let timestamp = parse_timestamp(transaction.timestamp.as_ref().unwrap(), version);
+            let block_height = transaction.block_height as i64;
+            let epoch = epochs
+                .entry(transaction.epoch as i64)
+                .or_insert_with(|| Self {
+                    epoch: transaction.epoch as i64,
+                    start_version: version,
+                    end_version: version,
+                    start_timestamp: timestamp,
+                    end_timestamp: timestamp,
+                    start_block_height: block_height,
+                    end_block_height: block_height,
+                    last_round: 0,
+                });
+            // Transactions are in version order
+            epoch.end_version = version;
+            epoch.end_timestamp = timestamp;
+            epoch.end_block_height = block_height;
+        }
+        for block_metadata_transaction in block_metadata_transactions {
+            if let Some(epoch) = epochs.get_mut(&block_metadata_transaction.epoch) {
+                epoch.last_round = epoch.last_round.max(block_metadata_transaction.round);
+            }
+        }
+        let mut epochs = epochs.into_values().collect::<Vec<_>>();
+        epochs.sort_by_key(|epoch| epoch.epoch);
+        epochs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::util::timestamp::Timestamp;
+
+    fn transaction(version: u64, epoch: u64, block_height: u64) -> Transaction {
+        Transaction {
+            version,
+            epoch,
+            block_height,
+            timestamp: Some(Timestamp {
+                seconds: 1_700_000_000 + version as i64,
+                nanos: 0,
+            }),
+            ..Transaction::default()
+        }
+    }
+
+    fn block_metadata_transaction(
+        version: i64,
+        epoch: i64,
+        round: i64,
+    ) -> BlockMetadataTransaction {
+        BlockMetadataTransaction {
+            version,
+            block_height: 0,
+            id: String::new(),
+            round,
+            epoch,
+            previous_block_votes_bitvec: serde_json::Value::Null,
+            proposer: String::new(),
+            failed_proposer_indices: serde_json::Value::Null,
+            timestamp: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_epochs_span_their_transactions() {
+        let transactions = [
+            transaction(10, 5, 100),
+            transaction(11, 5, 100),
+            transaction(12, 5, 101),
+            transaction(13, 6, 102),
+        ];
+        let block_metadata_transactions = [
+            block_metadata_transaction(10, 5, 8),
+            block_metadata_transaction(12, 5, 9),
+            block_metadata_transaction(13, 6, 1),
+        ];
+        let epochs = Epoch::from_transactions(&transactions, &block_metadata_transactions);
+
+        assert_eq!(epochs.len(), 2);
+        let (ending, starting) = (&epochs[0], &epochs[1]);
+        assert_eq!(
+            (ending.epoch, ending.start_version, ending.end_version),
+            (5, 10, 12)
+        );
+        assert_eq!(
+            (
+                ending.start_block_height,
+                ending.end_block_height,
+                ending.last_round
+            ),
+            (100, 101, 9)
+        );
+        assert_eq!(
+            ending.end_timestamp - ending.start_timestamp,
+            chrono::Duration::seconds(2)
+        );
+        assert_eq!(
+            (starting.epoch, starting.start_version, starting.end_version),
+            (6, 13, 13)
+        );
+        assert_eq!(starting.last_round, 1);
+    }
+}
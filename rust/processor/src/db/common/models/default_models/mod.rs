@@ -2,10 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod block_metadata_transactions;
+pub mod epochs;
 pub mod move_modules;
 pub mod move_resources;
 pub mod move_tables;
 pub mod transactions;
+pub mod validator_epoch_stats;
 pub mod write_set_changes;
 
 // parquet models
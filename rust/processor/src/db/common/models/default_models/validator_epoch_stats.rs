@@ -0,0 +1,303 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::move_resources::MoveResource;
+use crate::{
+    schema::validator_epoch_stats,
+    utils::util::{deserialize_from_string, standardize_address},
+};
+use ahash::AHashMap;
+use anyhow::Context;
+use aptos_protos::transaction::v1::{
+    transaction::TxnData, write_set_change::Change, Transaction, WriteResource,
+};
+use bigdecimal::BigDecimal;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+const VALIDATOR_SET_TYPE: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000001::stake::ValidatorSet";
+const VALIDATOR_PERFORMANCE_TYPE: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000001::stake::ValidatorPerformance";
+
+type Epoch = i64;
+type ValidatorIndex = i64;
+pub type ValidatorEpochStatMap = AHashMap<(Epoch, ValidatorIndex), ValidatorEpochStat>;
+
+/// Proposals of a validator in an epoch, from 0x1::stake::ValidatorPerformance which the block
+/// prologue updates for every block. Counts only grow within an epoch, so rows are merged by
+/// keeping the highest counts. The performance is reset in the block that ends the epoch, so
+/// that block's own proposal isn't counted.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(epoch, validator_index))]
+#[diesel(table_name = validator_epoch_stats)]
+pub struct ValidatorEpochStat {
+    pub epoch: i64,
+    pub validator_index: i64,
+    // Only known for epochs that started after the processor did
+    pub validator_address: Option<String>,
+    pub voting_power: Option<BigDecimal>,
+    pub successful_proposals: i64,
+    pub failed_proposals: i64,
+    pub last_transaction_version: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ValidatorSetResource {
+    active_validators: Vec<ValidatorInfoResource>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ValidatorInfoResource {
+    addr: String,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    voting_power: BigDecimal,
+    config: ValidatorConfigResource,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ValidatorConfigResource {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    validator_index: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ValidatorPerformanceResource {
+    validators: Vec<IndividualValidatorPerformance>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IndividualValidatorPerformance {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    successful_proposals: u64,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    failed_proposals: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct NewEpochEvent {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    epoch: u64,
+}
+
+enum ValidatorResource {
+    ValidatorSet(ValidatorSetResource),
+    ValidatorPerformance(ValidatorPerformanceResource),
+}
+
+impl ValidatorResource {
+    fn from_write_resource(
+        write_resource: &WriteResource,
+        txn_version: i64,
+    ) -> anyhow::Result<Option<Self>> {
+        let type_str = MoveResource::get_outer_type_from_write_resource(write_resource);
+        let data = write_resource.data.as_str();
+        match type_str.as_str() {
+            VALIDATOR_SET_TYPE => {
+                serde_json::from_str(data).map(|inner| Some(Self::ValidatorSet(inner)))
+            },
+            VALIDATOR_PERFORMANCE_TYPE => {
+                serde_json::from_str(data).map(|inner| Some(Self::ValidatorPerformance(inner)))
+            },
+            _ => Ok(None),
+        }
+        .context(format!(
+            "version {} failed! failed to parse type {}, data {:?}",
+            txn_version, type_str, data
+        ))
+    }
+}
+
+impl ValidatorEpochStat {
+    pub fn from_transactions(transactions: &[Transaction]) -> anyhow::Result<Vec<Self>> {
+        let mut stats: ValidatorEpochStatMap = AHashMap::new();
+        for transaction in transactions {
+            let events = match transaction.txn_data.as_ref() {
+                Some(TxnData::BlockMetadata(inner)) => &inner.events,
+                Some(TxnData::User(inner)) => &inner.events,
+                Some(TxnData::Validator(inner)) => &inner.events,
+                _ => continue,
+            };
+            let txn_version = transaction.version as i64;
+            // Resources written by the transaction that ends an epoch are those of the next one
+            let mut epoch = transaction.epoch as i64;
+            for event in events {
+                if matches!(
+                    event.type_str.as_str(),
+                    "0x1::reconfiguration::NewEpochEvent" | "0x1::reconfiguration::NewEpoch"
+                ) {
+                    let new_epoch: NewEpochEvent =
+                        serde_json::from_str(&event.data).context(format!(
+                            "version {} failed! failed to parse type {}, data {:?}",
+                            txn_version, event.type_str, event.data
+                        ))?;
+                    epoch = new_epoch.epoch as i64;
+                }
+            }
+            let is_new_epoch = epoch != transaction.epoch as i64;
+
+            let changes = &transaction
+                .info
+                .as_ref()
+                .expect("Transaction info doesn't exist!")
+                .changes;
+            for wsc in changes {
+                let Some(Change::WriteResource(write_resource)) = wsc.change.as_ref() else {
+                    continue;
+                };
+                match ValidatorResource::from_write_resource(write_resource, txn_version)? {
+                    // Joining and leaving only change the pending validators mid epoch
+                    Some(ValidatorResource::ValidatorSet(inner)) if is_new_epoch => {
+                        for validator in inner.active_validators {
+                            let validator_index = validator.config.validator_index as i64;
+                            let stat = stats
+                                .entry((epoch, validator_index))
+                                .or_insert_with(|| Self::new(epoch, validator_index, txn_version));
+                            stat.validator_address = Some(standardize_address(&validator.addr));
+                            stat.voting_power = Some(validator.voting_power);
+                        }
+                    },
+                    Some(ValidatorResource::ValidatorPerformance(inner)) => {
+                        for (index, performance) in inner.validators.iter().enumerate() {
+                            let validator_index = index as i64;
+                            let stat = stats
+                                .entry((epoch, validator_index))
+                                .or_insert_with(|| Self::new(epoch, validator_index, txn_version));
+                            stat.successful_proposals = performance.successful_proposals as i64;
+                            stat.failed_proposals = performance.failed_proposals as i64;
+                            stat.last_transaction_version = txn_version;
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+        let mut stats = stats.into_values().collect::<Vec<_>>();
+        stats.sort_by_key(|stat| (stat.epoch, stat.validator_index));
+        Ok(stats)
+    }
+
+    fn new(epoch: i64, validator_index: i64, txn_version: i64) -> Self {
+        Self {
+            epoch,
+            validator_index,
+            validator_address: None,
+            voting_power: None,
+            successful_proposals: 0,
+            failed_proposals: 0,
+            last_transaction_version: txn_version,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{
+        BlockMetadataTransaction, Event, MoveStructTag, TransactionInfo, WriteSetChange,
+    };
+
+    fn write_resource(name: &str, data: &str) -> WriteSetChange {
+        WriteSetChange {
+            change: Some(Change::WriteResource(WriteResource {
+                r#type: Some(MoveStructTag {
+                    address: "0x1".to_string(),
+                    module: "stake".to_string(),
+                    name: name.to_string(),
+                    ..MoveStructTag::default()
+                }),
+                data: data.to_string(),
+                ..WriteResource::default()
+            })),
+            ..WriteSetChange::default()
+        }
+    }
+
+    fn block_metadata_transaction(
+        version: u64,
+        epoch: u64,
+        events: Vec<Event>,
+        changes: Vec<WriteSetChange>,
+    ) -> Transaction {
+        Transaction {
+            version,
+            epoch,
+            info: Some(TransactionInfo {
+                changes,
+                ..TransactionInfo::default()
+            }),
+            txn_data: Some(TxnData::BlockMetadata(BlockMetadataTransaction {
+                events,
+                ..BlockMetadataTransaction::default()
+            })),
+            ..Transaction::default()
+        }
+    }
+
+    fn performance(successful_proposals: u64, failed_proposals: u64) -> WriteSetChange {
+        write_resource(
+            "ValidatorPerformance",
+            &format!(
+                r#"{{"validators": [{{"successful_proposals": "{}", "failed_proposals": "{}"}}]}}"#,
+                successful_proposals, failed_proposals
+            ),
+        )
+    }
+
+    #[test]
+    fn test_reconfiguration_starts_the_new_epoch() {
+        let transactions = [
+            block_metadata_transaction(10, 5, vec![], vec![performance(2, 0)]),
+            block_metadata_transaction(11, 5, vec![], vec![performance(3, 1)]),
+            // Ends epoch 5: the performance is reset and the validator set is the new epoch's
+            block_metadata_transaction(
+                12,
+                5,
+                vec![Event {
+                    type_str: "0x1::reconfiguration::NewEpochEvent".to_string(),
+                    data: r#"{"epoch": "6"}"#.to_string(),
+                    ..Event::default()
+                }],
+                vec![
+                    write_resource(
+                        "ValidatorSet",
+                        r#"{"active_validators": [{"addr": "0xa", "voting_power": "100", "config": {"validator_index": "0"}}]}"#,
+                    ),
+                    performance(0, 0),
+                ],
+            ),
+        ];
+        let stats = ValidatorEpochStat::from_transactions(&transactions).unwrap();
+
+        assert_eq!(stats.len(), 2);
+        let (ending, starting) = (&stats[0], &stats[1]);
+        assert_eq!((ending.epoch, ending.validator_index), (5, 0));
+        assert_eq!(
+            (ending.successful_proposals, ending.failed_proposals),
+            (3, 1)
+        );
+        assert_eq!(ending.last_transaction_version, 11);
+        assert_eq!(ending.validator_address, None);
+
+        assert_eq!((starting.epoch, starting.validator_index), (6, 0));
+        assert_eq!(starting.validator_address, Some(standardize_address("0xa")));
+        assert_eq!(starting.voting_power, Some(BigDecimal::from(100)));
+        assert_eq!(
+            (starting.successful_proposals, starting.failed_proposals),
+            (0, 0)
+        );
+        assert_eq!(starting.last_transaction_version, 12);
+    }
+
+    #[test]
+    fn test_unexpected_resource_data_is_an_error() {
+        let transactions = [block_metadata_transaction(10, 5, vec![], vec![
+            write_resource("ValidatorPerformance", r#"{"validators": "none"}"#),
+        ])];
+        assert!(ValidatorEpochStat::from_transactions(&transactions).is_err());
+    }
+}
@@ -27,6 +27,8 @@ pub struct Event {
     pub data: serde_json::Value,
     pub event_index: i64,
     pub indexed_type: String,
+    // Filled in by the processor if ABI decoding is enabled
+    pub decoded_data: Option<serde_json::Value>,
 }
 
 impl Event {
@@ -49,6 +51,7 @@ impl Event {
             data: serde_json::from_str(event.data.as_str()).unwrap(),
             event_index,
             indexed_type: truncate_str(t, EVENT_TYPE_MAX_LENGTH),
+            decoded_data: None,
         }
     }
 
@@ -16,7 +16,7 @@ use crate::{
     },
     schema::{
         current_fungible_asset_balances, current_fungible_asset_balances_legacy,
-        fungible_asset_balances,
+        fungible_asset_balance_daily_snapshots, fungible_asset_balances,
     },
     utils::util::{
         hex_to_raw_bytes, sha3_256, standardize_address, APTOS_COIN_TYPE_STR,
@@ -176,6 +176,83 @@ pub struct CurrentUnifiedFungibleAssetBalance {
     pub last_transaction_timestamp_v2: Option<chrono::NaiveDateTime>,
 }
 
+/// Need a separate struct for queryable because the unified columns (amount, asset_type, etc.)
+/// are generated by the DB from the v1 and v2 columns
+#[derive(Clone, Debug, Deserialize, Identifiable, Queryable, Selectable, Serialize)]
+#[diesel(primary_key(storage_id))]
+#[diesel(table_name = current_fungible_asset_balances)]
+pub struct CurrentUnifiedFungibleAssetBalanceQuery {
+    pub storage_id: String,
+    pub owner_address: String,
+    pub asset_type: String,
+    pub token_standard: String,
+    pub is_primary: bool,
+    pub is_frozen: bool,
+    pub amount: BigDecimal,
+    pub last_transaction_version: Option<i64>,
+    pub last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+}
+
+/// End of day balance of a primary fungible store, keyed by (owner, asset type, date). A row is
+/// only written for days where the balance changed. Coin balances aren't included.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(owner_address, asset_type, snapshot_date))]
+#[diesel(table_name = fungible_asset_balance_daily_snapshots)]
+pub struct FungibleAssetBalanceDailySnapshot {
+    pub owner_address: String,
+    pub asset_type: String,
+    pub snapshot_date: chrono::NaiveDate,
+    pub storage_id: String,
+    pub amount: BigDecimal,
+    pub token_standard: String,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl FungibleAssetBalanceDailySnapshot {
+    /// Keeps the last primary fungible store balance per owner, asset type and day from a batch
+    /// of balances. Coin stores are skipped: a paired coin's balance is split between its coin
+    /// store and its primary fungible store, and only current_fungible_asset_balances combines
+    /// them. Results are sorted by primary key to avoid deadlocks on insert.
+    pub fn from_balances(balances: &[FungibleAssetBalance]) -> Vec<Self> {
+        let mut snapshots: AHashMap<(String, String, chrono::NaiveDate), Self> = AHashMap::new();
+        for balance in balances
+            .iter()
+            .filter(|b| b.is_primary && b.token_standard == *V2_STANDARD)
+        {
+            let snapshot_date = balance.transaction_timestamp.date();
+            let key = (
+                balance.owner_address.clone(),
+                balance.asset_type.clone(),
+                snapshot_date,
+            );
+            if snapshots.get(&key).map_or(true, |s| {
+                s.last_transaction_version <= balance.transaction_version
+            }) {
+                snapshots.insert(key, Self {
+                    owner_address: balance.owner_address.clone(),
+                    asset_type: balance.asset_type.clone(),
+                    snapshot_date,
+                    storage_id: balance.storage_id.clone(),
+                    amount: balance.amount.clone(),
+                    token_standard: balance.token_standard.clone(),
+                    last_transaction_version: balance.transaction_version,
+                    last_transaction_timestamp: balance.transaction_timestamp,
+                });
+            }
+        }
+        let mut snapshots = snapshots.into_values().collect::<Vec<_>>();
+        snapshots.sort_by(|a, b| {
+            (&a.owner_address, &a.asset_type, a.snapshot_date).cmp(&(
+                &b.owner_address,
+                &b.asset_type,
+                b.snapshot_date,
+            ))
+        });
+        snapshots
+    }
+}
+
 fn get_paired_metadata_address(coin_type_name: &str) -> String {
     if coin_type_name == APTOS_COIN_TYPE_STR {
         APT_METADATA_ADDRESS_HEX.clone()
@@ -427,6 +504,53 @@ impl FungibleAssetBalance {
 mod tests {
     use super::*;
 
+    fn balance(version: i64, timestamp: &str, amount: u64) -> FungibleAssetBalance {
+        FungibleAssetBalance {
+            transaction_version: version,
+            write_set_change_index: 0,
+            storage_id: "0x2".to_string(),
+            owner_address: "0x1".to_string(),
+            asset_type: APT_METADATA_ADDRESS_HEX.clone(),
+            is_primary: true,
+            is_frozen: false,
+            amount: BigDecimal::from(amount),
+            transaction_timestamp: chrono::NaiveDateTime::parse_from_str(
+                timestamp,
+                "%Y-%m-%d %H:%M:%S",
+            )
+            .unwrap(),
+            token_standard: TokenStandard::V2.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_daily_snapshots_keep_last_balance_of_each_day() {
+        let balances = vec![
+            balance(3, "2024-09-01 23:00:00", 30),
+            balance(1, "2024-09-01 01:00:00", 10),
+            balance(4, "2024-09-02 00:00:01", 40),
+            FungibleAssetBalance {
+                is_primary: false,
+                ..balance(5, "2024-09-02 10:00:00", 50)
+            },
+            // The paired coin's store
+            FungibleAssetBalance {
+                storage_id: "0x3".to_string(),
+                asset_type: APTOS_COIN_TYPE_STR.to_string(),
+                token_standard: TokenStandard::V1.to_string(),
+                ..balance(6, "2024-09-02 11:00:00", 60)
+            },
+        ];
+        let snapshots = FungibleAssetBalanceDailySnapshot::from_balances(&balances);
+
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].snapshot_date.to_string(), "2024-09-01");
+        assert_eq!(snapshots[0].amount, BigDecimal::from(30));
+        assert_eq!(snapshots[0].last_transaction_version, 3);
+        assert_eq!(snapshots[1].snapshot_date.to_string(), "2024-09-02");
+        assert_eq!(snapshots[1].amount, BigDecimal::from(40));
+    }
+
     #[test]
     fn test_is_primary() {
         let owner_address = "0xfd2984f201abdbf30ccd0ec5c2f2357789222c0bbd3c68999acfebe188fdc09d";
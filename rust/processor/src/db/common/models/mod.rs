@@ -3,6 +3,7 @@
 
 pub mod account_transaction_models;
 pub mod ans_models;
+pub mod backfill_status;
 pub mod coin_models;
 pub mod default_models;
 pub mod events_models;
@@ -10,7 +11,9 @@ pub mod fungible_asset_models;
 pub mod ledger_info;
 pub mod object_models;
 pub mod processor_status;
+pub mod processor_table_status;
 pub mod property_map;
+pub mod spam_asset_models;
 pub mod stake_models;
 pub mod token_models;
 pub mod token_v2_models;
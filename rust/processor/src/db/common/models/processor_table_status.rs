@@ -0,0 +1,38 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+
+use crate::{schema::processor_table_status, utils::database::DbPoolConnection};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+#[derive(AsChangeset, Debug, Insertable)]
+#[diesel(table_name = processor_table_status)]
+/// Latest version up to which a processor has written all of a table's rows
+pub struct ProcessorTableStatus {
+    pub processor: String,
+    pub table_name: String,
+    pub last_success_version: i64,
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = processor_table_status)]
+pub struct ProcessorTableStatusQuery {
+    pub processor: String,
+    pub table_name: String,
+    pub last_success_version: i64,
+    pub last_updated: chrono::NaiveDateTime,
+}
+
+impl ProcessorTableStatusQuery {
+    pub async fn get_by_processor(
+        processor_name: &str,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        processor_table_status::table
+            .filter(processor_table_status::processor.eq(processor_name))
+            .load::<Self>(conn)
+            .await
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use crate::{schema, schema::spam_assets};
+use ahash::{AHashMap, AHashSet};
+use diesel::{
+    pg::{upsert::excluded, Pg},
+    prelude::*,
+    query_builder::QueryFragment,
+};
+use field_count::FieldCount;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt};
+
+/// Why an asset was (or wasn't) classified as spam, stored in `spam_assets.reason`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SpamReason {
+    AllowList,
+    DenyList,
+    NamePattern,
+    SymbolPattern,
+    MassAirdrop,
+}
+
+impl fmt::Display for SpamReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let res = match self {
+            SpamReason::AllowList => "allow_list",
+            SpamReason::DenyList => "deny_list",
+            SpamReason::NamePattern => "name_pattern",
+            SpamReason::SymbolPattern => "symbol_pattern",
+            SpamReason::MassAirdrop => "mass_airdrop",
+        };
+        write!(f, "{}", res)
+    }
+}
+
+/// Rules used to flag spam assets. Assets are fungible asset types / coin types in the
+/// fungible asset processor and collection or token data ids in the token v2 processor.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpamClassifierConfig {
+    // Case insensitive regexes matched against asset, collection and token names
+    #[serde(default)]
+    pub name_patterns: Vec<String>,
+    // Case insensitive regexes matched against fungible asset symbols
+    #[serde(default)]
+    pub symbol_patterns: Vec<String>,
+    // Flag assets sent to at least this many distinct addresses in a single transaction
+    pub airdrop_min_recipients: Option<usize>,
+    // Never flagged, and unflagged if they were flagged before
+    #[serde(default)]
+    pub allow_list: HashSet<String>,
+    // Always flagged
+    #[serde(default)]
+    pub deny_list: HashSet<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(asset))]
+#[diesel(table_name = spam_assets)]
+pub struct SpamAsset {
+    pub asset: String,
+    pub is_spam: bool,
+    pub reason: String,
+    pub last_transaction_version: i64,
+}
+
+impl SpamAsset {
+    fn new(asset: &str, reason: SpamReason, txn_version: i64) -> Self {
+        Self {
+            asset: asset.to_string(),
+            is_spam: reason != SpamReason::AllowList,
+            reason: reason.to_string(),
+            last_transaction_version: txn_version,
+        }
+    }
+
+    /// Keeps the latest classification of each asset, sorted by asset to avoid deadlocks
+    /// when upserting.
+    pub fn dedupe(spam_assets: Vec<Self>) -> Vec<Self> {
+        let mut latest: AHashMap<String, Self> = AHashMap::new();
+        for spam_asset in spam_assets {
+            let is_stale = latest.get(&spam_asset.asset).is_some_and(|existing| {
+                existing.last_transaction_version >= spam_asset.last_transaction_version
+            });
+            if !is_stale {
+                latest.insert(spam_asset.asset.clone(), spam_asset);
+            }
+        }
+        let mut spam_assets = latest.into_values().collect::<Vec<_>>();
+        spam_assets.sort_by(|a, b| a.asset.cmp(&b.asset));
+        spam_assets
+    }
+}
+
+/// Shared by the processors that classify spam. Rows without a transaction version weren't
+/// written by a processor, e.g. curated by hand, and are never overwritten.
+pub fn insert_spam_assets_query(
+    items_to_insert: Vec<SpamAsset>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::spam_assets::dsl::*;
+
+    (
+        diesel::insert_into(schema::spam_assets::table)
+            .values(items_to_insert)
+            .on_conflict(asset)
+            .do_update()
+            .set((
+                is_spam.eq(excluded(is_spam)),
+                reason.eq(excluded(reason)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_updated.eq(diesel::dsl::now),
+            )),
+        Some(" WHERE spam_assets.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+pub struct SpamClassifier {
+    config: SpamClassifierConfig,
+    name_patterns: Vec<Regex>,
+    symbol_patterns: Vec<Regex>,
+}
+
+impl SpamClassifier {
+    pub fn new(config: SpamClassifierConfig) -> anyhow::Result<Self> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| RegexBuilder::new(pattern).case_insensitive(true).build())
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            name_patterns: compile(&config.name_patterns)?,
+            symbol_patterns: compile(&config.symbol_patterns)?,
+            config,
+        })
+    }
+
+    fn classify_by_list(&self, asset: &str, txn_version: i64) -> Option<SpamAsset> {
+        if self.config.allow_list.contains(asset) {
+            Some(SpamAsset::new(asset, SpamReason::AllowList, txn_version))
+        } else if self.config.deny_list.contains(asset) {
+            Some(SpamAsset::new(asset, SpamReason::DenyList, txn_version))
+        } else {
+            None
+        }
+    }
+
+    /// Classifies an asset by the allow/deny lists, then by its name and symbol. Returns None
+    /// if no rule applies.
+    pub fn classify_asset(
+        &self,
+        asset: &str,
+        name: &str,
+        symbol: Option<&str>,
+        txn_version: i64,
+    ) -> Option<SpamAsset> {
+        if let Some(spam_asset) = self.classify_by_list(asset, txn_version) {
+            return Some(spam_asset);
+        }
+        if self.name_patterns.iter().any(|re| re.is_match(name)) {
+            return Some(SpamAsset::new(asset, SpamReason::NamePattern, txn_version));
+        }
+        if let Some(symbol) = symbol {
+            if self.symbol_patterns.iter().any(|re| re.is_match(symbol)) {
+                return Some(SpamAsset::new(
+                    asset,
+                    SpamReason::SymbolPattern,
+                    txn_version,
+                ));
+            }
+        }
+        None
+    }
+
+    /// Flags assets sent to at least `airdrop_min_recipients` distinct addresses in a single
+    /// transaction. Takes (transaction version, asset, recipient) for every transfer.
+    pub fn classify_airdrops<'a>(
+        &self,
+        transfers: impl IntoIterator<Item = (i64, &'a str, &'a str)>,
+    ) -> Vec<SpamAsset> {
+        let min_recipients = match self.config.airdrop_min_recipients {
+            Some(min_recipients) => min_recipients,
+            None => return vec![],
+        };
+        let mut recipients: AHashMap<(i64, &str), AHashSet<&str>> = AHashMap::new();
+        for (txn_version, asset, recipient) in transfers {
+            recipients
+                .entry((txn_version, asset))
+                .or_default()
+                .insert(recipient);
+        }
+        recipients
+            .into_iter()
+            .filter(|(_, recipients)| recipients.len() >= min_recipients)
+            .map(|((txn_version, asset), _)| {
+                self.classify_by_list(asset, txn_version)
+                    .unwrap_or_else(|| SpamAsset::new(asset, SpamReason::MassAirdrop, txn_version))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spam_classifier() {
+        let classifier = SpamClassifier::new(SpamClassifierConfig {
+            name_patterns: vec!["claim.*reward".to_string()],
+            symbol_patterns: vec!["^free".to_string()],
+            airdrop_min_recipients: Some(3),
+            allow_list: HashSet::from(["0xgood".to_string()]),
+            deny_list: HashSet::from(["0xbad".to_string()]),
+        })
+        .unwrap();
+
+        let reason = |spam_asset: Option<SpamAsset>| spam_asset.map(|s| (s.is_spam, s.reason));
+        assert_eq!(
+            reason(classifier.classify_asset("0xa", "Claim your REWARD", None, 1)),
+            Some((true, "name_pattern".to_string()))
+        );
+        assert_eq!(
+            reason(classifier.classify_asset("0xa", "Coin", Some("FREEAPT"), 1)),
+            Some((true, "symbol_pattern".to_string()))
+        );
+        assert_eq!(
+            reason(classifier.classify_asset("0xgood", "Claim reward", None, 1)),
+            Some((false, "allow_list".to_string()))
+        );
+        assert_eq!(
+            reason(classifier.classify_asset("0xbad", "Coin", None, 1)),
+            Some((true, "deny_list".to_string()))
+        );
+        assert_eq!(
+            reason(classifier.classify_asset("0xa", "Coin", Some("COIN"), 1)),
+            None
+        );
+
+        let mut transfers = vec![];
+        for recipient in ["0x1", "0x2", "0x3"] {
+            transfers.push((1, "0xdrop", recipient));
+            transfers.push((1, "0xgood", recipient));
+        }
+        // Only two distinct recipients in this transaction
+        transfers.extend([(2, "0xa", "0x1"), (2, "0xa", "0x2"), (2, "0xa", "0x2")]);
+        let airdrops = SpamAsset::dedupe(classifier.classify_airdrops(transfers));
+        assert_eq!(airdrops.len(), 2);
+        assert_eq!(airdrops[0].asset, "0xdrop");
+        assert_eq!(airdrops[0].reason, "mass_airdrop");
+        assert_eq!(airdrops[1].asset, "0xgood");
+        assert!(!airdrops[1].is_spam);
+    }
+}
@@ -40,7 +40,17 @@ pub struct CurrentDelegatorBalance {
     pub parent_table_handle: String,
 }
 
-#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    FieldCount,
+    Identifiable,
+    Insertable,
+    Queryable,
+    Selectable,
+    Serialize,
+)]
 #[diesel(primary_key(transaction_version, write_set_change_index))]
 #[diesel(table_name = delegator_balances)]
 pub struct DelegatorBalance {
@@ -0,0 +1,474 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+
+use super::{delegator_balances::DelegatorBalance, delegator_pools::DelegatorPoolBalance};
+use crate::{
+    schema::{
+        current_delegator_staking_rewards, delegated_staking_pool_balances,
+        delegated_staking_pool_epoch_rewards, delegator_balances,
+    },
+    utils::database::DbPoolConnection,
+};
+use ahash::{AHashMap, AHashSet};
+use bigdecimal::{BigDecimal, One, Zero};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+type StakingPoolAddress = String;
+type DelegatorAddress = String;
+type Epoch = i64;
+pub type DelegatorPoolEpochRewardMap =
+    AHashMap<(StakingPoolAddress, Epoch), DelegatorPoolEpochReward>;
+pub type CurrentDelegatorStakingRewardPK = (DelegatorAddress, StakingPoolAddress);
+
+const ACTIVE_SHARES: &str = "active_shares";
+// Share prices and APRs are rounded to this many decimal places
+const DECIMAL_SCALE: i64 = 18;
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+const QUERY_CHUNK_SIZE: usize = 1000;
+
+/// Coins per active share of a delegation pool. Delegators earn rewards as the pool's total coins
+/// grow while its shares don't, net of operator commission, so the price only ever goes up.
+pub fn get_share_price(total_coins: &BigDecimal, total_shares: &BigDecimal) -> BigDecimal {
+    if total_shares.is_zero() {
+        return BigDecimal::one();
+    }
+    (total_coins / total_shares).round(DECIMAL_SCALE)
+}
+
+/// Share price growth between two observations, annualized. None if no time has passed.
+pub fn get_apr(
+    previous_share_price: &BigDecimal,
+    share_price: &BigDecimal,
+    elapsed_secs: i64,
+) -> Option<BigDecimal> {
+    if elapsed_secs <= 0 || previous_share_price.is_zero() {
+        return None;
+    }
+    let growth = share_price / previous_share_price - BigDecimal::one();
+    Some(
+        (growth * BigDecimal::from(SECONDS_PER_YEAR) / BigDecimal::from(elapsed_secs))
+            .round(DECIMAL_SCALE),
+    )
+}
+
+/// The last balance of a delegation pool seen in an epoch, compared to the pool's previous row.
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    FieldCount,
+    Identifiable,
+    Insertable,
+    Queryable,
+    Selectable,
+    Serialize,
+)]
+#[diesel(primary_key(staking_pool_address, epoch))]
+#[diesel(table_name = delegated_staking_pool_epoch_rewards)]
+#[diesel(treat_none_as_null = true)]
+pub struct DelegatorPoolEpochReward {
+    pub staking_pool_address: String,
+    pub epoch: i64,
+    pub transaction_version: i64,
+    pub transaction_timestamp: chrono::NaiveDateTime,
+    pub total_coins: BigDecimal,
+    pub total_shares: BigDecimal,
+    pub share_price: BigDecimal,
+    pub operator_commission_percentage: BigDecimal,
+    pub previous_transaction_version: Option<i64>,
+    pub previous_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    pub previous_share_price: Option<BigDecimal>,
+    pub rewards_amount: Option<BigDecimal>,
+    pub apr: Option<BigDecimal>,
+}
+
+impl DelegatorPoolEpochReward {
+    pub fn from_pool_balance(
+        pool_balance: &DelegatorPoolBalance,
+        epoch: i64,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            staking_pool_address: pool_balance.staking_pool_address.clone(),
+            epoch,
+            transaction_version: pool_balance.transaction_version,
+            transaction_timestamp: txn_timestamp,
+            total_coins: pool_balance.total_coins.clone(),
+            total_shares: pool_balance.total_shares.clone(),
+            share_price: get_share_price(&pool_balance.total_coins, &pool_balance.total_shares),
+            operator_commission_percentage: pool_balance.operator_commission_percentage.clone(),
+            previous_transaction_version: None,
+            previous_transaction_timestamp: None,
+            previous_share_price: None,
+            rewards_amount: None,
+            apr: None,
+        }
+    }
+
+    /// Links the pools' rows around the batch's epochs to their previous row, reading them back
+    /// once the batch is written. Batches can be written out of order, so the batch's first row
+    /// may have been linked to an older row than its previous one so far, and the pool's row
+    /// after the batch's epochs is relinked to the batch's last. The batch's rows are added in
+    /// case they aren't in the table, e.g. in a dry run. Returns the relinked rows sorted by PK.
+    pub async fn relink(
+        epoch_rewards: &[Self],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        // Pool -> (first epoch, last epoch) in the batch
+        let mut batch_epochs: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+        for epoch_reward in epoch_rewards {
+            let (first_epoch, last_epoch) = batch_epochs
+                .entry(epoch_reward.staking_pool_address.as_str())
+                .or_insert((epoch_reward.epoch, epoch_reward.epoch));
+            *first_epoch = (*first_epoch).min(epoch_reward.epoch);
+            *last_epoch = (*last_epoch).max(epoch_reward.epoch);
+        }
+
+        let mut relinked = vec![];
+        for (pool_address, (first_epoch, last_epoch)) in batch_epochs {
+            let mut rows = Self::get_around_epochs(pool_address, first_epoch, last_epoch, conn)
+                .await?
+                .into_iter()
+                .map(|row| (row.epoch, row))
+                .collect::<BTreeMap<_, _>>();
+            for epoch_reward in epoch_rewards
+                .iter()
+                .filter(|epoch_reward| epoch_reward.staking_pool_address == pool_address)
+            {
+                let is_newer = rows
+                    .get(&epoch_reward.epoch)
+                    .map(|row| row.transaction_version < epoch_reward.transaction_version)
+                    .unwrap_or(true);
+                if is_newer {
+                    rows.insert(epoch_reward.epoch, epoch_reward.clone());
+                }
+            }
+
+            let mut previous: Option<Self> = None;
+            for mut row in rows.into_values() {
+                if let Some(previous) = &previous {
+                    if row.previous_transaction_version != Some(previous.transaction_version) {
+                        row.set_previous(previous);
+                        relinked.push(row.clone());
+                    }
+                }
+                previous = Some(row);
+            }
+        }
+        Ok(relinked)
+    }
+
+    /// The pool's rows from `first_epoch` to `last_epoch`, and its rows right before and after
+    async fn get_around_epochs(
+        pool_address: &str,
+        first_epoch: i64,
+        last_epoch: i64,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut rows = delegated_staking_pool_epoch_rewards::table
+            .select(Self::as_select())
+            .filter(delegated_staking_pool_epoch_rewards::staking_pool_address.eq(pool_address))
+            .filter(delegated_staking_pool_epoch_rewards::epoch.between(first_epoch, last_epoch))
+            .load::<Self>(conn)
+            .await?;
+        rows.extend(
+            delegated_staking_pool_epoch_rewards::table
+                .select(Self::as_select())
+                .filter(delegated_staking_pool_epoch_rewards::staking_pool_address.eq(pool_address))
+                .filter(delegated_staking_pool_epoch_rewards::epoch.lt(first_epoch))
+                .order(delegated_staking_pool_epoch_rewards::epoch.desc())
+                .first::<Self>(conn)
+                .await
+                .optional()?,
+        );
+        rows.extend(
+            delegated_staking_pool_epoch_rewards::table
+                .select(Self::as_select())
+                .filter(delegated_staking_pool_epoch_rewards::staking_pool_address.eq(pool_address))
+                .filter(delegated_staking_pool_epoch_rewards::epoch.gt(last_epoch))
+                .order(delegated_staking_pool_epoch_rewards::epoch.asc())
+                .first::<Self>(conn)
+                .await
+                .optional()?,
+        );
+        Ok(rows)
+    }
+
+    fn set_previous(&mut self, previous: &Self) {
+        let elapsed_secs =
+            (self.transaction_timestamp - previous.transaction_timestamp).num_seconds();
+        self.previous_transaction_version = Some(previous.transaction_version);
+        self.previous_transaction_timestamp = Some(previous.transaction_timestamp);
+        self.previous_share_price = Some(previous.share_price.clone());
+        self.rewards_amount = Some(
+            (&previous.total_shares * (&self.share_price - &previous.share_price))
+                .round(DECIMAL_SCALE),
+        );
+        self.apr = get_apr(&previous.share_price, &self.share_price, elapsed_secs);
+    }
+}
+
+/// Rewards a delegator has earned on their active stake in a pool. Active shares are bought and
+/// unlocked at the pool's share price, and what they're worth on top of what was paid for them
+/// is rewards. Delegators first seen after they had already staked accrue from that point on.
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    FieldCount,
+    Identifiable,
+    Insertable,
+    Queryable,
+    Selectable,
+    Serialize,
+)]
+#[diesel(primary_key(delegator_address, pool_address))]
+#[diesel(table_name = current_delegator_staking_rewards)]
+pub struct CurrentDelegatorStakingReward {
+    pub delegator_address: String,
+    pub pool_address: String,
+    pub active_shares: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub realized_rewards: BigDecimal,
+    pub accrued_rewards: BigDecimal,
+    pub share_price: BigDecimal,
+    pub last_transaction_version: i64,
+    // Active share changes applied, which only grows as more of the history is seen
+    pub num_share_changes: i64,
+}
+
+impl CurrentDelegatorStakingReward {
+    fn new(delegator_address: &str, pool_address: &str) -> Self {
+        Self {
+            delegator_address: delegator_address.to_string(),
+            pool_address: pool_address.to_string(),
+            active_shares: BigDecimal::zero(),
+            cost_basis: BigDecimal::zero(),
+            realized_rewards: BigDecimal::zero(),
+            accrued_rewards: BigDecimal::zero(),
+            share_price: BigDecimal::one(),
+            last_transaction_version: 0,
+            num_share_changes: 0,
+        }
+    }
+
+    /// Moves the delegator to `active_shares` at `share_price`. Shares bought add to the cost
+    /// basis, shares unlocked realize their rewards and take their share of the cost basis along.
+    pub fn apply_share_change(
+        &mut self,
+        active_shares: &BigDecimal,
+        share_price: &BigDecimal,
+        txn_version: i64,
+    ) {
+        if active_shares >= &self.active_shares {
+            self.cost_basis += (active_shares - &self.active_shares) * share_price;
+        } else {
+            let unlocked_shares = &self.active_shares - active_shares;
+            let unlocked_cost_basis =
+                (&self.cost_basis * &unlocked_shares / &self.active_shares).round(DECIMAL_SCALE);
+            self.realized_rewards += &unlocked_shares * share_price - &unlocked_cost_basis;
+            self.cost_basis -= unlocked_cost_basis;
+        }
+        self.active_shares = active_shares.clone();
+        self.share_price = share_price.clone();
+        self.accrued_rewards = self.accrued_rewards_at(share_price);
+        self.last_transaction_version = txn_version;
+        self.num_share_changes += 1;
+    }
+
+    /// Rewards earned so far if the pool's share price is now `share_price`
+    pub fn accrued_rewards_at(&self, share_price: &BigDecimal) -> BigDecimal {
+        (&self.realized_rewards + &self.active_shares * share_price - &self.cost_basis)
+            .round(DECIMAL_SCALE)
+    }
+
+    /// Replays the whole active share history of the delegators of the batch from
+    /// `delegator_balances`, at the share price of the pool in the same transaction from
+    /// `delegated_staking_pool_balances`. Run once the batch's history is written, so whichever
+    /// batch of a delegator is written last sees the others, in whatever order they're processed.
+    /// The batch's own rows are added in case they aren't in the table, e.g. in a dry run. Changes
+    /// without a pool balance are skipped. Returns the rows sorted by PK.
+    pub async fn from_history(
+        delegator_balances: &[DelegatorBalance],
+        pool_balances: &[DelegatorPoolBalance],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let batch_share_changes = delegator_balances
+            .iter()
+            .filter(|balance| balance.pool_type == ACTIVE_SHARES)
+            .collect::<Vec<_>>();
+        let pks = batch_share_changes
+            .iter()
+            .map(|balance| {
+                (
+                    balance.delegator_address.clone(),
+                    balance.pool_address.clone(),
+                )
+            })
+            .collect::<AHashSet<CurrentDelegatorStakingRewardPK>>();
+        let delegator_addresses = pks
+            .iter()
+            .map(|(delegator_address, _)| delegator_address.clone())
+            .collect::<AHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        // (transaction version, write set change index) -> active share change
+        let share_changes = get_active_share_history(&delegator_addresses, conn)
+            .await?
+            .into_iter()
+            .chain(batch_share_changes.into_iter().cloned())
+            .filter(|balance| {
+                pks.contains(&(
+                    balance.delegator_address.clone(),
+                    balance.pool_address.clone(),
+                ))
+            })
+            .map(|balance| {
+                (
+                    (balance.transaction_version, balance.write_set_change_index),
+                    balance,
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let pool_addresses = pks
+            .iter()
+            .map(|(_, pool_address)| pool_address.clone())
+            .collect::<AHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let versions = share_changes
+            .keys()
+            .map(|(txn_version, _)| *txn_version)
+            .collect::<AHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        // (pool address, transaction version) -> share price
+        let share_prices = get_pool_balances(&pool_addresses, &versions, conn)
+            .await?
+            .iter()
+            .chain(pool_balances)
+            .map(|pool_balance| {
+                (
+                    (
+                        pool_balance.staking_pool_address.clone(),
+                        pool_balance.transaction_version,
+                    ),
+                    get_share_price(&pool_balance.total_coins, &pool_balance.total_shares),
+                )
+            })
+            .collect::<AHashMap<_, _>>();
+
+        let mut rewards: AHashMap<CurrentDelegatorStakingRewardPK, Self> = AHashMap::new();
+        for balance in share_changes.values() {
+            let Some(share_price) =
+                share_prices.get(&(balance.pool_address.clone(), balance.transaction_version))
+            else {
+                tracing::warn!(
+                    transaction_version = balance.transaction_version,
+                    pool_address = balance.pool_address.as_str(),
+                    "No share price for active share change, skipping rewards",
+                );
+                continue;
+            };
+            rewards
+                .entry((
+                    balance.delegator_address.clone(),
+                    balance.pool_address.clone(),
+                ))
+                .or_insert_with(|| Self::new(&balance.delegator_address, &balance.pool_address))
+                .apply_share_change(&balance.shares, share_price, balance.transaction_version);
+        }
+        let mut rewards = rewards.into_values().collect::<Vec<_>>();
+        rewards.sort_by(|a, b| {
+            (&a.delegator_address, &a.pool_address).cmp(&(&b.delegator_address, &b.pool_address))
+        });
+        Ok(rewards)
+    }
+}
+
+/// Active share changes of the delegators in any pool
+async fn get_active_share_history(
+    delegator_addresses: &[String],
+    conn: &mut DbPoolConnection<'_>,
+) -> diesel::QueryResult<Vec<DelegatorBalance>> {
+    let mut balances = vec![];
+    for chunk in delegator_addresses.chunks(QUERY_CHUNK_SIZE) {
+        balances.extend(
+            delegator_balances::table
+                .select(DelegatorBalance::as_select())
+                .filter(delegator_balances::delegator_address.eq_any(chunk))
+                .filter(delegator_balances::pool_type.eq(ACTIVE_SHARES))
+                .load::<DelegatorBalance>(conn)
+                .await?,
+        );
+    }
+    Ok(balances)
+}
+
+/// Balances of the pools at the given versions
+async fn get_pool_balances(
+    pool_addresses: &[String],
+    versions: &[i64],
+    conn: &mut DbPoolConnection<'_>,
+) -> diesel::QueryResult<Vec<DelegatorPoolBalance>> {
+    let mut pool_balances = vec![];
+    for chunk in versions.chunks(QUERY_CHUNK_SIZE) {
+        pool_balances.extend(
+            delegated_staking_pool_balances::table
+                .select(DelegatorPoolBalance::as_select())
+                .filter(delegated_staking_pool_balances::transaction_version.eq_any(chunk))
+                .filter(
+                    delegated_staking_pool_balances::staking_pool_address.eq_any(pool_addresses),
+                )
+                .load::<DelegatorPoolBalance>(conn)
+                .await?,
+        );
+    }
+    Ok(pool_balances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_rewards_accrue_with_share_price() {
+        assert_eq!(
+            get_share_price(&decimal("110"), &decimal("100")),
+            decimal("1.1")
+        );
+        assert_eq!(
+            get_apr(&decimal("1"), &decimal("1.1"), SECONDS_PER_YEAR / 2),
+            Some(decimal("0.2"))
+        );
+
+        let mut reward = CurrentDelegatorStakingReward::new("0x1", "0x2");
+        // Buys 100 shares at 1, then another 100 at 1.1
+        reward.apply_share_change(&decimal("100"), &decimal("1"), 1);
+        reward.apply_share_change(&decimal("200"), &decimal("1.1"), 2);
+        assert_eq!(reward.cost_basis, decimal("210"));
+        assert_eq!(reward.accrued_rewards, decimal("10"));
+
+        // Unlocks half at 1.2, realizing the rewards on those shares
+        reward.apply_share_change(&decimal("100"), &decimal("1.2"), 3);
+        assert_eq!(reward.cost_basis, decimal("105"));
+        assert_eq!(reward.realized_rewards, decimal("15"));
+        assert_eq!(reward.accrued_rewards, decimal("30"));
+        assert_eq!(reward.accrued_rewards_at(&decimal("1.3")), decimal("40"));
+        assert_eq!(reward.num_share_changes, 3);
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+
+use super::stake_utils::{GovernanceProposalResource, StakeEvent, StakeTableItem};
+use crate::{
+    schema::{current_governance_proposal_tallies, governance_proposals},
+    utils::util::{parse_timestamp, parse_timestamp_secs, standardize_address},
+};
+use ahash::AHashMap;
+use anyhow::Context;
+use aptos_protos::transaction::v1::{transaction::TxnData, write_set_change::Change, Transaction};
+use bigdecimal::BigDecimal;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+type ProposalId = i64;
+pub type GovernanceProposalMap = AHashMap<ProposalId, GovernanceProposal>;
+pub type CurrentGovernanceProposalTallyMap = AHashMap<ProposalId, CurrentGovernanceProposalTally>;
+
+/// An on-chain governance proposal, from the proposal stored by 0x1::voting. Who created it and
+/// how it was resolved only come from the events of the creating and resolving transactions.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(proposal_id))]
+#[diesel(table_name = governance_proposals)]
+#[diesel(treat_none_as_null = true)]
+pub struct GovernanceProposal {
+    pub proposal_id: i64,
+    pub proposer_address: String,
+    pub stake_pool_address: Option<String>,
+    // Delegator voter that created the proposal on behalf of a delegation pool, the voter in
+    // current_delegated_voter
+    pub delegated_voter_address: Option<String>,
+    pub execution_hash: String,
+    pub metadata_location: Option<String>,
+    pub metadata_hash: Option<String>,
+    pub min_vote_threshold: BigDecimal,
+    pub early_resolution_vote_threshold: Option<BigDecimal>,
+    pub creation_timestamp: chrono::NaiveDateTime,
+    pub expiration_timestamp: chrono::NaiveDateTime,
+    pub creation_transaction_version: Option<i64>,
+    pub is_resolved: bool,
+    pub resolved_early: Option<bool>,
+    pub resolution_timestamp: Option<chrono::NaiveDateTime>,
+    pub resolution_transaction_version: Option<i64>,
+    pub last_transaction_version: i64,
+}
+
+/// Latest vote tally of a proposal. A proposal passes with more yes than no votes once enough
+/// votes are cast.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(proposal_id))]
+#[diesel(table_name = current_governance_proposal_tallies)]
+pub struct CurrentGovernanceProposalTally {
+    pub proposal_id: i64,
+    pub yes_votes: BigDecimal,
+    pub no_votes: BigDecimal,
+    pub total_votes: BigDecimal,
+    pub min_vote_threshold: BigDecimal,
+    pub is_passing: bool,
+    pub is_resolved: bool,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl GovernanceProposal {
+    pub fn from_transaction(
+        transaction: &Transaction,
+    ) -> anyhow::Result<(GovernanceProposalMap, CurrentGovernanceProposalTallyMap)> {
+        let mut proposals = AHashMap::new();
+        let mut tallies = AHashMap::new();
+        let user_txn = match transaction.txn_data.as_ref() {
+            Some(TxnData::User(user_txn)) => user_txn,
+            _ => return Ok((proposals, tallies)),
+        };
+        let txn_version = transaction.version as i64;
+        let txn_timestamp = parse_timestamp(transaction.timestamp.as_ref().unwrap(), txn_version);
+
+        let mut created = AHashMap::new();
+        let mut delegated_voters = AHashMap::new();
+        let mut resolved = AHashMap::new();
+        for event in &user_txn.events {
+            match StakeEvent::from_event(event.type_str.as_str(), &event.data, txn_version)? {
+                Some(StakeEvent::GovernanceCreateProposalEvent(inner)) => {
+                    created.insert(inner.proposal_id as i64, inner);
+                },
+                Some(StakeEvent::DelegationPoolCreateProposalEvent(inner)) => {
+                    delegated_voters.insert(inner.proposal_id as i64, inner);
+                },
+                Some(StakeEvent::ResolveProposalEvent(inner)) => {
+                    resolved.insert(inner.proposal_id as i64, inner);
+                },
+                _ => {},
+            }
+        }
+
+        let changes = &transaction
+            .info
+            .as_ref()
+            .expect("Transaction info doesn't exist!")
+            .changes;
+        for wsc in changes {
+            let Change::WriteTableItem(write_table_item) = wsc.change.as_ref().unwrap() else {
+                continue;
+            };
+            let table_item_data = write_table_item.data.as_ref().unwrap();
+            let Some(StakeTableItem::GovernanceProposal(inner)) =
+                StakeTableItem::from_table_item_type(
+                    table_item_data.value_type.as_str(),
+                    &table_item_data.value,
+                    txn_version,
+                )?
+            else {
+                continue;
+            };
+            let proposal_id = serde_json::from_str::<String>(&table_item_data.key)
+                .ok()
+                .and_then(|key| key.parse::<i64>().ok())
+                .context(format!(
+                    "version {} failed! failed to parse proposal id {:?}",
+                    txn_version, table_item_data.key
+                ))?;
+
+            let mut proposal = Self::from_resource(proposal_id, &inner, txn_version);
+            if let Some(event) = created.get(&proposal_id) {
+                proposal.stake_pool_address = Some(standardize_address(&event.stake_pool));
+                proposal.creation_transaction_version = Some(txn_version);
+            }
+            if let Some(event) = delegated_voters.get(&proposal_id) {
+                proposal.delegated_voter_address = Some(standardize_address(&event.voter));
+            }
+            if let Some(event) = resolved.get(&proposal_id) {
+                proposal.resolved_early = Some(event.resolved_early);
+                proposal.resolution_transaction_version = Some(txn_version);
+            }
+            proposals.insert(proposal_id, proposal);
+            tallies.insert(
+                proposal_id,
+                CurrentGovernanceProposalTally::from_resource(
+                    proposal_id,
+                    &inner,
+                    txn_version,
+                    txn_timestamp,
+                ),
+            );
+        }
+        Ok((proposals, tallies))
+    }
+
+    fn from_resource(
+        proposal_id: i64,
+        proposal: &GovernanceProposalResource,
+        txn_version: i64,
+    ) -> Self {
+        Self {
+            proposal_id,
+            proposer_address: proposal.get_proposer(),
+            stake_pool_address: None,
+            delegated_voter_address: None,
+            execution_hash: proposal.execution_hash.clone(),
+            metadata_location: proposal.get_metadata_location(),
+            metadata_hash: proposal.get_metadata_hash(),
+            min_vote_threshold: proposal.min_vote_threshold.clone(),
+            early_resolution_vote_threshold: proposal.get_early_resolution_vote_threshold(),
+            creation_timestamp: parse_timestamp_secs(proposal.creation_time_secs, txn_version),
+            expiration_timestamp: parse_timestamp_secs(proposal.expiration_secs, txn_version),
+            creation_transaction_version: None,
+            is_resolved: proposal.is_resolved,
+            resolved_early: None,
+            resolution_timestamp: proposal
+                .is_resolved
+                .then(|| parse_timestamp_secs(proposal.resolution_time_secs, txn_version)),
+            resolution_transaction_version: None,
+            last_transaction_version: txn_version,
+        }
+    }
+
+    /// Keeps what earlier transactions of the batch knew from their events, since later writes
+    /// of the proposal don't repeat them
+    pub fn merge_previous(&mut self, previous: &Self) {
+        if self.stake_pool_address.is_none() {
+            self.stake_pool_address = previous.stake_pool_address.clone();
+        }
+        if self.delegated_voter_address.is_none() {
+            self.delegated_voter_address = previous.delegated_voter_address.clone();
+        }
+        self.creation_transaction_version = self
+            .creation_transaction_version
+            .or(previous.creation_transaction_version);
+        self.resolved_early = self.resolved_early.or(previous.resolved_early);
+        self.resolution_transaction_version = self
+            .resolution_transaction_version
+            .or(previous.resolution_transaction_version);
+    }
+}
+
+impl CurrentGovernanceProposalTally {
+    fn from_resource(
+        proposal_id: i64,
+        proposal: &GovernanceProposalResource,
+        txn_version: i64,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        let total_votes = &proposal.yes_votes + &proposal.no_votes;
+        Self {
+            proposal_id,
+            yes_votes: proposal.yes_votes.clone(),
+            no_votes: proposal.no_votes.clone(),
+            is_passing: proposal.yes_votes > proposal.no_votes
+                && total_votes >= proposal.min_vote_threshold,
+            total_votes,
+            min_vote_threshold: proposal.min_vote_threshold.clone(),
+            is_resolved: proposal.is_resolved,
+            last_transaction_version: txn_version,
+            last_transaction_timestamp: txn_timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposal_from_voting_forum_table_item() {
+        // metadata_location is "https://a.io" hex encoded
+        let data = r#"{
+            "creation_time_secs": "1700000000",
+            "early_resolution_vote_threshold": {"vec": []},
+            "execution_content": {"vec": [{"dummy_field": false}]},
+            "execution_hash": "0x1234",
+            "expiration_secs": "1700604800",
+            "is_resolved": false,
+            "metadata": {"data": [{"key": "metadata_location", "value": "0x68747470733a2f2f612e696f"}]},
+            "min_vote_threshold": "400",
+            "no_votes": "100",
+            "proposer": "0x2",
+            "resolution_time_secs": "0",
+            "yes_votes": "300"
+        }"#;
+        let Some(StakeTableItem::GovernanceProposal(proposal)) =
+            StakeTableItem::from_table_item_type(
+                "0x1::voting::Proposal<0x1::governance_proposal::GovernanceProposal>",
+                data,
+                1,
+            )
+            .unwrap()
+        else {
+            panic!("Proposal table item isn't parsed");
+        };
+        let governance_proposal = GovernanceProposal::from_resource(7, &proposal, 1);
+        assert_eq!(
+            governance_proposal.metadata_location.as_deref(),
+            Some("https://a.io")
+        );
+        assert_eq!(governance_proposal.metadata_hash, None);
+        assert_eq!(governance_proposal.early_resolution_vote_threshold, None);
+        assert_eq!(governance_proposal.resolution_timestamp, None);
+
+        let tally = CurrentGovernanceProposalTally::from_resource(
+            7,
+            &proposal,
+            1,
+            chrono::NaiveDateTime::default(),
+        );
+        assert_eq!(tally.total_votes, BigDecimal::from(400));
+        assert!(tally.is_passing);
+    }
+}
@@ -5,6 +5,8 @@ pub mod current_delegated_voter;
 pub mod delegator_activities;
 pub mod delegator_balances;
 pub mod delegator_pools;
+pub mod delegator_rewards;
+pub mod governance_proposals;
 pub mod proposal_votes;
 pub mod stake_utils;
 pub mod staking_pool_voter;
@@ -5,7 +5,7 @@ use crate::{
     db::common::models::{
         default_models::move_resources::MoveResource, token_models::token_utils::Table,
     },
-    utils::util::{deserialize_from_string, standardize_address},
+    utils::util::{deserialize_from_string, hex_to_raw_bytes, standardize_address},
 };
 use anyhow::{Context, Result};
 use aptos_protos::transaction::v1::WriteResource;
@@ -104,9 +104,110 @@ pub struct ReactivateStakeEvent {
     pub pool_address: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GovernanceCreateProposalEvent {
+    pub proposer: String,
+    pub stake_pool: String,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub proposal_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DelegationPoolCreateProposalEvent {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub proposal_id: u64,
+    pub voter: String,
+    pub delegation_pool: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResolveProposalEvent {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub proposal_id: u64,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub yes_votes: BigDecimal,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub no_votes: BigDecimal,
+    pub resolved_early: bool,
+}
+
+/// 0x1::voting::Proposal<0x1::governance_proposal::GovernanceProposal>, stored in the voting
+/// forum's proposals table keyed by proposal id
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GovernanceProposalResource {
+    proposer: String,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub creation_time_secs: u64,
+    pub execution_hash: String,
+    metadata: ProposalMetadataResource,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub min_vote_threshold: BigDecimal,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub expiration_secs: u64,
+    early_resolution_vote_threshold: OptionalBigDecimal,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub yes_votes: BigDecimal,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub no_votes: BigDecimal,
+    pub is_resolved: bool,
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub resolution_time_secs: u64,
+}
+
+impl GovernanceProposalResource {
+    pub fn get_proposer(&self) -> String {
+        standardize_address(&self.proposer)
+    }
+
+    pub fn get_metadata_location(&self) -> Option<String> {
+        self.metadata.get_string("metadata_location")
+    }
+
+    pub fn get_metadata_hash(&self) -> Option<String> {
+        self.metadata.get_string("metadata_hash")
+    }
+
+    pub fn get_early_resolution_vote_threshold(&self) -> Option<BigDecimal> {
+        self.early_resolution_vote_threshold
+            .vec
+            .first()
+            .map(|threshold| threshold.0.clone())
+    }
+}
+
+/// SimpleMap<String, vector<u8>>
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ProposalMetadataResource {
+    data: Vec<ProposalMetadataEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ProposalMetadataEntry {
+    key: String,
+    value: String,
+}
+
+impl ProposalMetadataResource {
+    /// Metadata values are bytes, which for the known keys are utf8 strings
+    fn get_string(&self, key: &str) -> Option<String> {
+        let entry = self.data.iter().find(|entry| entry.key == key)?;
+        let bytes = hex_to_raw_bytes(&entry.value).ok()?;
+        String::from_utf8(bytes).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OptionalBigDecimal {
+    vec: Vec<BigDecimalWrapper>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BigDecimalWrapper(#[serde(deserialize_with = "deserialize_from_string")] pub BigDecimal);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum StakeTableItem {
     Pool(PoolResource),
+    GovernanceProposal(GovernanceProposalResource),
 }
 
 impl StakeTableItem {
@@ -119,6 +220,10 @@ impl StakeTableItem {
             "0x1::pool_u64_unbound::Pool" => {
                 serde_json::from_str(data).map(|inner| Some(StakeTableItem::Pool(inner)))
             },
+            "0x1::voting::Proposal<0x1::governance_proposal::GovernanceProposal>" => {
+                serde_json::from_str(data)
+                    .map(|inner| Some(StakeTableItem::GovernanceProposal(inner)))
+            },
             _ => Ok(None),
         }
         .context(format!(
@@ -195,6 +300,9 @@ pub enum StakeEvent {
     UnlockStakeEvent(UnlockStakeEvent),
     WithdrawStakeEvent(WithdrawStakeEvent),
     ReactivateStakeEvent(ReactivateStakeEvent),
+    GovernanceCreateProposalEvent(GovernanceCreateProposalEvent),
+    DelegationPoolCreateProposalEvent(DelegationPoolCreateProposalEvent),
+    ResolveProposalEvent(ResolveProposalEvent),
 }
 
 impl StakeEvent {
@@ -216,6 +324,14 @@ impl StakeEvent {
             },
             "0x1::delegation_pool::ReactivateStakeEvent" => serde_json::from_str(data)
                 .map(|inner| Some(StakeEvent::ReactivateStakeEvent(inner))),
+            "0x1::aptos_governance::CreateProposalEvent"
+            | "0x1::aptos_governance::CreateProposal" => serde_json::from_str(data)
+                .map(|inner| Some(StakeEvent::GovernanceCreateProposalEvent(inner))),
+            "0x1::delegation_pool::CreateProposalEvent"
+            | "0x1::delegation_pool::CreateProposal" => serde_json::from_str(data)
+                .map(|inner| Some(StakeEvent::DelegationPoolCreateProposalEvent(inner))),
+            "0x1::voting::ResolveProposal" => serde_json::from_str(data)
+                .map(|inner| Some(StakeEvent::ResolveProposalEvent(inner))),
             _ => Ok(None),
         }
         .context(format!(
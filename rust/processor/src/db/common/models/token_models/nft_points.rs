@@ -6,9 +6,10 @@
 #![allow(clippy::unused_unit)]
 
 use crate::{
-    schema::nft_points,
+    schema::{current_nft_points, nft_points},
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
+        database::DbPoolConnection,
         util::{
             get_clean_payload, get_entry_function_from_user_request, parse_timestamp,
             standardize_address,
@@ -17,10 +18,14 @@ use crate::{
 };
 use aptos_protos::transaction::v1::{transaction::TxnData, Transaction};
 use bigdecimal::BigDecimal;
-use diesel::prelude::*;
+use diesel::{dsl::count_star, prelude::*};
+use diesel_async::RunQueryDsl;
 use field_count::FieldCount;
 use serde::{Deserialize, Serialize};
 
+// Owners per query when recomputing totals
+const TOTALS_QUERY_CHUNK_SIZE: usize = 1000;
+
 #[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
 #[diesel(primary_key(transaction_version))]
 #[diesel(table_name = nft_points)]
@@ -82,52 +87,29 @@ impl NftPoints {
                     return None;
                 }
                 if entry_function_id_str == contract {
-                    let payload_cleaned = get_clean_payload(payload, version).unwrap();
-                    let args = payload_cleaned["arguments"]
-                        .as_array()
-                        .unwrap_or_else(|| {
-                            tracing::error!(
-                                transaction_version = version,
-                                payload = ?payload_cleaned,
-                                "Failed to get arguments from nft_points transaction"
-                            );
-                            panic!()
-                        })
-                        .iter()
-                        .map(|x| {
-                            unescape::unescape(x.as_str().unwrap_or_else(|| {
-                                tracing::error!(
-                                    transaction_version = version,
-                                    payload = ?payload_cleaned,
-                                    "Failed to parse arguments from nft_points transaction"
-                                );
-                                panic!()
-                            }))
-                            .unwrap_or_else(|| {
-                                tracing::error!(
-                                    transaction_version = version,
-                                    payload = ?payload_cleaned,
-                                    "Failed to escape arguments from nft_points transaction"
-                                );
-                                panic!()
-                            })
-                        })
-                        .collect::<Vec<String>>();
-                    let owner_address = standardize_address(&args[0]);
-                    let amount = args[2].parse().unwrap_or_else(|_| {
-                        tracing::error!(
+                    let payload_cleaned = get_clean_payload(payload, version)?;
+                    // The contract is configurable, so a call with other arguments is skipped
+                    // rather than taking down the processor
+                    let Some((owner_address, token_name, amount, point_type)) =
+                        Self::parse_arguments(&payload_cleaned)
+                    else {
+                        PROCESSOR_UNKNOWN_TYPE_COUNT
+                            .with_label_values(&["NftPoints"])
+                            .inc();
+                        tracing::warn!(
                             transaction_version = version,
-                            argument = &args[2],
-                            "Failed to parse amount from nft_points transaction"
+                            entry_function = contract.as_str(),
+                            payload = ?payload_cleaned,
+                            "Unexpected arguments in nft_points transaction, skipping it"
                         );
-                        panic!()
-                    });
+                        return None;
+                    };
                     let transaction_timestamp = parse_timestamp(timestamp, version);
                     return Some(Self {
                         transaction_version: version,
                         owner_address,
-                        token_name: args[1].clone(),
-                        point_type: args[3].clone(),
+                        token_name,
+                        point_type,
                         amount,
                         transaction_timestamp,
                     });
@@ -136,4 +118,146 @@ impl NftPoints {
         }
         None
     }
+
+    /// Arguments must be (owner address, token name, amount, point type)
+    fn parse_arguments(
+        payload_cleaned: &serde_json::Value,
+    ) -> Option<(String, String, BigDecimal, String)> {
+        let args = payload_cleaned["arguments"].as_array()?;
+        let arg = |i: usize| unescape::unescape(args.get(i)?.as_str()?);
+        let owner_address = standardize_address(&arg(0)?);
+        let amount = arg(2)?.parse().ok()?;
+        Some((owner_address, arg(1)?, amount, arg(3)?))
+    }
+}
+
+/// Total points per owner and point type, summed over all of their nft_points rows.
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(owner_address, point_type))]
+#[diesel(table_name = current_nft_points)]
+pub struct CurrentNftPoints {
+    pub owner_address: String,
+    pub point_type: String,
+    pub total_amount: BigDecimal,
+    pub num_transactions: i64,
+    pub last_transaction_version: i64,
+}
+
+impl CurrentNftPoints {
+    /// Recomputes the totals of every owner in `points` from nft_points, which must already
+    /// contain them. Summing the history rather than adding to the previous total keeps this
+    /// correct when batches are reprocessed or committed out of order.
+    pub async fn get_totals_from_db(
+        points: &[NftPoints],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        let mut owners = points
+            .iter()
+            .map(|p| p.owner_address.as_str())
+            .collect::<Vec<_>>();
+        owners.sort();
+        owners.dedup();
+
+        let mut totals = vec![];
+        for owners in owners.chunks(TOTALS_QUERY_CHUNK_SIZE) {
+            let rows: Vec<(String, String, Option<BigDecimal>, i64, Option<i64>)> =
+                nft_points::table
+                    .filter(nft_points::owner_address.eq_any(owners))
+                    .group_by((nft_points::owner_address, nft_points::point_type))
+                    .select((
+                        nft_points::owner_address,
+                        nft_points::point_type,
+                        diesel::dsl::sum(nft_points::amount),
+                        count_star(),
+                        diesel::dsl::max(nft_points::transaction_version),
+                    ))
+                    .load(conn)
+                    .await?;
+            totals.extend(rows.into_iter().map(
+                |(owner_address, point_type, total_amount, num_transactions, last_version)| Self {
+                    owner_address,
+                    point_type,
+                    total_amount: total_amount.unwrap_or_default(),
+                    num_transactions,
+                    last_transaction_version: last_version.unwrap_or_default(),
+                },
+            ));
+        }
+        Ok(totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::{
+        transaction::v1::{
+            transaction_payload::Payload, EntryFunctionPayload, TransactionInfo,
+            TransactionPayload, UserTransaction, UserTransactionRequest,
+        },
+        util::timestamp::Timestamp,
+    };
+
+    const CONTRACT: &str = "0x7::points::add_points";
+
+    fn points_transaction(function: &str, arguments: &[&str]) -> Transaction {
+        Transaction {
+            version: 5,
+            timestamp: Some(Timestamp::default()),
+            info: Some(TransactionInfo {
+                success: true,
+                ..TransactionInfo::default()
+            }),
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    payload: Some(TransactionPayload {
+                        payload: Some(Payload::EntryFunctionPayload(EntryFunctionPayload {
+                            entry_function_id_str: function.to_string(),
+                            arguments: arguments.iter().map(|arg| arg.to_string()).collect(),
+                            ..EntryFunctionPayload::default()
+                        })),
+                        ..TransactionPayload::default()
+                    }),
+                    ..UserTransactionRequest::default()
+                }),
+                ..UserTransaction::default()
+            })),
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn test_nft_points_from_transaction() {
+        let txn = points_transaction(CONTRACT, &[
+            r#""0x2""#,
+            r#""Token #1""#,
+            r#""100""#,
+            r#""quest""#,
+        ]);
+        let points = NftPoints::from_transaction(&txn, Some(CONTRACT.to_string())).unwrap();
+        assert_eq!(points.owner_address, standardize_address("0x2"));
+        assert_eq!(points.token_name, "Token #1");
+        assert_eq!(points.amount, BigDecimal::from(100));
+        assert_eq!(points.point_type, "quest");
+        assert!(
+            NftPoints::from_transaction(&txn, Some("0x7::points::other".to_string())).is_none()
+        );
+
+        // Calls with other arguments are skipped instead of panicking
+        let missing_point_type =
+            points_transaction(CONTRACT, &[r#""0x2""#, r#""Token #1""#, r#""100""#]);
+        assert!(
+            NftPoints::from_transaction(&missing_point_type, Some(CONTRACT.to_string())).is_none()
+        );
+        let bad_amount = points_transaction(CONTRACT, &[
+            r#""0x2""#,
+            r#""Token #1""#,
+            r#""lots""#,
+            r#""quest""#,
+        ]);
+        assert!(NftPoints::from_transaction(&bad_amount, Some(CONTRACT.to_string())).is_none());
+        let not_a_string =
+            points_transaction(CONTRACT, &["2", r#""Token #1""#, r#""100""#, r#""quest""#]);
+        assert!(NftPoints::from_transaction(&not_a_string, Some(CONTRACT.to_string())).is_none());
+    }
 }
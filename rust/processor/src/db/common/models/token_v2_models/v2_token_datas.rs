@@ -12,17 +12,21 @@ use crate::{
         token_models::token_utils::TokenWriteSet,
     },
     schema::{current_token_datas_v2, token_datas_v2},
-    utils::util::standardize_address,
+    utils::{database::DbPoolConnection, util::standardize_address},
 };
+use ahash::AHashMap;
 use aptos_protos::transaction::v1::{DeleteResource, WriteResource, WriteTableItem};
 use bigdecimal::BigDecimal;
 use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
 use field_count::FieldCount;
 use serde::{Deserialize, Serialize};
 
 // PK of current_token_datas_v2, i.e. token_data_id
 pub type CurrentTokenDataV2PK = String;
 
+const QUERY_CHUNK_SIZE: usize = 1000;
+
 #[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
 #[diesel(primary_key(transaction_version, write_set_change_index))]
 #[diesel(table_name = token_datas_v2)]
@@ -69,6 +73,30 @@ pub struct CurrentTokenDataV2 {
     pub is_deleted_v2: Option<bool>,
 }
 
+impl CurrentTokenDataV2 {
+    /// Collection ids of the tokens among `token_data_ids` already in current_token_datas_v2,
+    /// keyed by token_data_id
+    pub async fn get_collection_ids(
+        token_data_ids: &[String],
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<AHashMap<String, String>> {
+        let mut collection_ids = AHashMap::new();
+        for token_data_ids in token_data_ids.chunks(QUERY_CHUNK_SIZE) {
+            collection_ids.extend(
+                current_token_datas_v2::table
+                    .select((
+                        current_token_datas_v2::token_data_id,
+                        current_token_datas_v2::collection_id,
+                    ))
+                    .filter(current_token_datas_v2::token_data_id.eq_any(token_data_ids))
+                    .load::<(String, String)>(conn)
+                    .await?,
+            );
+        }
+        Ok(collection_ids)
+    }
+}
+
 impl TokenDataV2 {
     // TODO: remove the useless_asref lint when new clippy nighly is released.
     #[allow(clippy::useless_asref)]
@@ -56,7 +56,17 @@ pub struct TokenOwnershipV2 {
 }
 
 #[derive(
-    Clone, Debug, Deserialize, Eq, FieldCount, Identifiable, Insertable, PartialEq, Serialize,
+    Clone,
+    Debug,
+    Deserialize,
+    Eq,
+    FieldCount,
+    Identifiable,
+    Insertable,
+    PartialEq,
+    Queryable,
+    Selectable,
+    Serialize,
 )]
 #[diesel(primary_key(token_data_id, property_version_v1, owner_address, storage_id))]
 #[diesel(table_name = current_token_ownerships_v2)]
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod signatures;
+pub mod transaction_fees;
 pub mod user_transactions;
 // parquet models
 pub mod parquet_signatures;
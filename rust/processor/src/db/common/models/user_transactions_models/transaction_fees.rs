@@ -0,0 +1,151 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::signatures::Signature;
+use crate::{
+    db::common::models::fungible_asset_models::v2_fungible_asset_utils::FeeStatement,
+    schema::transaction_fees,
+    utils::util::{parse_timestamp, standardize_address, u64_to_bigdecimal},
+};
+use aptos_protos::{
+    transaction::v1::{TransactionInfo, UserTransaction as UserTransactionPB},
+    util::timestamp::Timestamp,
+};
+use bigdecimal::BigDecimal;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(transaction_version))]
+#[diesel(table_name = transaction_fees)]
+pub struct TransactionFee {
+    pub transaction_version: i64,
+    pub block_height: i64,
+    pub sender: String,
+    pub fee_payer_address: String,
+    pub is_sponsored: bool,
+    pub gas_unit_price: BigDecimal,
+    pub total_charge_gas_units: BigDecimal,
+    pub execution_gas_units: BigDecimal,
+    pub io_gas_units: BigDecimal,
+    pub storage_gas_units: BigDecimal,
+    pub storage_fee_octas: BigDecimal,
+    pub storage_fee_refund_octas: BigDecimal,
+    // Gross charge, total_charge_gas_units * gas_unit_price
+    pub total_fee_octas: BigDecimal,
+    // What the fee payer actually paid, the gross charge minus the storage refund
+    pub net_fee_octas: BigDecimal,
+    pub is_transaction_success: bool,
+    pub transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl TransactionFee {
+    /// Returns None if the transaction didn't emit a FeeStatement, i.e. before fee statements
+    /// were introduced.
+    pub fn from_transaction(
+        txn: &UserTransactionPB,
+        txn_info: &TransactionInfo,
+        timestamp: &Timestamp,
+        block_height: i64,
+        version: i64,
+    ) -> Option<Self> {
+        let fee_statement = txn.events.iter().find_map(|event| {
+            FeeStatement::from_event(event.type_str.as_str(), &event.data, version)
+        })?;
+        let user_request = txn
+            .request
+            .as_ref()
+            .expect("Sends is not present in user txn");
+        let sender = standardize_address(&user_request.sender);
+        let sponsor = user_request
+            .signature
+            .as_ref()
+            .and_then(|signature| Signature::get_fee_payer_address(signature, version));
+        // Storage is charged in octas and converted to gas units in the total
+        let storage_gas_units = fee_statement
+            .total_charge_gas_units
+            .saturating_sub(fee_statement.execution_gas_units)
+            .saturating_sub(fee_statement.io_gas_units);
+        let total_fee_octas = BigDecimal::from(fee_statement.total_charge_gas_units)
+            * BigDecimal::from(user_request.gas_unit_price);
+
+        Some(Self {
+            transaction_version: version,
+            block_height,
+            is_sponsored: sponsor.is_some(),
+            fee_payer_address: sponsor.unwrap_or_else(|| sender.clone()),
+            sender,
+            gas_unit_price: u64_to_bigdecimal(user_request.gas_unit_price),
+            total_charge_gas_units: u64_to_bigdecimal(fee_statement.total_charge_gas_units),
+            execution_gas_units: u64_to_bigdecimal(fee_statement.execution_gas_units),
+            io_gas_units: u64_to_bigdecimal(fee_statement.io_gas_units),
+            storage_gas_units: u64_to_bigdecimal(storage_gas_units),
+            storage_fee_octas: u64_to_bigdecimal(fee_statement.storage_fee_octas),
+            storage_fee_refund_octas: u64_to_bigdecimal(fee_statement.storage_fee_refund_octas),
+            total_fee_octas: total_fee_octas.clone(),
+            net_fee_octas: total_fee_octas
+                - BigDecimal::from(fee_statement.storage_fee_refund_octas),
+            is_transaction_success: txn_info.success,
+            transaction_timestamp: parse_timestamp(timestamp, version),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{Event, UserTransactionRequest};
+
+    #[test]
+    fn test_transaction_fee_from_fee_statement() {
+        let txn = UserTransactionPB {
+            request: Some(UserTransactionRequest {
+                sender: "0x7".to_string(),
+                gas_unit_price: 100,
+                ..UserTransactionRequest::default()
+            }),
+            events: vec![Event {
+                type_str: "0x1::transaction_fee::FeeStatement".to_string(),
+                data: r#"{
+                    "execution_gas_units": "4",
+                    "io_gas_units": "3",
+                    "storage_fee_octas": "43800",
+                    "storage_fee_refund_octas": "50000",
+                    "total_charge_gas_units": "445"
+                }"#
+                .to_string(),
+                ..Event::default()
+            }],
+            ..UserTransactionPB::default()
+        };
+        let txn_info = TransactionInfo {
+            success: true,
+            ..TransactionInfo::default()
+        };
+        let fee = TransactionFee::from_transaction(&txn, &txn_info, &Timestamp::default(), 10, 1)
+            .unwrap();
+        assert_eq!(fee.fee_payer_address, standardize_address("0x7"));
+        assert!(!fee.is_sponsored);
+        assert_eq!(fee.storage_gas_units, BigDecimal::from(438));
+        assert_eq!(fee.total_fee_octas, BigDecimal::from(44500));
+        // Deleting storage refunded more than the transaction was charged
+        assert_eq!(fee.net_fee_octas, BigDecimal::from(-5500));
+
+        let no_fee_statement = UserTransactionPB {
+            events: vec![],
+            ..txn
+        };
+        assert!(TransactionFee::from_transaction(
+            &no_fee_statement,
+            &txn_info,
+            &Timestamp::default(),
+            10,
+            1
+        )
+        .is_none());
+    }
+}
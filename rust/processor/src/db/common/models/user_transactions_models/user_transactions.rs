@@ -23,7 +23,17 @@ use bigdecimal::BigDecimal;
 use field_count::FieldCount;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Deserialize, Debug, FieldCount, Identifiable, Insertable, Serialize)]
+#[derive(
+    Clone,
+    Deserialize,
+    Debug,
+    FieldCount,
+    Identifiable,
+    Insertable,
+    Queryable,
+    Selectable,
+    Serialize,
+)]
 #[diesel(primary_key(version))]
 #[diesel(table_name = user_transactions)]
 pub struct UserTransaction {
@@ -38,6 +48,8 @@ pub struct UserTransaction {
     pub timestamp: chrono::NaiveDateTime,
     pub entry_function_id_str: String,
     pub epoch: i64,
+    // Filled in by the processor if ABI decoding is enabled
+    pub decoded_arguments: Option<serde_json::Value>,
 }
 
 impl UserTransaction {
@@ -79,6 +91,7 @@ impl UserTransaction {
                 entry_function_id_str: get_entry_function_from_user_request(user_request)
                     .unwrap_or_default(),
                 epoch,
+                decoded_arguments: None,
             },
             Self::get_signatures(user_request, version, block_height),
         )
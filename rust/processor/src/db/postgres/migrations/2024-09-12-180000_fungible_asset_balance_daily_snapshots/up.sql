@@ -0,0 +1,16 @@
+-- Your SQL goes here
+-- End of day balance of each primary store, only written for days the balance changed. To get the
+-- balance at the end of day D, take the latest snapshot with snapshot_date <= D.
+CREATE TABLE IF NOT EXISTS fungible_asset_balance_daily_snapshots (
+    owner_address VARCHAR(66) NOT NULL,
+    asset_type VARCHAR(1000) NOT NULL,
+    snapshot_date DATE NOT NULL,
+    storage_id VARCHAR(66) NOT NULL,
+    amount NUMERIC NOT NULL,
+    token_standard VARCHAR(10) NOT NULL,
+    last_transaction_version BIGINT NOT NULL,
+    last_transaction_timestamp TIMESTAMP NOT NULL,
+    inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (owner_address, asset_type, snapshot_date)
+);
+CREATE INDEX IF NOT EXISTS fabds_at_date_index ON fungible_asset_balance_daily_snapshots (asset_type, snapshot_date);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS sa_spam_index;
+ALTER TABLE spam_assets DROP COLUMN IF EXISTS reason,
+  DROP COLUMN IF EXISTS last_transaction_version;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE spam_assets
+ADD COLUMN IF NOT EXISTS reason VARCHAR(50),
+  ADD COLUMN IF NOT EXISTS last_transaction_version BIGINT;
+CREATE INDEX IF NOT EXISTS sa_spam_index ON spam_assets (is_spam);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Running totals of nft_points per owner and point type. num_transactions is the number of
+-- nft_points rows summed, so a total computed from fewer rows never overwrites a newer one.
+CREATE TABLE IF NOT EXISTS current_nft_points (
+    owner_address VARCHAR(66) NOT NULL,
+    point_type TEXT NOT NULL,
+    total_amount NUMERIC NOT NULL,
+    num_transactions BIGINT NOT NULL,
+    last_transaction_version BIGINT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (owner_address, point_type)
+);
+CREATE INDEX IF NOT EXISTS cnp_pt_ta_index ON current_nft_points (point_type, total_amount);
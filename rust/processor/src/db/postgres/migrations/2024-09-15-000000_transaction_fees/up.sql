@@ -0,0 +1,27 @@
+-- Your SQL goes here
+-- Fee breakdown of each user transaction, taken from its 0x1::transaction_fee::FeeStatement
+-- event. fee_payer_address is the sponsor for fee payer transactions and the sender otherwise.
+CREATE TABLE IF NOT EXISTS transaction_fees (
+    transaction_version BIGINT PRIMARY KEY NOT NULL,
+    block_height BIGINT NOT NULL,
+    sender VARCHAR(66) NOT NULL,
+    fee_payer_address VARCHAR(66) NOT NULL,
+    is_sponsored BOOLEAN NOT NULL,
+    gas_unit_price NUMERIC NOT NULL,
+    total_charge_gas_units NUMERIC NOT NULL,
+    execution_gas_units NUMERIC NOT NULL,
+    io_gas_units NUMERIC NOT NULL,
+    storage_gas_units NUMERIC NOT NULL,
+    storage_fee_octas NUMERIC NOT NULL,
+    storage_fee_refund_octas NUMERIC NOT NULL,
+    -- Gross charge. The storage refund goes back to the fee payer, so what they actually paid is
+    -- the net fee, which is negative when the refund is larger.
+    total_fee_octas NUMERIC NOT NULL,
+    net_fee_octas NUMERIC NOT NULL,
+    is_transaction_success BOOLEAN NOT NULL,
+    transaction_timestamp TIMESTAMP NOT NULL,
+    inserted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS tf_fpa_tt_index ON transaction_fees (fee_payer_address, transaction_timestamp);
+CREATE INDEX IF NOT EXISTS tf_sender_index ON transaction_fees (sender);
+CREATE INDEX IF NOT EXISTS tf_insat_index ON transaction_fees (inserted_at);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- Entry function arguments and event payloads decoded against their module ABIs, as
+-- [{"name", "type", "value"}]. NULL if decoding is disabled or the ABI is unknown.
+ALTER TABLE user_transactions
+ADD COLUMN IF NOT EXISTS decoded_arguments JSONB;
+ALTER TABLE events
+ADD COLUMN IF NOT EXISTS decoded_data JSONB;
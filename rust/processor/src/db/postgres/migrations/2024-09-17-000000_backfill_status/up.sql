@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Tracks the progress of backfills separately from processor_status so the live processor isn't affected
+CREATE TABLE IF NOT EXISTS backfill_status (
+  backfill_alias VARCHAR(100) PRIMARY KEY NOT NULL,
+  processor VARCHAR(50) NOT NULL,
+  -- in_progress or complete
+  status VARCHAR(50) NOT NULL,
+  last_success_version BIGINT NOT NULL,
+  last_updated TIMESTAMP NOT NULL DEFAULT NOW(),
+  last_transaction_timestamp TIMESTAMP NULL,
+  backfill_start_version BIGINT NOT NULL,
+  backfill_end_version BIGINT NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- Per table progress of Postgres processors, written in the same transaction as the table's rows
+CREATE TABLE IF NOT EXISTS processor_table_status (
+  processor VARCHAR(50) NOT NULL,
+  table_name VARCHAR(100) NOT NULL,
+  last_success_version BIGINT NOT NULL,
+  last_updated TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (processor, table_name)
+);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS ans_name_events;
+DROP INDEX IF EXISTS capn_v2_token_name_index;
+ALTER TABLE current_ans_primary_name_v2 DROP COLUMN IF EXISTS is_active;
+DROP INDEX IF EXISTS cal_v2_token_name_index;
+DROP INDEX IF EXISTS cal_v2_lifecycle_index;
+ALTER TABLE current_ans_lookup_v2 DROP COLUMN IF EXISTS is_active,
+  DROP COLUMN IF EXISTS lifecycle_status;
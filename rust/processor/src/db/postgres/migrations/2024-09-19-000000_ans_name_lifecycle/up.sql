@@ -0,0 +1,32 @@
+-- Your SQL goes here
+ALTER TABLE current_ans_lookup_v2
+ADD COLUMN IF NOT EXISTS lifecycle_status VARCHAR(20) NOT NULL DEFAULT 'active',
+  ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT TRUE;
+CREATE INDEX IF NOT EXISTS cal_v2_lifecycle_index ON current_ans_lookup_v2 (lifecycle_status, expiration_timestamp);
+CREATE INDEX IF NOT EXISTS cal_v2_token_name_index ON current_ans_lookup_v2 (token_name, token_standard);
+ALTER TABLE current_ans_primary_name_v2
+ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT TRUE;
+CREATE INDEX IF NOT EXISTS capn_v2_token_name_index ON current_ans_primary_name_v2 (token_name, token_standard);
+CREATE TABLE IF NOT EXISTS ans_name_events (
+  transaction_version BIGINT NOT NULL,
+  domain VARCHAR(64) NOT NULL,
+  -- if subdomain is null set to empty string
+  subdomain VARCHAR(64) NOT NULL,
+  token_standard VARCHAR(10) NOT NULL,
+  token_name VARCHAR(140) NOT NULL,
+  -- registered, renewed, transferred or expired
+  event_type VARCHAR(20) NOT NULL,
+  registered_address VARCHAR(66),
+  expiration_timestamp TIMESTAMP NOT NULL,
+  transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (
+    transaction_version,
+    domain,
+    subdomain,
+    token_standard,
+    event_type
+  )
+);
+CREATE INDEX IF NOT EXISTS ane_token_name_index ON ans_name_events (token_name, token_standard);
+CREATE INDEX IF NOT EXISTS ane_registered_address_index ON ans_name_events (registered_address);
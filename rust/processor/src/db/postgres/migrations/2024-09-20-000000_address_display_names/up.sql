@@ -0,0 +1,39 @@
+-- Your SQL goes here
+-- The name each address displays as, i.e. its primary name if it's active and still points at
+-- the address. token_name is null for addresses without one.
+CREATE TABLE IF NOT EXISTS address_display_names (
+  address VARCHAR(66) PRIMARY KEY NOT NULL,
+  domain VARCHAR(64),
+  subdomain VARCHAR(64),
+  token_name VARCHAR(140),
+  token_standard VARCHAR(10),
+  last_transaction_version BIGINT NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+-- Seed from the current primary names, preferring v2 names
+INSERT INTO address_display_names (
+    address,
+    domain,
+    subdomain,
+    token_name,
+    token_standard,
+    last_transaction_version
+  )
+SELECT DISTINCT ON (capn.registered_address) capn.registered_address,
+  capn.domain,
+  capn.subdomain,
+  capn.token_name,
+  capn.token_standard,
+  capn.last_transaction_version
+FROM current_ans_primary_name_v2 capn
+  LEFT JOIN current_ans_lookup_v2 cal ON cal.token_name = capn.token_name
+  AND cal.token_standard = capn.token_standard
+WHERE capn.is_deleted IS false
+  AND capn.is_active
+  AND (
+    cal.domain IS NULL
+    OR cal.registered_address = capn.registered_address
+  )
+ORDER BY capn.registered_address,
+  capn.token_standard = 'v2' DESC,
+  capn.last_transaction_version DESC ON CONFLICT DO NOTHING;
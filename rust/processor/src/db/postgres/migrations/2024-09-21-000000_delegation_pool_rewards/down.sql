@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS current_delegator_staking_rewards;
+DROP TABLE IF EXISTS delegated_staking_pool_epoch_rewards;
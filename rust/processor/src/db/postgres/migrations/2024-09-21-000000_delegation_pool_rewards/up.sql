@@ -0,0 +1,46 @@
+-- Your SQL goes here
+-- Rewards of each delegation pool per epoch, derived from the pool's active share price. The
+-- pool's balance is only synced when it's interacted with, so a row covers the rewards synced
+-- since the pool's previous row, i.e. possibly more than one epoch.
+CREATE TABLE IF NOT EXISTS delegated_staking_pool_epoch_rewards (
+  staking_pool_address VARCHAR(66) NOT NULL,
+  epoch BIGINT NOT NULL,
+  -- Last pool balance seen in the epoch
+  transaction_version BIGINT NOT NULL,
+  transaction_timestamp TIMESTAMP NOT NULL,
+  total_coins NUMERIC NOT NULL,
+  total_shares NUMERIC NOT NULL,
+  share_price NUMERIC NOT NULL,
+  operator_commission_percentage NUMERIC NOT NULL,
+  -- Last pool balance of the pool's previous row, null for the first one
+  previous_transaction_version BIGINT,
+  previous_transaction_timestamp TIMESTAMP,
+  previous_share_price NUMERIC,
+  -- Rewards earned by the delegators since the previous row, net of operator commission
+  rewards_amount NUMERIC,
+  apr NUMERIC,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (staking_pool_address, epoch)
+);
+CREATE INDEX IF NOT EXISTS dsper_epoch_index ON delegated_staking_pool_epoch_rewards (epoch);
+-- Rewards each delegator has earned on their active stake. Shares are valued at the pool's
+-- share price when they're bought, so accrued_rewards is what they earned on top of that.
+CREATE TABLE IF NOT EXISTS current_delegator_staking_rewards (
+  delegator_address VARCHAR(66) NOT NULL,
+  pool_address VARCHAR(66) NOT NULL,
+  active_shares NUMERIC NOT NULL,
+  -- Coins paid for the active shares still held
+  cost_basis NUMERIC NOT NULL,
+  -- Rewards on shares that have since been unlocked
+  realized_rewards NUMERIC NOT NULL,
+  -- realized_rewards plus the rewards on the active shares at share_price
+  accrued_rewards NUMERIC NOT NULL,
+  share_price NUMERIC NOT NULL,
+  last_transaction_version BIGINT NOT NULL,
+  -- Rewards are replayed from the delegator's whole share history, and a replay that saw more of
+  -- it wins
+  num_share_changes BIGINT NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (delegator_address, pool_address)
+);
+CREATE INDEX IF NOT EXISTS cdsr_pool_address_index ON current_delegator_staking_rewards (pool_address);
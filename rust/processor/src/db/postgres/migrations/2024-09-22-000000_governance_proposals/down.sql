@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS current_governance_proposal_tallies;
+DROP TABLE IF EXISTS governance_proposals;
@@ -0,0 +1,38 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS governance_proposals (
+  proposal_id BIGINT PRIMARY KEY NOT NULL,
+  proposer_address VARCHAR(66) NOT NULL,
+  -- Only known for proposals created after the processor started
+  stake_pool_address VARCHAR(66),
+  -- Voter that created the proposal on behalf of a delegation pool, see current_delegated_voter
+  delegated_voter_address VARCHAR(66),
+  execution_hash VARCHAR(200) NOT NULL,
+  metadata_location TEXT,
+  metadata_hash TEXT,
+  min_vote_threshold NUMERIC NOT NULL,
+  early_resolution_vote_threshold NUMERIC,
+  creation_timestamp TIMESTAMP NOT NULL,
+  expiration_timestamp TIMESTAMP NOT NULL,
+  creation_transaction_version BIGINT,
+  is_resolved BOOLEAN NOT NULL,
+  resolved_early BOOLEAN,
+  resolution_timestamp TIMESTAMP,
+  resolution_transaction_version BIGINT,
+  last_transaction_version BIGINT NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS gp_proposer_index ON governance_proposals (proposer_address);
+CREATE INDEX IF NOT EXISTS gp_stake_pool_index ON governance_proposals (stake_pool_address);
+CREATE INDEX IF NOT EXISTS gp_delegated_voter_index ON governance_proposals (delegated_voter_address);
+CREATE TABLE IF NOT EXISTS current_governance_proposal_tallies (
+  proposal_id BIGINT PRIMARY KEY NOT NULL,
+  yes_votes NUMERIC NOT NULL,
+  no_votes NUMERIC NOT NULL,
+  total_votes NUMERIC NOT NULL,
+  min_vote_threshold NUMERIC NOT NULL,
+  is_passing BOOLEAN NOT NULL,
+  is_resolved BOOLEAN NOT NULL,
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS validator_epoch_stats;
+DROP TABLE IF EXISTS epochs;
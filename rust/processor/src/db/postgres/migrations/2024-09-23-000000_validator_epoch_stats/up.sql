@@ -0,0 +1,27 @@
+-- Your SQL goes here
+-- Transactions and blocks of each epoch. The current epoch's end moves forward as it goes on.
+CREATE TABLE IF NOT EXISTS epochs (
+  epoch BIGINT PRIMARY KEY NOT NULL,
+  start_version BIGINT NOT NULL,
+  end_version BIGINT NOT NULL,
+  start_timestamp TIMESTAMP NOT NULL,
+  end_timestamp TIMESTAMP NOT NULL,
+  start_block_height BIGINT NOT NULL,
+  end_block_height BIGINT NOT NULL,
+  last_round BIGINT NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS e_start_version_index ON epochs (start_version);
+-- Proposals of each validator in an epoch, keyed by its index in the epoch's validator set
+CREATE TABLE IF NOT EXISTS validator_epoch_stats (
+  epoch BIGINT NOT NULL,
+  validator_index BIGINT NOT NULL,
+  validator_address VARCHAR(66),
+  voting_power NUMERIC,
+  successful_proposals BIGINT NOT NULL,
+  failed_proposals BIGINT NOT NULL,
+  last_transaction_version BIGINT NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (epoch, validator_index)
+);
+CREATE INDEX IF NOT EXISTS ves_validator_address_index ON validator_epoch_stats (validator_address, epoch);
@@ -9,6 +9,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    address_display_names (address) {
+        #[max_length = 66]
+        address -> Varchar,
+        #[max_length = 64]
+        domain -> Nullable<Varchar>,
+        #[max_length = 64]
+        subdomain -> Nullable<Varchar>,
+        #[max_length = 140]
+        token_name -> Nullable<Varchar>,
+        #[max_length = 10]
+        token_standard -> Nullable<Varchar>,
+        last_transaction_version -> Int8,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     ans_lookup (transaction_version, write_set_change_index) {
         transaction_version -> Int8,
@@ -48,6 +65,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    ans_name_events (transaction_version, domain, subdomain, token_standard, event_type) {
+        transaction_version -> Int8,
+        #[max_length = 64]
+        domain -> Varchar,
+        #[max_length = 64]
+        subdomain -> Varchar,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        #[max_length = 140]
+        token_name -> Varchar,
+        #[max_length = 20]
+        event_type -> Varchar,
+        #[max_length = 66]
+        registered_address -> Nullable<Varchar>,
+        expiration_timestamp -> Timestamp,
+        transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     ans_primary_name (transaction_version, write_set_change_index) {
         transaction_version -> Int8,
@@ -84,6 +122,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    backfill_status (backfill_alias) {
+        #[max_length = 100]
+        backfill_alias -> Varchar,
+        #[max_length = 50]
+        processor -> Varchar,
+        #[max_length = 50]
+        status -> Varchar,
+        last_success_version -> Int8,
+        last_updated -> Timestamp,
+        last_transaction_timestamp -> Nullable<Timestamp>,
+        backfill_start_version -> Int8,
+        backfill_end_version -> Int8,
+    }
+}
+
 diesel::table! {
     block_metadata_transactions (version) {
         version -> Int8,
@@ -266,6 +320,9 @@ diesel::table! {
         is_deleted -> Bool,
         inserted_at -> Timestamp,
         subdomain_expiration_policy -> Nullable<Int8>,
+        #[max_length = 20]
+        lifecycle_status -> Varchar,
+        is_active -> Bool,
     }
 }
 
@@ -300,6 +357,7 @@ diesel::table! {
         is_deleted -> Bool,
         last_transaction_version -> Int8,
         inserted_at -> Timestamp,
+        is_active -> Bool,
     }
 }
 
@@ -421,6 +479,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    current_delegator_staking_rewards (delegator_address, pool_address) {
+        #[max_length = 66]
+        delegator_address -> Varchar,
+        #[max_length = 66]
+        pool_address -> Varchar,
+        active_shares -> Numeric,
+        cost_basis -> Numeric,
+        realized_rewards -> Numeric,
+        accrued_rewards -> Numeric,
+        share_price -> Numeric,
+        last_transaction_version -> Int8,
+        num_share_changes -> Int8,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     current_fungible_asset_balances (storage_id) {
         #[max_length = 66]
@@ -469,6 +544,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    current_governance_proposal_tallies (proposal_id) {
+        proposal_id -> Int8,
+        yes_votes -> Numeric,
+        no_votes -> Numeric,
+        total_votes -> Numeric,
+        min_vote_threshold -> Numeric,
+        is_passing -> Bool,
+        is_resolved -> Bool,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    current_nft_points (owner_address, point_type) {
+        #[max_length = 66]
+        owner_address -> Varchar,
+        point_type -> Text,
+        total_amount -> Numeric,
+        num_transactions -> Int8,
+        last_transaction_version -> Int8,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     current_objects (object_address) {
         #[max_length = 66]
@@ -710,6 +812,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    delegated_staking_pool_epoch_rewards (staking_pool_address, epoch) {
+        #[max_length = 66]
+        staking_pool_address -> Varchar,
+        epoch -> Int8,
+        transaction_version -> Int8,
+        transaction_timestamp -> Timestamp,
+        total_coins -> Numeric,
+        total_shares -> Numeric,
+        share_price -> Numeric,
+        operator_commission_percentage -> Numeric,
+        previous_transaction_version -> Nullable<Int8>,
+        previous_transaction_timestamp -> Nullable<Timestamp>,
+        previous_share_price -> Nullable<Numeric>,
+        rewards_amount -> Nullable<Numeric>,
+        apr -> Nullable<Numeric>,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     delegated_staking_pools (staking_pool_address) {
         #[max_length = 66]
@@ -738,6 +860,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    epochs (epoch) {
+        epoch -> Int8,
+        start_version -> Int8,
+        end_version -> Int8,
+        start_timestamp -> Timestamp,
+        end_timestamp -> Timestamp,
+        start_block_height -> Int8,
+        end_block_height -> Int8,
+        last_round -> Int8,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     event_size_info (transaction_version, index) {
         transaction_version -> Int8,
@@ -763,6 +899,7 @@ diesel::table! {
         event_index -> Int8,
         #[max_length = 300]
         indexed_type -> Varchar,
+        decoded_data -> Nullable<Jsonb>,
     }
 }
 
@@ -815,6 +952,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    fungible_asset_balance_daily_snapshots (owner_address, asset_type, snapshot_date) {
+        #[max_length = 66]
+        owner_address -> Varchar,
+        #[max_length = 1000]
+        asset_type -> Varchar,
+        snapshot_date -> Date,
+        #[max_length = 66]
+        storage_id -> Varchar,
+        amount -> Numeric,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     fungible_asset_metadata (asset_type) {
         #[max_length = 1000]
@@ -844,6 +999,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    governance_proposals (proposal_id) {
+        proposal_id -> Int8,
+        #[max_length = 66]
+        proposer_address -> Varchar,
+        #[max_length = 66]
+        stake_pool_address -> Nullable<Varchar>,
+        #[max_length = 66]
+        delegated_voter_address -> Nullable<Varchar>,
+        #[max_length = 200]
+        execution_hash -> Varchar,
+        metadata_location -> Nullable<Text>,
+        metadata_hash -> Nullable<Text>,
+        min_vote_threshold -> Numeric,
+        early_resolution_vote_threshold -> Nullable<Numeric>,
+        creation_timestamp -> Timestamp,
+        expiration_timestamp -> Timestamp,
+        creation_transaction_version -> Nullable<Int8>,
+        is_resolved -> Bool,
+        resolved_early -> Nullable<Bool>,
+        resolution_timestamp -> Nullable<Timestamp>,
+        resolution_transaction_version -> Nullable<Int8>,
+        last_transaction_version -> Int8,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     indexer_status (db) {
         #[max_length = 50]
@@ -937,6 +1119,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    processor_table_status (processor, table_name) {
+        #[max_length = 50]
+        processor -> Varchar,
+        #[max_length = 100]
+        table_name -> Varchar,
+        last_success_version -> Int8,
+        last_updated -> Timestamp,
+    }
+}
+
 diesel::table! {
     proposal_votes (transaction_version, proposal_id, voter_address) {
         transaction_version -> Int8,
@@ -977,6 +1170,9 @@ diesel::table! {
         asset -> Varchar,
         is_spam -> Bool,
         last_updated -> Timestamp,
+        #[max_length = 50]
+        reason -> Nullable<Varchar>,
+        last_transaction_version -> Nullable<Int8>,
     }
 }
 
@@ -1197,6 +1393,30 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    transaction_fees (transaction_version) {
+        transaction_version -> Int8,
+        block_height -> Int8,
+        #[max_length = 66]
+        sender -> Varchar,
+        #[max_length = 66]
+        fee_payer_address -> Varchar,
+        is_sponsored -> Bool,
+        gas_unit_price -> Numeric,
+        total_charge_gas_units -> Numeric,
+        execution_gas_units -> Numeric,
+        io_gas_units -> Numeric,
+        storage_gas_units -> Numeric,
+        storage_fee_octas -> Numeric,
+        storage_fee_refund_octas -> Numeric,
+        total_fee_octas -> Numeric,
+        net_fee_octas -> Numeric,
+        is_transaction_success -> Bool,
+        transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     transaction_size_info (transaction_version) {
         transaction_version -> Int8,
@@ -1251,6 +1471,21 @@ diesel::table! {
         entry_function_id_str -> Varchar,
         inserted_at -> Timestamp,
         epoch -> Int8,
+        decoded_arguments -> Nullable<Jsonb>,
+    }
+}
+
+diesel::table! {
+    validator_epoch_stats (epoch, validator_index) {
+        epoch -> Int8,
+        validator_index -> Int8,
+        #[max_length = 66]
+        validator_address -> Nullable<Varchar>,
+        voting_power -> Nullable<Numeric>,
+        successful_proposals -> Int8,
+        failed_proposals -> Int8,
+        last_transaction_version -> Int8,
+        inserted_at -> Timestamp,
     }
 }
 
@@ -1281,10 +1516,13 @@ diesel::table! {
 
 diesel::allow_tables_to_appear_in_same_query!(
     account_transactions,
+    address_display_names,
     ans_lookup,
     ans_lookup_v2,
+    ans_name_events,
     ans_primary_name,
     ans_primary_name_v2,
+    backfill_status,
     block_metadata_transactions,
     coin_activities,
     coin_balances,
@@ -1302,8 +1540,11 @@ diesel::allow_tables_to_appear_in_same_query!(
     current_delegated_staking_pool_balances,
     current_delegated_voter,
     current_delegator_balances,
+    current_delegator_staking_rewards,
     current_fungible_asset_balances,
     current_fungible_asset_balances_legacy,
+    current_governance_proposal_tallies,
+    current_nft_points,
     current_objects,
     current_staking_pool_voter,
     current_table_items,
@@ -1316,13 +1557,17 @@ diesel::allow_tables_to_appear_in_same_query!(
     current_token_v2_metadata,
     delegated_staking_activities,
     delegated_staking_pool_balances,
+    delegated_staking_pool_epoch_rewards,
     delegated_staking_pools,
     delegator_balances,
+    epochs,
     event_size_info,
     events,
     fungible_asset_activities,
+    fungible_asset_balance_daily_snapshots,
     fungible_asset_balances,
     fungible_asset_metadata,
+    governance_proposals,
     indexer_status,
     ledger_infos,
     move_modules,
@@ -1330,6 +1575,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     nft_points,
     objects,
     processor_status,
+    processor_table_status,
     proposal_votes,
     signatures,
     spam_assets,
@@ -1342,9 +1588,11 @@ diesel::allow_tables_to_appear_in_same_query!(
     token_ownerships,
     token_ownerships_v2,
     tokens,
+    transaction_fees,
     transaction_size_info,
     transactions,
     user_transactions,
+    validator_epoch_stats,
     write_set_changes,
     write_set_size_info,
 );
@@ -26,7 +26,10 @@ impl GapDetectorTrait for DefaultGapDetector {
         match result {
             ProcessingResult::DefaultProcessingResult(result) => {
                 // Check for gaps
-                if self.next_version_to_process != result.start_version {
+                if result.start_version < self.next_version_to_process {
+                    // Already covered, e.g. by a gap repair that finished before this batch
+                    tracing::debug!("Batch already processed");
+                } else if self.next_version_to_process != result.start_version {
                     self.seen_versions.insert(result.start_version, result);
                     tracing::debug!("Gap detected");
                 } else {
@@ -48,6 +51,13 @@ impl GapDetectorTrait for DefaultGapDetector {
             },
         }
     }
+
+    fn missing_version_range(&self) -> Option<(u64, u64)> {
+        self.seen_versions
+            .keys()
+            .min()
+            .map(|first_seen_version| (self.next_version_to_process, first_seen_version - 1))
+    }
 }
 
 impl DefaultGapDetector {
@@ -140,4 +150,59 @@ mod test {
             199 + (DEFAULT_GAP_DETECTION_BATCH_SIZE - 1) * 100
         );
     }
+
+    #[tokio::test]
+    async fn missing_version_range_test() {
+        let mut default_gap_detector = DefaultGapDetector::new(0);
+        assert_eq!(default_gap_detector.missing_version_range(), None);
+
+        for (start_version, end_version) in [(200, 299), (100, 199)] {
+            default_gap_detector
+                .process_versions(ProcessingResult::DefaultProcessingResult(
+                    DefaultProcessingResult {
+                        start_version,
+                        end_version,
+                        last_transaction_timestamp: None,
+                        processing_duration_in_secs: 0.0,
+                        db_insertion_duration_in_secs: 0.0,
+                    },
+                ))
+                .unwrap();
+        }
+        assert_eq!(default_gap_detector.missing_version_range(), Some((0, 99)));
+
+        // Repairing the gap advances past the batches that were waiting on it
+        default_gap_detector
+            .process_versions(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version: 0,
+                    end_version: 99,
+                    last_transaction_timestamp: None,
+                    processing_duration_in_secs: 0.0,
+                    db_insertion_duration_in_secs: 0.0,
+                },
+            ))
+            .unwrap();
+        assert_eq!(default_gap_detector.missing_version_range(), None);
+
+        // A late duplicate of a repaired batch isn't counted as a gap
+        let res = default_gap_detector
+            .process_versions(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version: 0,
+                    end_version: 99,
+                    last_transaction_timestamp: None,
+                    processing_duration_in_secs: 0.0,
+                    db_insertion_duration_in_secs: 0.0,
+                },
+            ))
+            .unwrap();
+        match res {
+            GapDetectorResult::DefaultGapDetectorResult(res) => {
+                assert_eq!(res.num_gaps, 0);
+                assert_eq!(res.next_version_to_process, 300);
+            },
+            _ => panic!("Invalid result type"),
+        }
+    }
 }
@@ -0,0 +1,346 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Repairs gaps found by the gap detector by re-fetching the missing version range from the
+//! data service and reprocessing it, instead of waiting for everything downstream to panic.
+//!
+//! Repairs run in their own task so the gap detector keeps tracking results and updating the
+//! processor status meanwhile. Repaired batches reach the gap detector like any other batch.
+
+use crate::{
+    config::IndexerGrpcHttp2Config,
+    gap_detectors::{GapDetector, GapDetectorTrait, ProcessingResult},
+    grpc_stream::try_get_stream,
+    local_stream::build_batches,
+    processors::{Processor, ProcessorTrait},
+    transaction_filter::TransactionFilter,
+    utils::{counters::PROCESSOR_GAP_REPAIR_COUNT, write_context::WriteMode},
+    worker::{do_processor, PROCESSOR_SERVICE_TYPE},
+};
+use ahash::AHashMap;
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_protos::transaction::v1::Transaction;
+use futures_util::StreamExt;
+use kanal::AsyncSender;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use url::Url;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GapRepairConfig {
+    // Number of consecutive failed repairs before the gap detector gives up
+    #[serde(default = "GapRepairConfig::default_max_repair_attempts")]
+    pub max_repair_attempts: u64,
+    // Minimum time between two repair attempts
+    #[serde(default = "GapRepairConfig::default_retry_delay_secs")]
+    pub retry_delay_secs: u64,
+}
+
+impl GapRepairConfig {
+    pub const fn default_max_repair_attempts() -> u64 {
+        3
+    }
+
+    pub const fn default_retry_delay_secs() -> u64 {
+        10
+    }
+}
+
+pub struct GapRepairer {
+    config: GapRepairConfig,
+    source: Arc<RepairSource>,
+    failed_attempts: u64,
+    last_attempt_time: Option<Instant>,
+    running_repair: Option<RunningRepair>,
+}
+
+/// Where repairs re-fetch transactions from and where their results go
+struct RepairSource {
+    indexer_grpc_data_service_address: Url,
+    grpc_http2_config: IndexerGrpcHttp2Config,
+    grpc_response_item_timeout: Duration,
+    auth_token: String,
+    chain_id: u64,
+    transaction_filter: TransactionFilter,
+    pb_channel_txn_chunk_size: usize,
+    write_mode: WriteMode,
+    gap_detector_sender: AsyncSender<ProcessingResult>,
+}
+
+struct RunningRepair {
+    start_version: u64,
+    end_version: u64,
+    task: JoinHandle<Result<()>>,
+}
+
+impl GapRepairer {
+    pub fn new(
+        config: GapRepairConfig,
+        indexer_grpc_data_service_address: Url,
+        grpc_http2_config: IndexerGrpcHttp2Config,
+        grpc_response_item_timeout: Duration,
+        auth_token: String,
+        chain_id: u64,
+        transaction_filter: TransactionFilter,
+        pb_channel_txn_chunk_size: usize,
+        write_mode: WriteMode,
+        gap_detector_sender: AsyncSender<ProcessingResult>,
+    ) -> Self {
+        Self {
+            config,
+            source: Arc::new(RepairSource {
+                indexer_grpc_data_service_address,
+                grpc_http2_config,
+                grpc_response_item_timeout,
+                auth_token,
+                chain_id,
+                transaction_filter,
+                pb_channel_txn_chunk_size,
+                write_mode,
+                gap_detector_sender,
+            }),
+            failed_attempts: 0,
+            last_attempt_time: None,
+            running_repair: None,
+        }
+    }
+
+    /// Starts re-fetching and reprocessing the first missing version range of the gap detector,
+    /// if any, unless a repair is already running or the last attempt was too recent.
+    pub fn start_repair(&mut self, processor: &Arc<Processor>, gap_detector: &GapDetector) {
+        if self.running_repair.is_some()
+            || self
+                .last_attempt_time
+                .is_some_and(|t| t.elapsed() < Duration::from_secs(self.config.retry_delay_secs))
+        {
+            return;
+        }
+        let Some((start_version, end_version)) = gap_detector.missing_version_range() else {
+            return;
+        };
+        self.last_attempt_time = Some(Instant::now());
+
+        info!(
+            processor_name = processor.name(),
+            service_type = PROCESSOR_SERVICE_TYPE,
+            start_version,
+            end_version,
+            attempt = self.failed_attempts + 1,
+            "[Parser] Repairing gap"
+        );
+        let source = self.source.clone();
+        let processor = processor.clone();
+        let gap_detector = gap_detector.clone();
+        let task = tokio::spawn(async move {
+            source
+                .fetch_and_process(&processor, &gap_detector, start_version, end_version)
+                .await
+        });
+        self.running_repair = Some(RunningRepair {
+            start_version,
+            end_version,
+            task,
+        });
+    }
+
+    pub fn is_repairing(&self) -> bool {
+        self.running_repair.is_some()
+    }
+
+    /// Waits for the running repair to finish, forever if there is none. Errors once
+    /// `max_repair_attempts` consecutive repairs have failed.
+    pub async fn wait_for_repair(&mut self, processor_name: &str) -> Result<()> {
+        let Some(running_repair) = self.running_repair.as_mut() else {
+            return std::future::pending().await;
+        };
+        let result = (&mut running_repair.task)
+            .await
+            .unwrap_or_else(|e| Err(anyhow!("Gap repair task has died: {:?}", e)));
+        let RunningRepair {
+            start_version,
+            end_version,
+            ..
+        } = self.running_repair.take().unwrap();
+
+        match result {
+            Ok(()) => {
+                PROCESSOR_GAP_REPAIR_COUNT
+                    .with_label_values(&[processor_name, "success"])
+                    .inc();
+                info!(
+                    processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    start_version,
+                    end_version,
+                    "[Parser] Gap repaired"
+                );
+                self.failed_attempts = 0;
+                Ok(())
+            },
+            Err(e) => {
+                PROCESSOR_GAP_REPAIR_COUNT
+                    .with_label_values(&[processor_name, "failure"])
+                    .inc();
+                self.failed_attempts += 1;
+                error!(
+                    processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    start_version,
+                    end_version,
+                    failed_attempts = self.failed_attempts,
+                    error = ?e,
+                    "[Parser] Failed to repair gap"
+                );
+                if self.failed_attempts >= self.config.max_repair_attempts {
+                    return Err(e.context(format!(
+                        "Failed to repair gap [{}, {}] after {} attempts",
+                        start_version, end_version, self.failed_attempts
+                    )));
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl RepairSource {
+    async fn fetch_and_process(
+        &self,
+        processor: &Processor,
+        gap_detector: &GapDetector,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<()> {
+        let processor_name = processor.name();
+        let response = try_get_stream(
+            self.indexer_grpc_data_service_address.clone(),
+            self.grpc_http2_config.grpc_http2_ping_interval_in_secs(),
+            self.grpc_http2_config.grpc_http2_ping_timeout_in_secs(),
+            self.grpc_http2_config.grpc_connection_timeout_secs(),
+            start_version,
+            Some(end_version),
+            self.auth_token.clone(),
+            processor_name.to_string(),
+        )
+        .await
+        .context("Failed to connect to the data service")?;
+
+        // Process the range in batches as it streams in rather than holding all of it
+        let chunk_size = self.pb_channel_txn_chunk_size.max(1);
+        let mut stream = response.into_inner();
+        let mut transactions = vec![];
+        let mut next_version = start_version;
+        let mut last_version = None;
+        while let Some(response) =
+            tokio::time::timeout(self.grpc_response_item_timeout, stream.next())
+                .await
+                .context("Timed out waiting for the data service")?
+        {
+            let response = response.context("Error in the GRPC stream")?;
+            if response.chain_id != Some(self.chain_id) {
+                bail!(
+                    "Data service returned chain id {:?}, expected {}",
+                    response.chain_id,
+                    self.chain_id
+                );
+            }
+            transactions.extend(
+                response
+                    .transactions
+                    .into_iter()
+                    .filter(|txn| txn.version <= end_version),
+            );
+            last_version = transactions.last().map(|txn| txn.version).or(last_version);
+            while transactions.len() >= chunk_size {
+                let rest = transactions.split_off(chunk_size);
+                let chunk = std::mem::replace(&mut transactions, rest);
+                next_version = self
+                    .process_transactions(processor, gap_detector, chunk, next_version)
+                    .await?;
+            }
+        }
+        if last_version != Some(end_version) {
+            bail!(
+                "Data service stream ended at version {:?}, expected {}",
+                last_version,
+                end_version
+            );
+        }
+        if !transactions.is_empty() {
+            self.process_transactions(processor, gap_detector, transactions, next_version)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Filters and processes `transactions`, which start at `start_version`, and reports the
+    /// results to the gap detector the way processor tasks do. Returns the version following
+    /// the last transaction.
+    async fn process_transactions(
+        &self,
+        processor: &Processor,
+        gap_detector: &GapDetector,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+    ) -> Result<u64> {
+        let processor_name = processor.name();
+        let mut next_version = start_version;
+        for transactions_pb in build_batches(
+            transactions,
+            self.chain_id,
+            start_version,
+            &self.transaction_filter,
+            self.pb_channel_txn_chunk_size,
+        ) {
+            let batch_start_version = transactions_pb.start_version as i64;
+            let batch_end_version = transactions_pb.end_version as i64;
+            next_version = transactions_pb.end_version + 1;
+            let result = do_processor(
+                transactions_pb,
+                processor,
+                self.chain_id,
+                processor_name,
+                &self.auth_token,
+                false,
+                self.write_mode.clone(),
+            )
+            .await?;
+
+            match gap_detector {
+                GapDetector::DefaultGapDetector(_) => match result {
+                    ProcessingResult::DefaultProcessingResult(_) => {
+                        self.gap_detector_sender
+                            .send(result)
+                            .await
+                            .context("Failed to send versions to gap detector")?;
+                    },
+                    ProcessingResult::ParquetProcessingResult(_) => {
+                        bail!("Parquet processing result sent to the default gap detector");
+                    },
+                },
+                GapDetector::ParquetFileGapDetector(parquet_gap_detector) => {
+                    // Batches where every transaction was filtered out come back as default
+                    // results and have no structs to wait for
+                    let txn_version_to_struct_count = match result {
+                        ProcessingResult::ParquetProcessingResult(result) => {
+                            result.txn_version_to_struct_count.unwrap_or_default()
+                        },
+                        ProcessingResult::DefaultProcessingResult(_) => AHashMap::new(),
+                    };
+                    parquet_gap_detector.lock().unwrap().update_struct_map(
+                        txn_version_to_struct_count,
+                        batch_start_version,
+                        batch_end_version,
+                    );
+                },
+            }
+        }
+        Ok(next_version)
+    }
+}
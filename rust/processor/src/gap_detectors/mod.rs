@@ -1,7 +1,9 @@
 use crate::{
     bq_analytics::ParquetProcessingResult,
+    db::common::models::backfill_status::BackfillJob,
     gap_detectors::{
         gap_detector::{DefaultGapDetector, DefaultGapDetectorResult},
+        gap_repair::GapRepairer,
         parquet_gap_detector::{ParquetFileGapDetectorInner, ParquetFileGapDetectorResult},
     },
     processors::{DefaultProcessingResult, Processor, ProcessorTrait},
@@ -12,8 +14,10 @@ use anyhow::Result;
 use enum_dispatch::enum_dispatch;
 use kanal::AsyncReceiver;
 use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
 
 pub mod gap_detector;
+pub mod gap_repair;
 pub mod parquet_gap_detector;
 
 // Size of a gap (in txn version) before gap detected
@@ -48,6 +52,10 @@ pub enum GapDetectorResult {
 #[enum_dispatch]
 pub trait GapDetectorTrait: Send {
     fn process_versions(&mut self, result: ProcessingResult) -> Result<GapDetectorResult>;
+
+    /// The first range of versions that hasn't been processed while later versions have,
+    /// as an inclusive (start, end). Used to repair gaps.
+    fn missing_version_range(&self) -> Option<(u64, u64)>;
 }
 
 #[derive(Debug, Clone)]
@@ -56,12 +64,18 @@ pub enum ProcessingResult {
     ParquetProcessingResult(ParquetProcessingResult),
 }
 
+/// Tracks processing results until `input_closed` fires, i.e. every processor task is done. The
+/// results still queued and the running gap repair are waited for, and the last contiguous
+/// version written before returning. Errors if gaps can't be repaired.
 pub async fn create_gap_detector_status_tracker_loop(
     mut gap_detector: GapDetector,
     gap_detector_receiver: AsyncReceiver<ProcessingResult>,
-    processor: Processor,
+    mut input_closed: oneshot::Receiver<()>,
+    processor: Arc<Processor>,
     gap_detection_batch_size: u64,
-) {
+    mut gap_repairer: Option<GapRepairer>,
+    backfill_job: Option<BackfillJob>,
+) -> Result<()> {
     let processor_name = processor.name();
     tracing::info!(
         processor_name = processor_name,
@@ -70,8 +84,47 @@ pub async fn create_gap_detector_status_tracker_loop(
     );
 
     let mut last_update_time = std::time::Instant::now();
+    // Latest contiguous version not yet written to processor_status, flushed when the channel closes
+    let mut pending_update: Option<(u64, Option<aptos_protos::util::timestamp::Timestamp>)> = None;
+    let mut is_input_closed = false;
+    let mut is_processor_flushed = false;
     loop {
-        match gap_detector_receiver.recv().await {
+        let is_repairing = gap_repairer.as_ref().is_some_and(GapRepairer::is_repairing);
+        if is_input_closed && !is_repairing && gap_detector_receiver.is_empty() {
+            if !is_processor_flushed {
+                // Repaired parquet structs are still buffered in this processor's handlers, and
+                // their uploads are reported back to this channel
+                processor
+                    .flush()
+                    .await
+                    .expect("[Parser] Failed to flush gap repairs");
+                is_processor_flushed = true;
+                continue;
+            }
+            // Nothing is sent anymore, closing makes recv return an error so the loop exits
+            gap_detector_receiver.close();
+        }
+        let result = tokio::select! {
+            biased;
+            repair = wait_for_repair(gap_repairer.as_mut(), processor_name) => {
+                if let Err(e) = repair {
+                    tracing::error!(
+                        processor_name,
+                        service_type = PROCESSOR_SERVICE_TYPE,
+                        error = ?e,
+                        "[Parser] Giving up on repairing gaps"
+                    );
+                    return Err(e);
+                }
+                continue;
+            },
+            result = gap_detector_receiver.recv() => result,
+            _ = &mut input_closed, if !is_input_closed => {
+                is_input_closed = true;
+                continue;
+            },
+        };
+        match result {
             Ok(ProcessingResult::DefaultProcessingResult(result)) => {
                 match gap_detector
                     .process_versions(ProcessingResult::DefaultProcessingResult(result))
@@ -90,21 +143,34 @@ pub async fn create_gap_detector_status_tracker_loop(
                                     "[Parser] Processed {gap_detection_batch_size} batches with a gap",
                                 );
                                     // We don't panic as everything downstream will panic if it doesn't work/receive
+                                    // Repaired batches come back through this channel
+                                    if let Some(gap_repairer) =
+                                        gap_repairer.as_mut().filter(|_| !is_input_closed)
+                                    {
+                                        gap_repairer.start_repair(&processor, &gap_detector);
+                                    }
                                 }
                                 if let Some(res_last_success_batch) = res.last_success_batch {
                                     if last_update_time.elapsed().as_secs()
                                         >= UPDATE_PROCESSOR_STATUS_SECS
                                     {
-                                        processor
-                                            .update_last_processed_version(
-                                                res_last_success_batch.end_version,
-                                                res_last_success_batch
-                                                    .last_transaction_timestamp
-                                                    .clone(),
-                                            )
-                                            .await
-                                            .unwrap();
+                                        update_status(
+                                            &processor,
+                                            backfill_job.as_ref(),
+                                            res_last_success_batch.end_version,
+                                            res_last_success_batch
+                                                .last_transaction_timestamp
+                                                .clone(),
+                                        )
+                                        .await
+                                        .unwrap();
                                         last_update_time = std::time::Instant::now();
+                                        pending_update = None;
+                                    } else {
+                                        pending_update = Some((
+                                            res_last_success_batch.end_version,
+                                            res_last_success_batch.last_transaction_timestamp,
+                                        ));
                                     }
                                 }
                             },
@@ -149,6 +215,12 @@ pub async fn create_gap_detector_status_tracker_loop(
                                         "[Parser] Processed batches with a gap",
                                     );
                                     // We don't panic as everything downstream will panic if it doesn't work/receive
+                                    // Repaired structs reach the gap detector once they're uploaded
+                                    if let Some(gap_repairer) =
+                                        gap_repairer.as_mut().filter(|_| !is_input_closed)
+                                    {
+                                        gap_repairer.start_repair(&processor, &gap_detector);
+                                    }
                                 }
 
                                 if last_update_time.elapsed().as_secs()
@@ -159,16 +231,22 @@ pub async fn create_gap_detector_status_tracker_loop(
                                         processor_name,
                                         "Updating last processed version"
                                     );
-                                    processor
-                                        .update_last_processed_version(
-                                            res.last_success_version,
-                                            res.last_transaction_timestamp,
-                                        )
-                                        .await
-                                        .unwrap();
+                                    update_status(
+                                        &processor,
+                                        backfill_job.as_ref(),
+                                        res.last_success_version,
+                                        res.last_transaction_timestamp,
+                                    )
+                                    .await
+                                    .unwrap();
                                     last_update_time = std::time::Instant::now();
+                                    pending_update = None;
                                 } else {
                                     tracing::info!("Not Updating last processed version");
+                                    pending_update = Some((
+                                        res.last_success_version,
+                                        res.last_transaction_timestamp,
+                                    ));
                                 }
                             },
                             _ => {
@@ -194,8 +272,57 @@ pub async fn create_gap_detector_status_tracker_loop(
                     error = ?e,
                     "[Parser] Gap detector channel has been closed",
                 );
-                return;
+                if let Some((last_success_version, last_transaction_timestamp)) = pending_update {
+                    tracing::info!(
+                        processor_name,
+                        service_type = PROCESSOR_SERVICE_TYPE,
+                        last_success_version,
+                        "[Parser] Flushing last processed version before exiting",
+                    );
+                    update_status(
+                        &processor,
+                        backfill_job.as_ref(),
+                        last_success_version,
+                        last_transaction_timestamp,
+                    )
+                    .await
+                    .unwrap();
+                }
+                return Ok(());
             },
         };
     }
 }
+
+/// Resolves once the running gap repair finishes, never if gaps aren't repaired
+async fn wait_for_repair(
+    gap_repairer: Option<&mut GapRepairer>,
+    processor_name: &str,
+) -> Result<()> {
+    match gap_repairer {
+        Some(gap_repairer) => gap_repairer.wait_for_repair(processor_name).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Backfills track their progress in backfill_status so they don't move the live processor's
+/// checkpoint
+async fn update_status(
+    processor: &Processor,
+    backfill_job: Option<&BackfillJob>,
+    version: u64,
+    last_transaction_timestamp: Option<aptos_protos::util::timestamp::Timestamp>,
+) -> Result<()> {
+    match backfill_job {
+        Some(backfill_job) => {
+            processor
+                .update_last_backfilled_version(backfill_job, version, last_transaction_timestamp)
+                .await
+        },
+        None => {
+            processor
+                .update_last_processed_version(version, last_transaction_timestamp)
+                .await
+        },
+    }
+}
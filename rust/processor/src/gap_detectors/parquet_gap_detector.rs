@@ -15,6 +15,10 @@ impl GapDetectorTrait for Arc<Mutex<ParquetFileGapDetectorInner>> {
         let mut detector = self.lock().unwrap();
         detector.process_versions(result)
     }
+
+    fn missing_version_range(&self) -> Option<(u64, u64)> {
+        self.lock().unwrap().missing_version_range()
+    }
 }
 
 #[derive(Clone)]
@@ -170,4 +174,19 @@ impl GapDetectorTrait for ParquetFileGapDetectorInner {
             },
         ))
     }
+
+    /// Versions that were never handed to the gap detector at all. Versions still waiting on
+    /// a parquet upload have a struct count, so they aren't reported.
+    fn missing_version_range(&self) -> Option<(u64, u64)> {
+        let is_known = |version: &i64| {
+            self.version_counters.contains_key(version) || self.seen_versions.contains(version)
+        };
+        let start_version = self.next_version_to_process;
+        if is_known(&start_version) {
+            return None;
+        }
+        (start_version + 1..=self.max_version)
+            .find(is_known)
+            .map(|next_known_version| (start_version as u64, next_known_version as u64 - 1))
+    }
 }
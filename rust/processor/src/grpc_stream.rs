@@ -6,6 +6,7 @@ use crate::utils::{
     },
     util::{timestamp_to_iso, timestamp_to_unixtime},
 };
+use anyhow::{bail, Context};
 use aptos_moving_average::MovingAverage;
 use aptos_protos::{
     indexer::v1::{raw_data_client::RawDataClient, GetTransactionsRequest, TransactionsResponse},
@@ -80,6 +81,32 @@ pub async fn get_stream(
     auth_token: String,
     processor_name: String,
 ) -> Response<Streaming<TransactionsResponse>> {
+    try_get_stream(
+        indexer_grpc_data_service_address,
+        indexer_grpc_http2_ping_interval,
+        indexer_grpc_http2_ping_timeout,
+        indexer_grpc_reconnection_timeout_secs,
+        starting_version,
+        ending_version,
+        auth_token,
+        processor_name,
+    )
+    .await
+    .unwrap_or_else(|e| panic!("{:#}", e))
+}
+
+/// Same as `get_stream`, but returns an error instead of panicking when we can't connect, for
+/// callers that can recover from it.
+pub async fn try_get_stream(
+    indexer_grpc_data_service_address: Url,
+    indexer_grpc_http2_ping_interval: Duration,
+    indexer_grpc_http2_ping_timeout: Duration,
+    indexer_grpc_reconnection_timeout_secs: Duration,
+    starting_version: u64,
+    ending_version: Option<u64>,
+    auth_token: String,
+    processor_name: String,
+) -> anyhow::Result<Response<Streaming<TransactionsResponse>>> {
     info!(
         processor_name = processor_name,
         service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
@@ -92,9 +119,9 @@ pub async fn get_stream(
     let channel = tonic::transport::Channel::from_shared(
         indexer_grpc_data_service_address.to_string(),
     )
-    .expect(
+    .context(
         "[Parser] Failed to build GRPC channel, perhaps because the data service URL is invalid",
-    )
+    )?
     .http2_keep_alive_interval(indexer_grpc_http2_ping_interval)
     .keep_alive_timeout(indexer_grpc_http2_ping_timeout);
 
@@ -103,7 +130,7 @@ pub async fn get_stream(
         let config = tonic::transport::channel::ClientTlsConfig::new();
         channel
             .tls_config(config)
-            .expect("[Parser] Failed to create TLS config")
+            .context("[Parser] Failed to create TLS config")?
     } else {
         channel
     };
@@ -146,7 +173,7 @@ pub async fn get_stream(
             },
         }
     }
-    .expect("[Parser] Timeout connecting to GRPC server");
+    .context("[Parser] Timeout connecting to GRPC server")?;
 
     let mut rpc_client = match connect_res {
         Ok(client) => client
@@ -165,7 +192,7 @@ pub async fn get_stream(
                 error = ?e,
                 "[Parser] Error connecting to GRPC client"
             );
-            panic!("[Parser] Error connecting to GRPC client");
+            bail!("[Parser] Error connecting to GRPC client: {}", e);
         },
     };
     let count = ending_version.map(|v| (v as i64 - starting_version as i64 + 1) as u64);
@@ -213,10 +240,10 @@ pub async fn get_stream(
             },
         }
     }
-    .expect("[Parser] Timed out making grpc request after max retries.");
+    .context("[Parser] Timed out making grpc request after max retries.")?;
 
     match stream_res {
-        Ok(stream) => stream,
+        Ok(stream) => Ok(stream),
         Err(e) => {
             error!(
                 processor_name = processor_name,
@@ -227,7 +254,10 @@ pub async fn get_stream(
                 error = ?e,
                 "[Parser] Failed to get grpc response. Is the server running?"
             );
-            panic!("[Parser] Failed to get grpc response. Is the server running?");
+            bail!(
+                "[Parser] Failed to get grpc response. Is the server running? {}",
+                e
+            );
         },
     }
 }
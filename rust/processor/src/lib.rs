@@ -16,13 +16,14 @@ extern crate canonical_json;
 extern crate parquet;
 extern crate parquet_derive;
 
-pub use config::IndexerGrpcProcessorConfig;
+pub use config::{IndexerGrpcProcessorConfig, TransactionSourceConfig};
 
 pub mod bq_analytics;
 mod config;
 pub mod db;
 pub mod gap_detectors;
 pub mod grpc_stream;
+pub mod local_stream;
 pub mod processors;
 #[path = "db/postgres/schema.rs"]
 pub mod schema;
@@ -0,0 +1,393 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline transaction source that replays transactions from a local directory instead of
+//! the GRPC data service. Files are either JSON encoded `Transaction`s (the same format as
+//! `testing-transactions/json_transactions`, or a JSON array of them) or length-delimited
+//! protobuf `Transaction` messages.
+
+use crate::{
+    grpc_stream::TransactionsPBResponse,
+    transaction_filter::TransactionFilter,
+    utils::counters::{
+        ProcessorStep, FETCHER_THREAD_CHANNEL_SIZE, LATEST_PROCESSED_VERSION,
+        NUM_TRANSACTIONS_FILTERED_OUT_COUNT, NUM_TRANSACTIONS_PROCESSED_COUNT,
+        PROCESSED_BYTES_COUNT,
+    },
+};
+use anyhow::{Context, Result};
+use aptos_protos::transaction::v1::Transaction;
+use kanal::AsyncSender;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+const JSON_FILE_EXTENSION: &str = "json";
+const PROTOBUF_FILE_EXTENSIONS: [&str; 2] = ["pb", "bin"];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalFileStreamConfig {
+    // Directory containing the transaction files
+    pub path: PathBuf,
+    // Chain id of the archived transactions, since there is no data service to ask
+    pub chain_id: u64,
+}
+
+/// Reads all transactions from a single file. `.json` files may contain either a single
+/// transaction or an array of transactions, `.pb`/`.bin` files contain length-delimited
+/// protobuf messages. Files with any other extension are ignored.
+pub fn read_transactions_from_file(path: &Path) -> Result<Vec<Transaction>> {
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    if extension == JSON_FILE_EXTENSION {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read transaction file {:?}", path))?;
+        match serde_json::from_slice::<Vec<Transaction>>(&bytes) {
+            Ok(transactions) => Ok(transactions),
+            Err(_) => serde_json::from_slice::<Transaction>(&bytes)
+                .map(|transaction| vec![transaction])
+                .with_context(|| format!("Failed to parse JSON transaction file {:?}", path)),
+        }
+    } else if PROTOBUF_FILE_EXTENSIONS.contains(&extension) {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read transaction file {:?}", path))?;
+        let mut buf = bytes.as_slice();
+        let mut transactions = vec![];
+        while !buf.is_empty() {
+            let transaction =
+                Transaction::decode_length_delimited(&mut buf).with_context(|| {
+                    format!("Failed to decode protobuf transaction file {:?}", path)
+                })?;
+            transactions.push(transaction);
+        }
+        Ok(transactions)
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Lists the files in `dir` sorted by name, which is the order they're replayed in
+pub fn list_transaction_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read transaction directory {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("Failed to read transaction directory {:?}", dir))?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    Ok(paths)
+}
+
+/// Splits sorted transactions into batches of at most `pb_channel_txn_chunk_size` and applies
+/// the filter. Versions missing from the archive are folded into the next batch's range, the
+/// same way filtered out transactions are, so the gap detector sees a contiguous stream.
+pub fn build_batches(
+    transactions: Vec<Transaction>,
+    chain_id: u64,
+    starting_version: u64,
+    transaction_filter: &TransactionFilter,
+    pb_channel_txn_chunk_size: usize,
+) -> Vec<TransactionsPBResponse> {
+    let mut next_version = starting_version;
+    let mut batches = vec![];
+    let mut transactions = transactions.into_iter().peekable();
+    while transactions.peek().is_some() {
+        let chunk: Vec<Transaction> = transactions
+            .by_ref()
+            .take(pb_channel_txn_chunk_size.max(1))
+            .collect();
+        let start_txn_timestamp = chunk.first().unwrap().timestamp.clone();
+        let end_txn_timestamp = chunk.last().unwrap().timestamp.clone();
+        let end_version = chunk.last().unwrap().version;
+        let size_in_bytes = chunk.iter().map(|txn| txn.encoded_len() as u64).sum();
+        let transactions = chunk
+            .into_iter()
+            .filter(|txn| transaction_filter.include(txn))
+            .collect();
+        batches.push(TransactionsPBResponse {
+            transactions,
+            chain_id,
+            start_version: next_version,
+            end_version,
+            start_txn_timestamp,
+            end_txn_timestamp,
+            size_in_bytes,
+        });
+        next_version = end_version + 1;
+    }
+    batches
+}
+
+/// Replays the transactions archived in the configured directory into the channel, mirroring
+/// `grpc_stream::create_fetcher_loop`. Once every batch is sent, or a file can't be read, we
+/// return, which drops the sender and closes the channel. The processor tasks then drain what's
+/// left in it and exit.
+pub async fn create_local_fetcher_loop(
+    txn_sender: AsyncSender<TransactionsPBResponse>,
+    config: LocalFileStreamConfig,
+    starting_version: u64,
+    request_ending_version: Option<u64>,
+    processor_name: String,
+    transaction_filter: TransactionFilter,
+    pb_channel_txn_chunk_size: usize,
+) {
+    info!(
+        processor_name = processor_name,
+        service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+        path = ?config.path,
+        start_version = starting_version,
+        end_version = request_ending_version,
+        "[Parser] Replaying transactions from local directory",
+    );
+    let mut fetcher = LocalFetcher {
+        txn_sender,
+        chain_id: config.chain_id,
+        processor_name: processor_name.clone(),
+        transaction_filter,
+        pb_channel_txn_chunk_size: pb_channel_txn_chunk_size.max(1),
+        next_version: starting_version,
+        pending: vec![],
+    };
+    match fetcher
+        .replay(&config.path, starting_version, request_ending_version)
+        .await
+    {
+        Ok(()) if fetcher.next_version == starting_version => warn!(
+            processor_name = processor_name,
+            service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+            path = ?config.path,
+            start_version = starting_version,
+            end_version = request_ending_version,
+            "[Parser] No transactions found in the requested range"
+        ),
+        Ok(()) => info!(
+            processor_name = processor_name,
+            service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+            path = ?config.path,
+            end_version = fetcher.next_version - 1,
+            "[Parser] Finished replaying local transactions."
+        ),
+        Err(e) => error!(
+            processor_name = processor_name,
+            service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+            path = ?config.path,
+            next_version = fetcher.next_version,
+            error = ?e,
+            "[Parser] Stopped replaying local transactions"
+        ),
+    }
+}
+
+/// Streams the archive one file at a time, so only the current file and the batch being built
+/// are held in memory
+struct LocalFetcher {
+    txn_sender: AsyncSender<TransactionsPBResponse>,
+    chain_id: u64,
+    processor_name: String,
+    transaction_filter: TransactionFilter,
+    pb_channel_txn_chunk_size: usize,
+    // First version of the next batch sent
+    next_version: u64,
+    pending: Vec<Transaction>,
+}
+
+impl LocalFetcher {
+    /// Sends the transactions within [starting_version, ending_version] of every file in `dir`.
+    /// Files are read in name order and have to be in version order too: a transaction at or
+    /// below one already read, e.g. a duplicate, is skipped. Stops at the first file that can't
+    /// be read.
+    async fn replay(
+        &mut self,
+        dir: &Path,
+        starting_version: u64,
+        ending_version: Option<u64>,
+    ) -> Result<()> {
+        for path in list_transaction_files(dir)? {
+            let mut transactions = match read_transactions_from_file(&path) {
+                Ok(transactions) => transactions,
+                Err(e) => {
+                    // Everything before this file is still contiguous, so it's processed and a
+                    // rerun can resume from there
+                    self.send_pending().await?;
+                    return Err(e);
+                },
+            };
+            transactions.retain(|txn| {
+                txn.version >= starting_version && ending_version.map_or(true, |v| txn.version <= v)
+            });
+            transactions.sort_by_key(|txn| txn.version);
+            let mut num_skipped = 0;
+            for txn in transactions {
+                let min_version = self
+                    .pending
+                    .last()
+                    .map_or(self.next_version, |last| last.version + 1);
+                if txn.version < min_version {
+                    num_skipped += 1;
+                    continue;
+                }
+                self.pending.push(txn);
+                if self.pending.len() >= self.pb_channel_txn_chunk_size {
+                    self.send_pending().await?;
+                }
+            }
+            if num_skipped > 0 {
+                warn!(
+                    processor_name = self.processor_name,
+                    service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+                    path = ?path,
+                    num_skipped,
+                    "[Parser] Skipped transactions at or below versions already replayed"
+                );
+            }
+        }
+        self.send_pending().await
+    }
+
+    async fn send_pending(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let step = ProcessorStep::ReceivedTxnsFromGrpc.get_step();
+        let label = ProcessorStep::ReceivedTxnsFromGrpc.get_label();
+        for txn_pb in build_batches(
+            std::mem::take(&mut self.pending),
+            self.chain_id,
+            self.next_version,
+            &self.transaction_filter,
+            self.pb_channel_txn_chunk_size,
+        ) {
+            let num_txns = txn_pb.end_version - txn_pb.start_version + 1;
+            let num_filtered_txns = num_txns - txn_pb.transactions.len() as u64;
+            let end_version = txn_pb.end_version;
+            LATEST_PROCESSED_VERSION
+                .with_label_values(&[&self.processor_name, step, label, "-"])
+                .set(txn_pb.end_version as i64);
+            PROCESSED_BYTES_COUNT
+                .with_label_values(&[&self.processor_name, step, label, "-"])
+                .inc_by(txn_pb.size_in_bytes);
+            NUM_TRANSACTIONS_PROCESSED_COUNT
+                .with_label_values(&[&self.processor_name, step, label, "-"])
+                .inc_by(num_txns);
+            NUM_TRANSACTIONS_FILTERED_OUT_COUNT
+                .with_label_values(&[&self.processor_name])
+                .inc_by(num_filtered_txns);
+
+            self.txn_sender
+                .send(txn_pb)
+                .await
+                .context("Error sending local transactions to channel")?;
+            FETCHER_THREAD_CHANNEL_SIZE
+                .with_label_values(&[&self.processor_name])
+                .set(self.txn_sender.len() as i64);
+            self.next_version = end_version + 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(version: u64) -> Transaction {
+        Transaction {
+            version,
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn test_build_batches_covers_missing_versions() {
+        let transactions = vec![transaction(10), transaction(11), transaction(15)];
+        let batches = build_batches(transactions, 1, 5, &TransactionFilter::default(), 2);
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].start_version, 5);
+        assert_eq!(batches[0].end_version, 11);
+        assert_eq!(batches[0].transactions.len(), 2);
+        assert_eq!(batches[1].start_version, 12);
+        assert_eq!(batches[1].end_version, 15);
+        assert_eq!(batches[1].transactions.len(), 1);
+    }
+
+    fn write_protobuf_file(path: &Path, versions: &[u64]) {
+        let mut bytes = vec![];
+        for version in versions {
+            transaction(*version)
+                .encode_length_delimited(&mut bytes)
+                .unwrap();
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_streams_files_in_order() {
+        let dir = std::env::temp_dir().join(format!("local_stream_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_protobuf_file(&dir.join("1.pb"), &[3, 1, 2]);
+        // 2 was already replayed from the previous file
+        write_protobuf_file(&dir.join("2.pb"), &[2, 5]);
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let (txn_sender, receiver) = kanal::bounded_async(10);
+        create_local_fetcher_loop(
+            txn_sender,
+            LocalFileStreamConfig {
+                path: dir.clone(),
+                chain_id: 1,
+            },
+            2,
+            None,
+            "test".to_string(),
+            TransactionFilter::default(),
+            2,
+        )
+        .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut batches = vec![];
+        while let Ok(batch) = receiver.recv().await {
+            let versions: Vec<u64> = batch.transactions.iter().map(|t| t.version).collect();
+            batches.push((batch.start_version, batch.end_version, versions));
+        }
+        assert_eq!(batches, vec![(2, 3, vec![2, 3]), (4, 5, vec![5])]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_stops_at_malformed_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "local_stream_malformed_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_protobuf_file(&dir.join("1.pb"), &[1, 2]);
+        std::fs::write(dir.join("2.json"), "not a transaction").unwrap();
+        write_protobuf_file(&dir.join("3.pb"), &[3]);
+
+        let (txn_sender, receiver) = kanal::bounded_async(10);
+        create_local_fetcher_loop(
+            txn_sender,
+            LocalFileStreamConfig {
+                path: dir.clone(),
+                chain_id: 1,
+            },
+            1,
+            None,
+            "test".to_string(),
+            TransactionFilter::default(),
+            10,
+        )
+        .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Only the versions before the malformed file are sent, then the channel is closed
+        let batch = receiver.recv().await.unwrap();
+        assert_eq!((batch.start_version, batch.end_version), (1, 2));
+        assert!(receiver.recv().await.is_err());
+    }
+}
@@ -58,6 +58,7 @@ async fn insert_to_db(
     );
     execute_in_chunks(
         conn.clone(),
+        "account_transactions",
         insert_account_transactions_query,
         account_transactions,
         get_config_table_chunk_size::<AccountTransaction>(
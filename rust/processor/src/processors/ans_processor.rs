@@ -4,22 +4,25 @@
 use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
 use crate::{
     db::common::models::ans_models::{
+        address_display_names::{AddressDisplayName, NameTargets},
         ans_lookup::{AnsLookup, AnsPrimaryName, CurrentAnsLookup, CurrentAnsPrimaryName},
         ans_lookup_v2::{
-            AnsLookupV2, AnsPrimaryNameV2, CurrentAnsLookupV2, CurrentAnsPrimaryNameV2,
+            AnsLookupV2, AnsPrimaryNameV2, CurrentAnsLookupV2, CurrentAnsLookupV2Query,
+            CurrentAnsPrimaryNameV2, CurrentAnsPrimaryNameV2Query, DomainPK,
         },
-        ans_utils::{RenewNameEvent, SubdomainExtV2},
+        ans_name_events::AnsNameEvent,
+        ans_utils::{SubdomainExtV2, ANS_LIFECYCLE_ACTIVE},
     },
     gap_detectors::ProcessingResult,
     schema,
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
-        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
-        util::standardize_address,
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool, DbPoolConnection},
+        util::{parse_timestamp, standardize_address},
     },
     worker::TableFlags,
 };
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use anyhow::bail;
 use aptos_protos::transaction::v1::{
     transaction::TxnData, write_set_change::Change as WriteSetChange, Transaction,
@@ -40,6 +43,16 @@ pub struct AnsProcessorConfig {
     pub ans_v1_primary_names_table_handle: String,
     pub ans_v1_name_records_table_handle: String,
     pub ans_v2_contract_address: String,
+    // How long an expired name stays in grace, during which it can still be renewed, before
+    // it's expired
+    #[serde(default = "AnsProcessorConfig::default_grace_period_secs")]
+    pub grace_period_secs: i64,
+}
+
+impl AnsProcessorConfig {
+    pub const fn default_grace_period_secs() -> i64 {
+        30 * 24 * 60 * 60
+    }
 }
 
 pub struct AnsProcessor {
@@ -60,6 +73,7 @@ impl AnsProcessor {
             ans_v1_primary_names_table_handle = config.ans_v1_primary_names_table_handle,
             ans_v1_name_records_table_handle = config.ans_v1_name_records_table_handle,
             ans_v2_contract_address = config.ans_v2_contract_address,
+            grace_period_secs = config.grace_period_secs,
             "init AnsProcessor"
         );
         Self {
@@ -82,6 +96,7 @@ impl Debug for AnsProcessor {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn insert_to_db(
     conn: ArcDbPool,
     name: &'static str,
@@ -95,6 +110,8 @@ async fn insert_to_db(
     ans_lookups_v2: &[AnsLookupV2],
     current_ans_primary_names_v2: &[CurrentAnsPrimaryNameV2],
     ans_primary_names_v2: &[AnsPrimaryNameV2],
+    ans_name_events: &[AnsNameEvent],
+    address_display_names: &[AddressDisplayName],
     per_table_chunk_sizes: &AHashMap<String, usize>,
 ) -> Result<(), diesel::result::Error> {
     tracing::trace!(
@@ -105,6 +122,7 @@ async fn insert_to_db(
     );
     let cal = execute_in_chunks(
         conn.clone(),
+        "current_ans_lookup",
         insert_current_ans_lookups_query,
         current_ans_lookups,
         get_config_table_chunk_size::<CurrentAnsLookup>(
@@ -114,12 +132,14 @@ async fn insert_to_db(
     );
     let al = execute_in_chunks(
         conn.clone(),
+        "ans_lookup",
         insert_ans_lookups_query,
         ans_lookups,
         get_config_table_chunk_size::<AnsLookup>("ans_lookup", per_table_chunk_sizes),
     );
     let capn = execute_in_chunks(
         conn.clone(),
+        "current_ans_primary_name",
         insert_current_ans_primary_names_query,
         current_ans_primary_names,
         get_config_table_chunk_size::<CurrentAnsPrimaryName>(
@@ -129,12 +149,14 @@ async fn insert_to_db(
     );
     let apn = execute_in_chunks(
         conn.clone(),
+        "ans_primary_name",
         insert_ans_primary_names_query,
         ans_primary_names,
         get_config_table_chunk_size::<AnsPrimaryName>("ans_primary_name", per_table_chunk_sizes),
     );
     let cal_v2 = execute_in_chunks(
         conn.clone(),
+        "current_ans_lookup_v2",
         insert_current_ans_lookups_v2_query,
         current_ans_lookups_v2,
         get_config_table_chunk_size::<CurrentAnsLookupV2>(
@@ -144,12 +166,14 @@ async fn insert_to_db(
     );
     let al_v2 = execute_in_chunks(
         conn.clone(),
+        "ans_lookup_v2",
         insert_ans_lookups_v2_query,
         ans_lookups_v2,
         get_config_table_chunk_size::<AnsLookupV2>("ans_lookup_v2", per_table_chunk_sizes),
     );
     let capn_v2 = execute_in_chunks(
         conn.clone(),
+        "current_ans_primary_name_v2",
         insert_current_ans_primary_names_v2_query,
         current_ans_primary_names_v2,
         get_config_table_chunk_size::<CurrentAnsPrimaryNameV2>(
@@ -158,7 +182,8 @@ async fn insert_to_db(
         ),
     );
     let apn_v2 = execute_in_chunks(
-        conn,
+        conn.clone(),
+        "ans_primary_name_v2",
         insert_ans_primary_names_v2_query,
         ans_primary_names_v2,
         get_config_table_chunk_size::<AnsPrimaryNameV2>(
@@ -166,9 +191,36 @@ async fn insert_to_db(
             per_table_chunk_sizes,
         ),
     );
+    let ane = execute_in_chunks(
+        conn.clone(),
+        "ans_name_events",
+        insert_ans_name_events_query,
+        ans_name_events,
+        get_config_table_chunk_size::<AnsNameEvent>("ans_name_events", per_table_chunk_sizes),
+    );
+    let adn = execute_in_chunks(
+        conn,
+        "address_display_names",
+        insert_address_display_names_query,
+        address_display_names,
+        get_config_table_chunk_size::<AddressDisplayName>(
+            "address_display_names",
+            per_table_chunk_sizes,
+        ),
+    );
 
-    let (cal_res, al_res, capn_res, apn_res, cal_v2_res, al_v2_res, capn_v2_res, apn_v2_res) =
-        tokio::join!(cal, al, capn, apn, cal_v2, al_v2, capn_v2, apn_v2);
+    let (
+        cal_res,
+        al_res,
+        capn_res,
+        apn_res,
+        cal_v2_res,
+        al_v2_res,
+        capn_v2_res,
+        apn_v2_res,
+        ane_res,
+        adn_res,
+    ) = tokio::join!(cal, al, capn, apn, cal_v2, al_v2, capn_v2, apn_v2, ane, adn);
 
     for res in vec![
         cal_res,
@@ -179,6 +231,8 @@ async fn insert_to_db(
         al_v2_res,
         capn_v2_res,
         apn_v2_res,
+        ane_res,
+        adn_res,
     ] {
         res?;
     }
@@ -291,6 +345,8 @@ fn insert_current_ans_lookups_v2_query(
                 is_deleted.eq(excluded(is_deleted)),
                 inserted_at.eq(excluded(inserted_at)),
                 subdomain_expiration_policy.eq(excluded(subdomain_expiration_policy)),
+                lifecycle_status.eq(excluded(lifecycle_status)),
+                is_active.eq(excluded(is_active)),
             )),
         Some(" WHERE current_ans_lookup_v2.last_transaction_version <= excluded.last_transaction_version "),
     )
@@ -337,6 +393,7 @@ fn insert_current_ans_primary_names_v2_query(
                 is_deleted.eq(excluded(is_deleted)),
                 last_transaction_version.eq(excluded(last_transaction_version)),
                 inserted_at.eq(excluded(inserted_at)),
+                is_active.eq(excluded(is_active)),
             )),
         Some(" WHERE current_ans_primary_name_v2.last_transaction_version <= excluded.last_transaction_version "),
     )
@@ -359,6 +416,337 @@ fn insert_ans_primary_names_v2_query(
     )
 }
 
+fn insert_ans_name_events_query(
+    items_to_insert: Vec<AnsNameEvent>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::ans_name_events::dsl::*;
+
+    (
+        diesel::insert_into(schema::ans_name_events::table)
+            .values(items_to_insert)
+            .on_conflict((
+                transaction_version,
+                domain,
+                subdomain,
+                token_standard,
+                event_type,
+            ))
+            .do_nothing(),
+        None,
+    )
+}
+
+fn insert_address_display_names_query(
+    items_to_insert: Vec<AddressDisplayName>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::address_display_names::dsl::*;
+
+    (
+        diesel::insert_into(schema::address_display_names::table)
+            .values(items_to_insert)
+            .on_conflict(address)
+            .do_update()
+            .set((
+                domain.eq(excluded(domain)),
+                subdomain.eq(excluded(subdomain)),
+                token_name.eq(excluded(token_name)),
+                token_standard.eq(excluded(token_standard)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE address_display_names.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+/// Moves names along their lifecycle as of `now`, the timestamp of the batch's last transaction.
+/// Besides the names written in the batch, names that expired or ran out of grace since are
+/// rewritten as is with their new status, and primary names follow the status of their name.
+/// Subdomains following their domain's expiration move along with the domain.
+async fn update_ans_lifecycle(
+    conn: &mut DbPoolConnection<'_>,
+    now: chrono::NaiveDateTime,
+    grace_period_secs: i64,
+    current_ans_lookups_v2: &mut Vec<CurrentAnsLookupV2>,
+    current_ans_primary_names_v2: &mut Vec<CurrentAnsPrimaryNameV2>,
+    ans_name_events: &mut Vec<AnsNameEvent>,
+) -> diesel::QueryResult<()> {
+    let mut domain_expirations = current_ans_lookups_v2
+        .iter()
+        .filter(|lookup| lookup.subdomain.is_empty())
+        .map(|lookup| (lookup.domain_pk(), lookup.expiration_timestamp))
+        .collect::<AHashMap<_, _>>();
+    load_domain_expirations(current_ans_lookups_v2, &mut domain_expirations, conn).await?;
+    for current_ans_lookup in current_ans_lookups_v2.iter_mut() {
+        let domain_expiration = domain_expirations
+            .get(&current_ans_lookup.domain_pk())
+            .copied();
+        current_ans_lookup.set_lifecycle(now, grace_period_secs, domain_expiration);
+        if !current_ans_lookup.is_deleted
+            && current_ans_lookup.lifecycle_status != ANS_LIFECYCLE_ACTIVE
+        {
+            let expiration_timestamp =
+                current_ans_lookup.get_expiration_timestamp(domain_expiration);
+            ans_name_events.push(AnsNameEvent::expired(
+                current_ans_lookup,
+                expiration_timestamp,
+            ));
+        }
+    }
+
+    // Names in the batch already have their status as of now
+    let batch_lookup_pks = current_ans_lookups_v2
+        .iter()
+        .map(|lookup| lookup.pk())
+        .collect::<AHashSet<_>>();
+    for name in CurrentAnsLookupV2Query::get_names_to_expire(now, grace_period_secs, conn).await? {
+        let was_active = name.lifecycle_status == ANS_LIFECYCLE_ACTIVE;
+        let mut current_ans_lookup = CurrentAnsLookupV2::from(name);
+        if batch_lookup_pks.contains(&current_ans_lookup.pk()) {
+            continue;
+        }
+        current_ans_lookup.set_lifecycle(now, grace_period_secs, None);
+        if was_active {
+            ans_name_events.push(AnsNameEvent::expired(
+                &current_ans_lookup,
+                current_ans_lookup.expiration_timestamp,
+            ));
+        }
+        if current_ans_lookup.subdomain.is_empty() {
+            domain_expirations.insert(
+                current_ans_lookup.domain_pk(),
+                current_ans_lookup.expiration_timestamp,
+            );
+        }
+        current_ans_lookups_v2.push(current_ans_lookup);
+    }
+
+    // The status of these domains was just set, so the subdomains following them may have to
+    // expire with them, or become active again if they were renewed
+    let domains = current_ans_lookups_v2
+        .iter()
+        .filter(|lookup| lookup.subdomain.is_empty())
+        .map(|lookup| lookup.domain.clone())
+        .collect::<AHashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for name in CurrentAnsLookupV2Query::get_subdomains_following_domains(&domains, conn).await? {
+        let previous_status = name.lifecycle_status.clone();
+        let mut current_ans_lookup = CurrentAnsLookupV2::from(name);
+        if batch_lookup_pks.contains(&current_ans_lookup.pk()) {
+            continue;
+        }
+        let Some(&domain_expiration) = domain_expirations.get(&current_ans_lookup.domain_pk())
+        else {
+            continue;
+        };
+        current_ans_lookup.set_lifecycle(now, grace_period_secs, Some(domain_expiration));
+        if current_ans_lookup.lifecycle_status == previous_status {
+            continue;
+        }
+        if previous_status == ANS_LIFECYCLE_ACTIVE {
+            ans_name_events.push(AnsNameEvent::expired(
+                &current_ans_lookup,
+                domain_expiration,
+            ));
+        }
+        current_ans_lookups_v2.push(current_ans_lookup);
+    }
+
+    // (token name, token standard) -> whether the name is active
+    let mut is_name_active = current_ans_lookups_v2
+        .iter()
+        .map(|lookup| {
+            (
+                (lookup.token_name.clone(), lookup.token_standard.clone()),
+                lookup.is_active,
+            )
+        })
+        .collect::<AHashMap<_, _>>();
+    let missing_token_names = current_ans_primary_names_v2
+        .iter()
+        .filter_map(|primary_name| {
+            primary_name.token_name.clone().filter(|token_name| {
+                !is_name_active
+                    .contains_key(&(token_name.clone(), primary_name.token_standard.clone()))
+            })
+        })
+        .collect::<Vec<_>>();
+    let missing_names = CurrentAnsLookupV2Query::get_by_token_names(&missing_token_names, conn)
+        .await?
+        .into_iter()
+        .filter(|name| name.token_name.is_some())
+        .map(CurrentAnsLookupV2::from)
+        .collect::<Vec<_>>();
+    load_domain_expirations(&missing_names, &mut domain_expirations, conn).await?;
+    for mut name in missing_names {
+        let domain_expiration = domain_expirations.get(&name.domain_pk()).copied();
+        name.set_lifecycle(now, grace_period_secs, domain_expiration);
+        is_name_active.insert((name.token_name, name.token_standard), name.is_active);
+    }
+
+    // Primary names in the batch, where a name that isn't indexed yet is taken as active
+    let batch_primary_name_pks = current_ans_primary_names_v2
+        .iter()
+        .map(|primary_name| primary_name.pk())
+        .collect::<AHashSet<_>>();
+    for primary_name in current_ans_primary_names_v2.iter_mut() {
+        let is_active = match &primary_name.token_name {
+            Some(token_name) => is_name_active
+                .get(&(token_name.clone(), primary_name.token_standard.clone()))
+                .copied()
+                .unwrap_or(true),
+            None => true,
+        };
+        primary_name.is_active = !primary_name.is_deleted && is_active;
+    }
+
+    // Primary names pointing at names whose status may have changed
+    let changed_token_names = current_ans_lookups_v2
+        .iter()
+        .map(|lookup| lookup.token_name.clone())
+        .collect::<Vec<_>>();
+    for primary_name in
+        CurrentAnsPrimaryNameV2Query::get_by_token_names(&changed_token_names, conn).await?
+    {
+        let mut primary_name = CurrentAnsPrimaryNameV2::from(primary_name);
+        if batch_primary_name_pks.contains(&primary_name.pk()) {
+            continue;
+        }
+        let Some(&is_active) = primary_name.token_name.as_ref().and_then(|token_name| {
+            is_name_active.get(&(token_name.clone(), primary_name.token_standard.clone()))
+        }) else {
+            continue;
+        };
+        if primary_name.is_active != is_active {
+            primary_name.is_active = is_active;
+            current_ans_primary_names_v2.push(primary_name);
+        }
+    }
+
+    current_ans_lookups_v2.sort();
+    current_ans_primary_names_v2.sort();
+    Ok(())
+}
+
+/// Adds the expirations of the domains followed by subdomains among `lookups` that aren't known
+/// yet
+async fn load_domain_expirations(
+    lookups: &[CurrentAnsLookupV2],
+    domain_expirations: &mut AHashMap<DomainPK, chrono::NaiveDateTime>,
+    conn: &mut DbPoolConnection<'_>,
+) -> diesel::QueryResult<()> {
+    let missing_domains = lookups
+        .iter()
+        .filter(|lookup| {
+            lookup.follows_domain_expiration()
+                && !domain_expirations.contains_key(&lookup.domain_pk())
+        })
+        .map(|lookup| lookup.domain.clone())
+        .collect::<AHashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for domain in CurrentAnsLookupV2Query::get_domains(&missing_domains, conn).await? {
+        domain_expirations.insert(
+            (domain.domain, domain.token_standard),
+            domain.expiration_timestamp,
+        );
+    }
+    Ok(())
+}
+
+/// Display names of the addresses whose primary name changed in the batch. A name can also stop
+/// pointing at an address without its primary name changing, so the addresses with a primary
+/// name among the batch's names are refreshed too.
+async fn get_address_display_names(
+    conn: &mut DbPoolConnection<'_>,
+    current_ans_lookups_v2: &[CurrentAnsLookupV2],
+    current_ans_primary_names_v2: &[CurrentAnsPrimaryNameV2],
+) -> diesel::QueryResult<Vec<AddressDisplayName>> {
+    let token_names = current_ans_lookups_v2
+        .iter()
+        .map(|lookup| lookup.token_name.clone())
+        .collect::<Vec<_>>();
+    let mut addresses = current_ans_primary_names_v2
+        .iter()
+        .map(|primary_name| primary_name.registered_address.clone())
+        .collect::<AHashSet<_>>();
+    addresses.extend(
+        CurrentAnsPrimaryNameV2Query::get_by_token_names(&token_names, conn)
+            .await?
+            .into_iter()
+            .map(|primary_name| primary_name.registered_address),
+    );
+    let addresses = addresses.into_iter().collect::<Vec<_>>();
+
+    // Primary names of these addresses, with the batch's taking precedence over the DB's
+    let mut primary_names = AHashMap::new();
+    for primary_name in CurrentAnsPrimaryNameV2Query::get_by_addresses(&addresses, conn).await? {
+        let primary_name = CurrentAnsPrimaryNameV2::from(primary_name);
+        primary_names.insert(primary_name.pk(), primary_name);
+    }
+    for primary_name in current_ans_primary_names_v2 {
+        let is_newer = primary_names
+            .get(&primary_name.pk())
+            .map_or(true, |existing| {
+                existing.last_transaction_version <= primary_name.last_transaction_version
+            });
+        if is_newer {
+            primary_names.insert(primary_name.pk(), primary_name.clone());
+        }
+    }
+
+    let mut name_targets = current_ans_lookups_v2
+        .iter()
+        .map(|lookup| {
+            (
+                (lookup.token_name.clone(), lookup.token_standard.clone()),
+                lookup.registered_address.clone(),
+            )
+        })
+        .collect::<NameTargets>();
+    let missing_token_names = primary_names
+        .values()
+        .filter_map(|primary_name| {
+            primary_name.token_name.clone().filter(|token_name| {
+                !name_targets
+                    .contains_key(&(token_name.clone(), primary_name.token_standard.clone()))
+            })
+        })
+        .collect::<Vec<_>>();
+    for name in CurrentAnsLookupV2Query::get_by_token_names(&missing_token_names, conn).await? {
+        if let Some(token_name) = name.token_name {
+            name_targets.insert((token_name, name.token_standard), name.registered_address);
+        }
+    }
+
+    let mut primary_names_by_address: AHashMap<String, Vec<CurrentAnsPrimaryNameV2>> =
+        AHashMap::new();
+    for primary_name in primary_names.into_values() {
+        primary_names_by_address
+            .entry(primary_name.registered_address.clone())
+            .or_default()
+            .push(primary_name);
+    }
+    let mut address_display_names = addresses
+        .iter()
+        .map(|address| {
+            AddressDisplayName::from_primary_names(
+                address,
+                primary_names_by_address.get(address).into_iter().flatten(),
+                &name_targets,
+            )
+        })
+        .collect::<Vec<_>>();
+    address_display_names.sort();
+    Ok(address_display_names)
+}
+
 #[async_trait]
 impl ProcessorTrait for AnsProcessor {
     fn name(&self) -> &'static str {
@@ -380,10 +768,11 @@ impl ProcessorTrait for AnsProcessor {
             mut all_ans_lookups,
             mut all_current_ans_primary_names,
             mut all_ans_primary_names,
-            all_current_ans_lookups_v2,
+            mut all_current_ans_lookups_v2,
             all_ans_lookups_v2,
-            all_current_ans_primary_names_v2,
+            mut all_current_ans_primary_names_v2,
             mut all_ans_primary_names_v2,
+            mut all_ans_name_events,
         ) = parse_ans(
             &transactions,
             self.config.ans_v1_primary_names_table_handle.clone(),
@@ -391,6 +780,28 @@ impl ProcessorTrait for AnsProcessor {
             self.config.ans_v2_contract_address.clone(),
         );
 
+        let now = parse_timestamp(
+            last_transaction_timestamp.as_ref().unwrap(),
+            end_version as i64,
+        );
+        let mut conn = self.get_read_conn().await;
+        update_ans_lifecycle(
+            &mut conn,
+            now,
+            self.config.grace_period_secs,
+            &mut all_current_ans_lookups_v2,
+            &mut all_current_ans_primary_names_v2,
+            &mut all_ans_name_events,
+        )
+        .await?;
+        let all_address_display_names = get_address_display_names(
+            &mut conn,
+            &all_current_ans_lookups_v2,
+            &all_current_ans_primary_names_v2,
+        )
+        .await?;
+        drop(conn);
+
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
@@ -436,6 +847,8 @@ impl ProcessorTrait for AnsProcessor {
             &all_ans_lookups_v2,
             &all_current_ans_primary_names_v2,
             &all_ans_primary_names_v2,
+            &all_ans_name_events,
+            &all_address_display_names,
             &self.per_table_chunk_sizes,
         )
         .await;
@@ -484,6 +897,7 @@ fn parse_ans(
     Vec<AnsLookupV2>,
     Vec<CurrentAnsPrimaryNameV2>,
     Vec<AnsPrimaryNameV2>,
+    Vec<AnsNameEvent>,
 ) {
     let mut all_current_ans_lookups = AHashMap::new();
     let mut all_ans_lookups = vec![];
@@ -493,6 +907,7 @@ fn parse_ans(
     let mut all_ans_lookups_v2 = vec![];
     let mut all_current_ans_primary_names_v2 = AHashMap::new();
     let mut all_ans_primary_names_v2 = vec![];
+    let mut all_ans_name_events = vec![];
 
     for transaction in transactions {
         let txn_version = transaction.version as i64;
@@ -517,19 +932,23 @@ fn parse_ans(
         // Extracts from user transactions. Other transactions won't have any ANS changes
 
         if let TxnData::User(user_txn) = txn_data {
-            // TODO: Use the v2_renew_name_events to preserve metadata once we switch to a single ANS table to store everything
-            let mut v2_renew_name_events = vec![];
+            let txn_timestamp =
+                parse_timestamp(transaction.timestamp.as_ref().unwrap(), txn_version);
             let mut v2_address_to_subdomain_ext = AHashMap::new();
 
             // Parse V2 ANS Events. We only care about the following events:
-            // 1. RenewNameEvents: helps to fill in metadata for name records with updated expiration time
+            // 1. RegisterNameEvents, RenewNameEvents and SetTargetAddressEvents: parse to get ans_name_events
             // 2. SetReverseLookupEvents: parse to get current_ans_primary_names
             for (event_index, event) in user_txn.events.iter().enumerate() {
-                if let Some(renew_name_event) =
-                    RenewNameEvent::from_event(event, &ans_v2_contract_address, txn_version)
-                        .unwrap()
+                if let Some(ans_name_event) = AnsNameEvent::from_event(
+                    event,
+                    &ans_v2_contract_address,
+                    txn_version,
+                    txn_timestamp,
+                )
+                .unwrap()
                 {
-                    v2_renew_name_events.push(renew_name_event);
+                    all_ans_name_events.push(ans_name_event);
                 }
                 if let Some((current_ans_lookup_v2, ans_lookup_v2)) =
                     CurrentAnsPrimaryNameV2::parse_v2_primary_name_record_from_event(
@@ -740,5 +1159,6 @@ fn parse_ans(
         all_ans_lookups_v2,
         all_current_ans_primary_names_v2,
         all_ans_primary_names_v2,
+        all_ans_name_events,
     )
 }
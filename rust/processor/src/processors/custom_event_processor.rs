@@ -0,0 +1,578 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    gap_detectors::ProcessingResult,
+    utils::{
+        counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
+        database::{clean_data_for_db, execute_with_better_error, ArcDbPool, DbPoolConnection},
+        dry_run,
+        util::{parse_timestamp, standardize_address},
+        write_context::{self, TableWrite},
+    },
+};
+use ahash::AHashMap;
+use anyhow::{bail, ensure, Context};
+use aptos_protos::transaction::v1::{transaction::TxnData, Event as EventPB, Transaction};
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use diesel::sql_types::Jsonb;
+use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{collections::HashSet, fmt::Debug, str::FromStr};
+use tracing::error;
+
+/// Number of rows per insert if the table isn't in `per_table_chunk_sizes`. Rows are sent as a
+/// single JSONB parameter, so this isn't bound by the Postgres parameter limit.
+const DEFAULT_CHUNK_SIZE: usize = 1000;
+// Postgres truncates identifiers longer than this
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+// Custom event tables must start with this, so they can't be core tables, which never do
+const TABLE_NAME_PREFIX: &str = "custom_";
+
+/// Columns every custom event table has, in addition to the configured ones.
+const BASE_COLUMNS: [(&str, &str); 5] = [
+    ("transaction_version", "BIGINT"),
+    ("event_index", "BIGINT"),
+    ("account_address", "VARCHAR(66)"),
+    ("event_type", "TEXT"),
+    ("transaction_timestamp", "TIMESTAMP"),
+];
+
+/// Stores events as rows of tables declared in the config. The tables are created on startup,
+/// and columns added to the config later are added to the existing tables.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomEventProcessorConfig {
+    pub tables: Vec<CustomEventTableConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomEventTableConfig {
+    pub table_name: String,
+    // Event types stored in this table, e.g. "0xabc::market::ListingEvent". Types without type
+    // arguments match every instantiation of a generic event
+    pub event_types: Vec<String>,
+    pub columns: Vec<CustomEventColumnConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomEventColumnConfig {
+    pub name: String,
+    // Dot separated path into the event data, e.g. "price" or "$.listing.token.inner". Array
+    // elements are selected by index, e.g. "items.0". "$" selects the whole payload
+    pub path: String,
+    #[serde(default, rename = "type")]
+    pub column_type: CustomEventColumnType,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomEventColumnType {
+    #[default]
+    Text,
+    Bigint,
+    Numeric,
+    Boolean,
+    Jsonb,
+    // Standardized to 0x + 64 hex characters
+    Address,
+}
+
+impl CustomEventColumnType {
+    fn sql_type(&self) -> &'static str {
+        match self {
+            Self::Text => "TEXT",
+            Self::Bigint => "BIGINT",
+            Self::Numeric => "NUMERIC",
+            Self::Boolean => "BOOLEAN",
+            Self::Jsonb => "JSONB",
+            Self::Address => "VARCHAR(66)",
+        }
+    }
+
+    /// Values that can't be converted are stored as NULL rather than failing the batch.
+    fn convert(&self, value: &Value) -> Value {
+        match (self, value) {
+            (_, Value::Null) => Value::Null,
+            (Self::Jsonb, value) => value.clone(),
+            (Self::Text, Value::String(s)) => Value::String(s.clone()),
+            (Self::Text, value) => Value::String(value.to_string()),
+            (Self::Bigint, Value::Number(n)) => n.as_i64().map(Value::from).unwrap_or(Value::Null),
+            (Self::Bigint, Value::String(s)) => {
+                s.parse::<i64>().map(Value::from).unwrap_or(Value::Null)
+            },
+            (Self::Numeric, Value::Number(n)) => Value::String(n.to_string()),
+            (Self::Numeric, Value::String(s)) if BigDecimal::from_str(s).is_ok() => {
+                Value::String(s.clone())
+            },
+            (Self::Boolean, Value::Bool(b)) => Value::Bool(*b),
+            (Self::Boolean, Value::String(s)) => {
+                s.parse::<bool>().map(Value::Bool).unwrap_or(Value::Null)
+            },
+            (Self::Address, Value::String(s)) => Value::String(standardize_address(s)),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl CustomEventProcessorConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut table_names = HashSet::new();
+        for table in &self.tables {
+            validate_identifier(&table.table_name)?;
+            ensure!(
+                table.table_name.len() > TABLE_NAME_PREFIX.len()
+                    && table.table_name.starts_with(TABLE_NAME_PREFIX),
+                "Custom event table {} must be named {}<name>",
+                table.table_name,
+                TABLE_NAME_PREFIX
+            );
+            ensure!(
+                table_names.insert(table.table_name.as_str()),
+                "Custom event table {} is declared more than once",
+                table.table_name
+            );
+            ensure!(
+                !table.event_types.is_empty(),
+                "Custom event table {} has no event types",
+                table.table_name
+            );
+            let mut column_names = BASE_COLUMNS
+                .iter()
+                .map(|(name, _)| *name)
+                .chain(["inserted_at"])
+                .collect::<HashSet<_>>();
+            for column in &table.columns {
+                validate_identifier(&column.name)?;
+                ensure!(
+                    column_names.insert(column.name.as_str()),
+                    "Column {} of custom event table {} is reserved or declared more than once",
+                    column.name,
+                    table.table_name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the configured tables, and adds columns that were added to the config since.
+    /// Columns removed from the config are left in place.
+    pub async fn create_tables(&self, conn: &mut DbPoolConnection<'_>) -> anyhow::Result<()> {
+        self.validate()?;
+        for table in &self.tables {
+            let base_columns = BASE_COLUMNS
+                .iter()
+                .map(|(name, sql_type)| format!("{} {} NOT NULL", name, sql_type))
+                .collect::<Vec<_>>();
+            let mut statements = vec![
+                format!(
+                    "CREATE TABLE IF NOT EXISTS \"{}\" ({}, inserted_at TIMESTAMP NOT NULL DEFAULT \
+                     NOW(), PRIMARY KEY (transaction_version, event_index))",
+                    table.table_name,
+                    base_columns.join(", "),
+                ),
+                format!(
+                    "CREATE INDEX IF NOT EXISTS \"{}_insat_index\" ON \"{}\" (inserted_at)",
+                    table.table_name, table.table_name
+                ),
+            ];
+            statements.extend(table.columns.iter().map(|column| {
+                format!(
+                    "ALTER TABLE \"{}\" ADD COLUMN IF NOT EXISTS \"{}\" {}",
+                    table.table_name,
+                    column.name,
+                    column.column_type.sql_type()
+                )
+            }));
+            for statement in statements {
+                diesel::sql_query(&statement)
+                    .execute(conn)
+                    .await
+                    .with_context(|| format!("Failed to run {}", statement))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CustomEventTableConfig {
+    fn matches(&self, event_type: &str) -> bool {
+        let event_type = normalize_event_type(event_type);
+        let base_type = event_type.split('<').next().unwrap_or_default();
+        self.event_types.iter().any(|configured| {
+            let configured = normalize_event_type(configured);
+            if configured.contains('<') {
+                configured == event_type
+            } else {
+                configured == base_type
+            }
+        })
+    }
+
+    fn to_row(
+        &self,
+        event: &EventPB,
+        txn_version: i64,
+        event_index: i64,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Value {
+        let account_address = event
+            .key
+            .as_ref()
+            .map(|key| key.account_address.as_str())
+            .unwrap_or_default();
+        let mut row = Map::new();
+        row.insert("transaction_version".to_string(), json!(txn_version));
+        row.insert("event_index".to_string(), json!(event_index));
+        row.insert(
+            "account_address".to_string(),
+            json!(standardize_address(account_address)),
+        );
+        row.insert("event_type".to_string(), json!(event.type_str));
+        row.insert("transaction_timestamp".to_string(), json!(txn_timestamp));
+
+        let data: Value = serde_json::from_str(&event.data).unwrap_or(Value::Null);
+        for column in &self.columns {
+            let value = get_path(&data, &column.path).unwrap_or(&Value::Null);
+            row.insert(column.name.clone(), column.column_type.convert(value));
+        }
+        Value::Object(row)
+    }
+
+    /// Inserts the rows through `jsonb_to_recordset`, so the columns can be chosen at runtime.
+    fn insert_query(&self) -> String {
+        let columns = BASE_COLUMNS
+            .iter()
+            .map(|(name, sql_type)| (name.to_string(), *sql_type))
+            .chain(self.columns.iter().map(|column| {
+                (
+                    format!("\"{}\"", column.name),
+                    column.column_type.sql_type(),
+                )
+            }))
+            .collect::<Vec<_>>();
+        let names = columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let definitions = columns
+            .iter()
+            .map(|(name, sql_type)| format!("{} {}", name, sql_type))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "INSERT INTO \"{}\" ({}) SELECT {} FROM jsonb_to_recordset($1) AS rows({}) ON \
+             CONFLICT (transaction_version, event_index) DO NOTHING",
+            self.table_name, names, names, definitions
+        )
+    }
+}
+
+fn validate_identifier(identifier: &str) -> anyhow::Result<()> {
+    ensure!(
+        !identifier.is_empty()
+            && identifier.len() <= MAX_IDENTIFIER_LENGTH
+            && identifier.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+            && identifier
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+        "{} must be a lowercase identifier of at most {} characters",
+        identifier,
+        MAX_IDENTIFIER_LENGTH
+    );
+    Ok(())
+}
+
+/// Standardizes the address of an event type so that "0x1::..." and "0x00..01::..." match.
+fn normalize_event_type(event_type: &str) -> String {
+    match event_type.split_once("::") {
+        Some((address, rest)) => format!("{}::{}", standardize_address(address), rest),
+        None => event_type.to_string(),
+    }
+}
+
+fn get_path<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+    if path.is_empty() {
+        return Some(data);
+    }
+    path.split('.')
+        .try_fold(data, |value, segment| match value {
+            Value::Array(values) => values.get(segment.parse::<usize>().ok()?),
+            value => value.get(segment),
+        })
+}
+
+pub struct CustomEventProcessor {
+    connection_pool: ArcDbPool,
+    config: CustomEventProcessorConfig,
+    insert_queries: Vec<String>,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+}
+
+impl CustomEventProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: CustomEventProcessorConfig,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        let insert_queries = config
+            .tables
+            .iter()
+            .map(CustomEventTableConfig::insert_query)
+            .collect();
+        Self {
+            connection_pool,
+            config,
+            insert_queries,
+            per_table_chunk_sizes,
+        }
+    }
+}
+
+impl Debug for CustomEventProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "CustomEventProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+async fn insert_to_db(
+    conn: ArcDbPool,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    tables: &[CustomEventTableConfig],
+    insert_queries: &[String],
+    rows_per_table: Vec<Vec<Value>>,
+    per_table_chunk_sizes: &AHashMap<String, usize>,
+) -> Result<(), diesel::result::Error> {
+    tracing::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+    let mut queries = vec![];
+    for ((table, insert_query), rows) in tables.iter().zip(insert_queries).zip(rows_per_table) {
+        let chunk_size = per_table_chunk_sizes
+            .get(&table.table_name)
+            .copied()
+            .unwrap_or(DEFAULT_CHUNK_SIZE);
+        let rows = clean_data_for_db(rows, true);
+        match write_context::table_write(&table.table_name) {
+            TableWrite::Write => {},
+            TableWrite::Capture => {
+                dry_run::capture_rows(&table.table_name, rows);
+                continue;
+            },
+            TableWrite::Skip => continue,
+        }
+        for chunk in rows.chunks(chunk_size.max(1)) {
+            let query = diesel::sql_query(insert_query.clone())
+                .bind::<Jsonb, _>(Value::Array(chunk.to_vec()));
+            queries.push(execute_with_better_error(conn.clone(), query, None));
+        }
+    }
+    futures::future::try_join_all(queries).await?;
+    Ok(())
+}
+
+#[async_trait]
+impl ProcessorTrait for CustomEventProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::CustomEventProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let mut rows_per_table = vec![vec![]; self.config.tables.len()];
+        for txn in &transactions {
+            let txn_version = txn.version as i64;
+            let txn_data = match txn.txn_data.as_ref() {
+                Some(data) => data,
+                None => {
+                    tracing::warn!(
+                        transaction_version = txn_version,
+                        "Transaction data doesn't exist"
+                    );
+                    PROCESSOR_UNKNOWN_TYPE_COUNT
+                        .with_label_values(&["CustomEventProcessor"])
+                        .inc();
+                    continue;
+                },
+            };
+            let default = vec![];
+            let raw_events = match txn_data {
+                TxnData::BlockMetadata(tx_inner) => &tx_inner.events,
+                TxnData::Genesis(tx_inner) => &tx_inner.events,
+                TxnData::User(tx_inner) => &tx_inner.events,
+                TxnData::Validator(tx_inner) => &tx_inner.events,
+                _ => &default,
+            };
+            let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+
+            for (index, event) in raw_events.iter().enumerate() {
+                for (table, rows) in self.config.tables.iter().zip(rows_per_table.iter_mut()) {
+                    if !table.matches(&event.type_str) {
+                        continue;
+                    }
+                    rows.push(table.to_row(event, txn_version, index as i64, txn_timestamp));
+                }
+            }
+        }
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        let tx_result = insert_to_db(
+            self.get_pool(),
+            self.name(),
+            start_version,
+            end_version,
+            &self.config.tables,
+            &self.insert_queries,
+            rows_per_table,
+            &self.per_table_chunk_sizes,
+        )
+        .await;
+
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version,
+                    end_version,
+                    processing_duration_in_secs,
+                    db_insertion_duration_in_secs,
+                    last_transaction_timestamp,
+                },
+            )),
+            Err(e) => {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            },
+        }
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_event_table() {
+        let table: CustomEventTableConfig = serde_json::from_value(json!({
+            "table_name": "custom_market_listings",
+            "event_types": ["0xabc::market::ListingEvent"],
+            "columns": [
+                { "name": "seller", "path": "seller", "type": "address" },
+                { "name": "price", "path": "$.price", "type": "numeric" },
+                { "name": "first_item", "path": "items.0" },
+                { "name": "is_active", "path": "active", "type": "boolean" },
+            ],
+        }))
+        .unwrap();
+        CustomEventProcessorConfig {
+            tables: vec![table.clone()],
+        }
+        .validate()
+        .unwrap();
+
+        assert!(table.matches("0xabc::market::ListingEvent"));
+        assert!(table.matches(&format!(
+            "{}::market::ListingEvent<0x1::aptos_coin::AptosCoin>",
+            standardize_address("0xabc")
+        )));
+        assert!(!table.matches("0xabc::market::ListingEventV2"));
+
+        let event = EventPB {
+            type_str: "0xabc::market::ListingEvent".to_string(),
+            data: r#"{"seller": "0x1", "price": "100", "items": ["a", "b"], "active": "yes"}"#
+                .to_string(),
+            ..Default::default()
+        };
+        let timestamp = chrono::NaiveDateTime::default();
+        assert_eq!(
+            table.to_row(&event, 1, 0, timestamp),
+            json!({
+                "transaction_version": 1,
+                "event_index": 0,
+                "account_address": standardize_address("0x0"),
+                "event_type": "0xabc::market::ListingEvent",
+                "transaction_timestamp": timestamp,
+                "seller": standardize_address("0x1"),
+                "price": "100",
+                "first_item": "a",
+                "is_active": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_custom_event_config_validation() {
+        let column = |name: &str| CustomEventColumnConfig {
+            name: name.to_string(),
+            path: "$".to_string(),
+            column_type: CustomEventColumnType::Jsonb,
+        };
+        let config = |table_name: &str, columns| CustomEventProcessorConfig {
+            tables: vec![CustomEventTableConfig {
+                table_name: table_name.to_string(),
+                event_types: vec!["0x1::coin::DepositEvent".to_string()],
+                columns,
+            }],
+        };
+        assert!(config("custom_deposits", vec![column("data")])
+            .validate()
+            .is_ok());
+        assert!(config("custom_deposits", vec![column("event_index")])
+            .validate()
+            .is_err());
+        assert!(config("custom_deposits; DROP TABLE events", vec![])
+            .validate()
+            .is_err());
+        assert!(config("custom_deposits", vec![column("Data")])
+            .validate()
+            .is_err());
+        // Core tables can't be written to
+        for table_name in [
+            "events",
+            "processor_status",
+            "current_fungible_asset_balances",
+            "deposits",
+            "custom_",
+        ] {
+            assert!(config(table_name, vec![]).validate().is_err());
+        }
+    }
+}
@@ -5,8 +5,10 @@ use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
 use crate::{
     db::common::models::default_models::{
         block_metadata_transactions::{BlockMetadataTransaction, BlockMetadataTransactionModel},
+        epochs::Epoch,
         move_tables::{CurrentTableItem, TableItem, TableMetadata},
         transactions::TransactionModel,
+        validator_epoch_stats::ValidatorEpochStat,
         write_set_changes::WriteSetChangeDetail,
     },
     gap_detectors::ProcessingResult,
@@ -19,8 +21,10 @@ use anyhow::bail;
 use aptos_protos::transaction::v1::Transaction;
 use async_trait::async_trait;
 use diesel::{
+    dsl::sql,
     pg::{upsert::excluded, Pg},
     query_builder::QueryFragment,
+    sql_types::{BigInt, Nullable, Numeric, Timestamp, VarChar},
     ExpressionMethods,
 };
 use std::fmt::Debug;
@@ -69,6 +73,7 @@ async fn insert_to_db(
         &[CurrentTableItem],
         &[TableMetadata],
     ),
+    (epochs, validator_epoch_stats): (&[Epoch], &[ValidatorEpochStat]),
     per_table_chunk_sizes: &AHashMap<String, usize>,
 ) -> Result<(), diesel::result::Error> {
     tracing::trace!(
@@ -80,6 +85,7 @@ async fn insert_to_db(
 
     let bmt_res = execute_in_chunks(
         conn.clone(),
+        "block_metadata_transactions",
         insert_block_metadata_transactions_query,
         block_metadata_transactions,
         get_config_table_chunk_size::<BlockMetadataTransactionModel>(
@@ -90,6 +96,7 @@ async fn insert_to_db(
 
     let ti_res = execute_in_chunks(
         conn.clone(),
+        "table_items",
         insert_table_items_query,
         table_items,
         get_config_table_chunk_size::<TableItem>("table_items", per_table_chunk_sizes),
@@ -97,6 +104,7 @@ async fn insert_to_db(
 
     let cti_res = execute_in_chunks(
         conn.clone(),
+        "current_table_items",
         insert_current_table_items_query,
         current_table_items,
         get_config_table_chunk_size::<CurrentTableItem>(
@@ -107,14 +115,35 @@ async fn insert_to_db(
 
     let tm_res = execute_in_chunks(
         conn.clone(),
+        "table_metadatas",
         insert_table_metadata_query,
         table_metadata,
         get_config_table_chunk_size::<TableMetadata>("table_metadatas", per_table_chunk_sizes),
     );
 
-    let (bmt_res, ti_res, cti_res, tm_res) = join!(bmt_res, ti_res, cti_res, tm_res);
+    let e_res = execute_in_chunks(
+        conn.clone(),
+        "epochs",
+        insert_epochs_query,
+        epochs,
+        get_config_table_chunk_size::<Epoch>("epochs", per_table_chunk_sizes),
+    );
 
-    for res in [bmt_res, ti_res, cti_res, tm_res] {
+    let ves_res = execute_in_chunks(
+        conn.clone(),
+        "validator_epoch_stats",
+        insert_validator_epoch_stats_query,
+        validator_epoch_stats,
+        get_config_table_chunk_size::<ValidatorEpochStat>(
+            "validator_epoch_stats",
+            per_table_chunk_sizes,
+        ),
+    );
+
+    let (bmt_res, ti_res, cti_res, tm_res, e_res, ves_res) =
+        join!(bmt_res, ti_res, cti_res, tm_res, e_res, ves_res);
+
+    for res in [bmt_res, ti_res, cti_res, tm_res, e_res, ves_res] {
         res?;
     }
 
@@ -197,6 +226,86 @@ fn insert_table_metadata_query(
     )
 }
 
+/// Batches each see part of an epoch and can be written in any order, so rows are merged
+/// rather than replaced
+fn insert_epochs_query(
+    items_to_insert: Vec<Epoch>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::epochs::dsl::*;
+
+    (
+        diesel::insert_into(schema::epochs::table)
+            .values(items_to_insert)
+            .on_conflict(epoch)
+            .do_update()
+            .set((
+                start_version.eq(sql::<BigInt>(
+                    "LEAST(epochs.start_version, EXCLUDED.start_version)",
+                )),
+                end_version.eq(sql::<BigInt>(
+                    "GREATEST(epochs.end_version, EXCLUDED.end_version)",
+                )),
+                start_timestamp.eq(sql::<Timestamp>(
+                    "LEAST(epochs.start_timestamp, EXCLUDED.start_timestamp)",
+                )),
+                end_timestamp.eq(sql::<Timestamp>(
+                    "GREATEST(epochs.end_timestamp, EXCLUDED.end_timestamp)",
+                )),
+                start_block_height.eq(sql::<BigInt>(
+                    "LEAST(epochs.start_block_height, EXCLUDED.start_block_height)",
+                )),
+                end_block_height.eq(sql::<BigInt>(
+                    "GREATEST(epochs.end_block_height, EXCLUDED.end_block_height)",
+                )),
+                last_round.eq(sql::<BigInt>(
+                    "GREATEST(epochs.last_round, EXCLUDED.last_round)",
+                )),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        None,
+    )
+}
+
+/// Proposal counts only grow within an epoch, so the highest counts win. The validator set
+/// is only known from the batch that started the epoch.
+fn insert_validator_epoch_stats_query(
+    items_to_insert: Vec<ValidatorEpochStat>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::validator_epoch_stats::dsl::*;
+
+    (
+        diesel::insert_into(schema::validator_epoch_stats::table)
+            .values(items_to_insert)
+            .on_conflict((epoch, validator_index))
+            .do_update()
+            .set((
+                validator_address.eq(sql::<Nullable<VarChar>>(
+                    "COALESCE(EXCLUDED.validator_address, validator_epoch_stats.validator_address)",
+                )),
+                voting_power.eq(sql::<Nullable<Numeric>>(
+                    "COALESCE(EXCLUDED.voting_power, validator_epoch_stats.voting_power)",
+                )),
+                successful_proposals.eq(sql::<BigInt>(
+                    "GREATEST(validator_epoch_stats.successful_proposals, EXCLUDED.successful_proposals)",
+                )),
+                failed_proposals.eq(sql::<BigInt>(
+                    "GREATEST(validator_epoch_stats.failed_proposals, EXCLUDED.failed_proposals)",
+                )),
+                last_transaction_version.eq(sql::<BigInt>(
+                    "GREATEST(validator_epoch_stats.last_transaction_version, EXCLUDED.last_transaction_version)",
+                )),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        None,
+    )
+}
+
 #[async_trait]
 impl ProcessorTrait for DefaultProcessor {
     fn name(&self) -> &'static str {
@@ -213,10 +322,13 @@ impl ProcessorTrait for DefaultProcessor {
         let processing_start = std::time::Instant::now();
         let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
         let flags = self.deprecated_tables;
-        let (block_metadata_transactions, (table_items, current_table_items, table_metadata)) =
-            tokio::task::spawn_blocking(move || process_transactions(transactions, flags))
-                .await
-                .expect("Failed to spawn_blocking for TransactionModel::from_transactions");
+        let (
+            block_metadata_transactions,
+            (table_items, current_table_items, table_metadata),
+            (epochs, validator_epoch_stats),
+        ) = tokio::task::spawn_blocking(move || process_transactions(transactions, flags))
+            .await
+            .expect("Failed to spawn_blocking for TransactionModel::from_transactions")?;
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
@@ -227,6 +339,7 @@ impl ProcessorTrait for DefaultProcessor {
             end_version,
             &block_metadata_transactions,
             (&table_items, &current_table_items, &table_metadata),
+            (&epochs, &validator_epoch_stats),
             &self.per_table_chunk_sizes,
         )
         .await;
@@ -238,6 +351,8 @@ impl ProcessorTrait for DefaultProcessor {
             drop(table_items);
             drop(current_table_items);
             drop(table_metadata);
+            drop(epochs);
+            drop(validator_epoch_stats);
         });
 
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
@@ -272,15 +387,18 @@ impl ProcessorTrait for DefaultProcessor {
 fn process_transactions(
     transactions: Vec<Transaction>,
     flags: TableFlags,
-) -> (
+) -> anyhow::Result<(
     Vec<BlockMetadataTransaction>,
     (Vec<TableItem>, Vec<CurrentTableItem>, Vec<TableMetadata>),
-) {
+    (Vec<Epoch>, Vec<ValidatorEpochStat>),
+)> {
     let (block_metadata_txns, wsc_details) = TransactionModel::from_transactions(&transactions);
     let mut block_metadata_transactions = vec![];
     for block_metadata_txn in block_metadata_txns {
         block_metadata_transactions.push(block_metadata_txn);
     }
+    let mut epochs = Epoch::from_transactions(&transactions, &block_metadata_transactions);
+    let mut validator_epoch_stats = ValidatorEpochStat::from_transactions(&transactions)?;
     let mut table_items = vec![];
     let mut current_table_items = AHashMap::new();
     let mut table_metadata = AHashMap::new();
@@ -316,9 +434,16 @@ fn process_transactions(
     if flags.contains(TableFlags::TABLE_METADATAS) {
         table_metadata.clear();
     }
+    if flags.contains(TableFlags::EPOCHS) {
+        epochs.clear();
+    }
+    if flags.contains(TableFlags::VALIDATOR_EPOCH_STATS) {
+        validator_epoch_stats.clear();
+    }
 
-    (
+    Ok((
         block_metadata_transactions,
         (table_items, current_table_items, table_metadata),
-    )
+        (epochs, validator_epoch_stats),
+    ))
 }
@@ -9,6 +9,8 @@ use crate::{
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        move_abi_decoder::MoveAbiDecoder,
+        webhook_sink::{send_processed_rows, WebhookSink, WebhookSinkConfig},
     },
 };
 use ahash::AHashMap;
@@ -16,23 +18,45 @@ use anyhow::bail;
 use aptos_protos::transaction::v1::{transaction::TxnData, Transaction};
 use async_trait::async_trait;
 use diesel::{
+    dsl::sql,
     pg::{upsert::excluded, Pg},
     query_builder::QueryFragment,
+    sql_types::{Jsonb, Nullable},
     ExpressionMethods,
 };
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use tracing::error;
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventsProcessorConfig {
+    // Also post the events to an HTTP endpoint
+    #[serde(default)]
+    pub webhook: Option<WebhookSinkConfig>,
+    // Decode event payloads against their module ABIs into events.decoded_data
+    #[serde(default)]
+    pub decode_with_move_abis: bool,
+}
+
 pub struct EventsProcessor {
     connection_pool: ArcDbPool,
     per_table_chunk_sizes: AHashMap<String, usize>,
+    webhook_sink: Option<WebhookSink>,
+    abi_decoder: Option<MoveAbiDecoder>,
 }
 
 impl EventsProcessor {
-    pub fn new(connection_pool: ArcDbPool, per_table_chunk_sizes: AHashMap<String, usize>) -> Self {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: EventsProcessorConfig,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
         Self {
             connection_pool,
             per_table_chunk_sizes,
+            webhook_sink: config.webhook.map(WebhookSink::new),
+            abi_decoder: config.decode_with_move_abis.then(MoveAbiDecoder::default),
         }
     }
 }
@@ -64,6 +88,7 @@ async fn insert_to_db(
     );
     execute_in_chunks(
         conn,
+        "events",
         insert_events_query,
         events,
         get_config_table_chunk_size::<EventModel>("events", per_table_chunk_sizes),
@@ -87,6 +112,11 @@ fn insert_events_query(
             .set((
                 inserted_at.eq(excluded(inserted_at)),
                 indexed_type.eq(excluded(indexed_type)),
+                // Keeps values decoded earlier when reprocessing without decoding, or when the
+                // ABI wasn't available this time
+                decoded_data.eq(sql::<Nullable<Jsonb>>(
+                    "COALESCE(EXCLUDED.decoded_data, events.decoded_data)",
+                )),
             )),
         None,
     )
@@ -108,6 +138,12 @@ impl ProcessorTrait for EventsProcessor {
         let processing_start = std::time::Instant::now();
         let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
 
+        if let Some(abi_decoder) = &self.abi_decoder {
+            abi_decoder
+                .prepare(&mut self.get_conn().await, &transactions)
+                .await?;
+        }
+
         let mut events = vec![];
         for txn in &transactions {
             let txn_version = txn.version as i64;
@@ -134,7 +170,12 @@ impl ProcessorTrait for EventsProcessor {
                 _ => &default,
             };
 
-            let txn_events = EventModel::from_events(raw_events, txn_version, block_height);
+            let mut txn_events = EventModel::from_events(raw_events, txn_version, block_height);
+            if let Some(abi_decoder) = &self.abi_decoder {
+                for (event, raw_event) in txn_events.iter_mut().zip(raw_events) {
+                    event.decoded_data = abi_decoder.decode_event(raw_event);
+                }
+            }
             events.extend(txn_events);
         }
 
@@ -153,15 +194,26 @@ impl ProcessorTrait for EventsProcessor {
 
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
         match tx_result {
-            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
-                DefaultProcessingResult {
+            Ok(_) => {
+                send_processed_rows(
+                    self.webhook_sink.as_ref(),
+                    self.name(),
+                    "events",
                     start_version,
                     end_version,
-                    processing_duration_in_secs,
-                    db_insertion_duration_in_secs,
-                    last_transaction_timestamp,
-                },
-            )),
+                    &events,
+                )
+                .await?;
+                Ok(ProcessingResult::DefaultProcessingResult(
+                    DefaultProcessingResult {
+                        start_version,
+                        end_version,
+                        processing_duration_in_secs,
+                        db_insertion_duration_in_secs,
+                        last_transaction_timestamp,
+                    },
+                ))
+            },
             Err(e) => {
                 error!(
                     start_version = start_version,
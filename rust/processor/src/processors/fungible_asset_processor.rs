@@ -10,6 +10,7 @@ use crate::{
             v2_fungible_asset_balances::{
                 CurrentFungibleAssetBalance, CurrentFungibleAssetMapping,
                 CurrentUnifiedFungibleAssetBalance, FungibleAssetBalance,
+                FungibleAssetBalanceDailySnapshot,
             },
             v2_fungible_asset_utils::{
                 ConcurrentFungibleAssetBalance, ConcurrentFungibleAssetSupply, FeeStatement,
@@ -20,6 +21,9 @@ use crate::{
         object_models::v2_object_utils::{
             ObjectAggregatedData, ObjectAggregatedDataMapping, ObjectWithMetadata, Untransferable,
         },
+        spam_asset_models::spam_assets::{
+            insert_spam_assets_query, SpamAsset, SpamClassifier, SpamClassifierConfig,
+        },
     },
     gap_detectors::ProcessingResult,
     schema,
@@ -27,6 +31,7 @@ use crate::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
         util::{get_entry_function_from_user_request, standardize_address},
+        webhook_sink::{send_processed_rows, WebhookSink, WebhookSinkConfig},
     },
     worker::TableFlags,
 };
@@ -41,23 +46,49 @@ use diesel::{
     ExpressionMethods,
 };
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use tracing::error;
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FungibleAssetProcessorConfig {
+    // Also write end of day primary store balances to fungible_asset_balance_daily_snapshots.
+    // Snapshots are keyed by owner and asset type, so only primary fungible stores are covered:
+    // balances held in other (secondary) fungible stores or in coin stores aren't included.
+    #[serde(default)]
+    pub daily_balance_snapshots: bool,
+    // Also post the fungible asset activities to an HTTP endpoint
+    #[serde(default)]
+    pub webhook: Option<WebhookSinkConfig>,
+    // Also flag spam fungible assets in spam_assets
+    #[serde(default)]
+    pub spam_classifier: Option<SpamClassifierConfig>,
+}
+
 pub struct FungibleAssetProcessor {
     connection_pool: ArcDbPool,
+    config: FungibleAssetProcessorConfig,
     per_table_chunk_sizes: AHashMap<String, usize>,
     deprecated_tables: TableFlags,
+    webhook_sink: Option<WebhookSink>,
+    spam_classifier: Option<SpamClassifier>,
 }
 
 impl FungibleAssetProcessor {
     pub fn new(
         connection_pool: ArcDbPool,
+        config: FungibleAssetProcessorConfig,
         per_table_chunk_sizes: AHashMap<String, usize>,
         deprecated_tables: TableFlags,
     ) -> Self {
         Self {
             connection_pool,
+            webhook_sink: config.webhook.clone().map(WebhookSink::new),
+            spam_classifier: config.spam_classifier.clone().map(|spam_classifier| {
+                SpamClassifier::new(spam_classifier).expect("Invalid spam classifier config")
+            }),
+            config,
             per_table_chunk_sizes,
             deprecated_tables,
         }
@@ -89,6 +120,8 @@ async fn insert_to_db(
         &[CurrentUnifiedFungibleAssetBalance],
     ),
     coin_supply: &[CoinSupply],
+    fungible_asset_balance_daily_snapshots: &[FungibleAssetBalanceDailySnapshot],
+    spam_assets: &[SpamAsset],
     per_table_chunk_sizes: &AHashMap<String, usize>,
 ) -> Result<(), diesel::result::Error> {
     tracing::trace!(
@@ -100,6 +133,7 @@ async fn insert_to_db(
 
     let faa = execute_in_chunks(
         conn.clone(),
+        "fungible_asset_activities",
         insert_fungible_asset_activities_query,
         fungible_asset_activities,
         get_config_table_chunk_size::<FungibleAssetActivity>(
@@ -109,6 +143,7 @@ async fn insert_to_db(
     );
     let fam = execute_in_chunks(
         conn.clone(),
+        "fungible_asset_metadata",
         insert_fungible_asset_metadata_query,
         fungible_asset_metadata,
         get_config_table_chunk_size::<FungibleAssetMetadataModel>(
@@ -118,6 +153,7 @@ async fn insert_to_db(
     );
     let fab = execute_in_chunks(
         conn.clone(),
+        "fungible_asset_balances",
         insert_fungible_asset_balances_query,
         fungible_asset_balances,
         get_config_table_chunk_size::<FungibleAssetBalance>(
@@ -127,6 +163,7 @@ async fn insert_to_db(
     );
     let cfab = execute_in_chunks(
         conn.clone(),
+        "current_fungible_asset_balances_legacy",
         insert_current_fungible_asset_balances_query,
         current_fungible_asset_balances,
         get_config_table_chunk_size::<CurrentFungibleAssetBalance>(
@@ -136,6 +173,7 @@ async fn insert_to_db(
     );
     let cufab_v1 = execute_in_chunks(
         conn.clone(),
+        "current_fungible_asset_balances",
         insert_current_unified_fungible_asset_balances_v1_query,
         current_unified_fungible_asset_balances.0,
         get_config_table_chunk_size::<CurrentUnifiedFungibleAssetBalance>(
@@ -145,6 +183,7 @@ async fn insert_to_db(
     );
     let cufab_v2 = execute_in_chunks(
         conn.clone(),
+        "current_fungible_asset_balances",
         insert_current_unified_fungible_asset_balances_v2_query,
         current_unified_fungible_asset_balances.1,
         get_config_table_chunk_size::<CurrentUnifiedFungibleAssetBalance>(
@@ -153,15 +192,33 @@ async fn insert_to_db(
         ),
     );
     let cs = execute_in_chunks(
-        conn,
+        conn.clone(),
+        "coin_supply",
         insert_coin_supply_query,
         coin_supply,
         get_config_table_chunk_size::<CoinSupply>("coin_supply", per_table_chunk_sizes),
     );
-    let (faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, cs_res) =
-        tokio::join!(faa, fam, fab, cfab, cufab_v1, cufab_v2, cs);
+    let fabds = execute_in_chunks(
+        conn.clone(),
+        "fungible_asset_balance_daily_snapshots",
+        insert_fungible_asset_balance_daily_snapshots_query,
+        fungible_asset_balance_daily_snapshots,
+        get_config_table_chunk_size::<FungibleAssetBalanceDailySnapshot>(
+            "fungible_asset_balance_daily_snapshots",
+            per_table_chunk_sizes,
+        ),
+    );
+    let sa = execute_in_chunks(
+        conn,
+        "spam_assets",
+        insert_spam_assets_query,
+        spam_assets,
+        get_config_table_chunk_size::<SpamAsset>("spam_assets", per_table_chunk_sizes),
+    );
+    let (faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, cs_res, fabds_res, sa_res) =
+        tokio::join!(faa, fam, fab, cfab, cufab_v1, cufab_v2, cs, fabds, sa);
     for res in [
-        faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, cs_res,
+        faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, cs_res, fabds_res, sa_res,
     ] {
         res?;
     }
@@ -345,6 +402,33 @@ fn insert_coin_supply_query(
     )
 }
 
+fn insert_fungible_asset_balance_daily_snapshots_query(
+    items_to_insert: Vec<FungibleAssetBalanceDailySnapshot>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::fungible_asset_balance_daily_snapshots::dsl::*;
+
+    (
+        diesel::insert_into(schema::fungible_asset_balance_daily_snapshots::table)
+            .values(items_to_insert)
+            .on_conflict((owner_address, asset_type, snapshot_date))
+            .do_update()
+            .set(
+                (
+                    storage_id.eq(excluded(storage_id)),
+                    amount.eq(excluded(amount)),
+                    token_standard.eq(excluded(token_standard)),
+                    last_transaction_version.eq(excluded(last_transaction_version)),
+                    last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                    inserted_at.eq(excluded(inserted_at)),
+                )
+            ),
+        Some(" WHERE fungible_asset_balance_daily_snapshots.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
 #[async_trait]
 impl ProcessorTrait for FungibleAssetProcessor {
     fn name(&self) -> &'static str {
@@ -370,6 +454,23 @@ impl ProcessorTrait for FungibleAssetProcessor {
             mut coin_supply,
         ) = parse_v2_coin(&transactions).await;
 
+        // Computed before the deprecated tables are cleared, since the snapshots only need
+        // the balances in memory
+        let fungible_asset_balance_daily_snapshots = if self.config.daily_balance_snapshots {
+            FungibleAssetBalanceDailySnapshot::from_balances(&fungible_asset_balances)
+        } else {
+            vec![]
+        };
+
+        let spam_assets = match &self.spam_classifier {
+            Some(spam_classifier) => classify_spam_assets(
+                spam_classifier,
+                &fungible_asset_metadata,
+                &fungible_asset_activities,
+            ),
+            None => vec![],
+        };
+
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
@@ -418,20 +519,33 @@ impl ProcessorTrait for FungibleAssetProcessor {
             &current_fungible_asset_balances,
             (&coin_balance, &fa_balance),
             &coin_supply,
+            &fungible_asset_balance_daily_snapshots,
+            &spam_assets,
             &self.per_table_chunk_sizes,
         )
         .await;
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
         match tx_result {
-            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
-                DefaultProcessingResult {
+            Ok(_) => {
+                send_processed_rows(
+                    self.webhook_sink.as_ref(),
+                    self.name(),
+                    "fungible_asset_activities",
                     start_version,
                     end_version,
-                    processing_duration_in_secs,
-                    db_insertion_duration_in_secs,
-                    last_transaction_timestamp,
-                },
-            )),
+                    &fungible_asset_activities,
+                )
+                .await?;
+                Ok(ProcessingResult::DefaultProcessingResult(
+                    DefaultProcessingResult {
+                        start_version,
+                        end_version,
+                        processing_duration_in_secs,
+                        db_insertion_duration_in_secs,
+                        last_transaction_timestamp,
+                    },
+                ))
+            },
             Err(err) => {
                 error!(
                     start_version = start_version,
@@ -450,6 +564,43 @@ impl ProcessorTrait for FungibleAssetProcessor {
     }
 }
 
+/// Flags fungible assets by their metadata and by deposits fanning out to many owners.
+fn classify_spam_assets(
+    spam_classifier: &SpamClassifier,
+    fungible_asset_metadata: &[FungibleAssetMetadataModel],
+    fungible_asset_activities: &[FungibleAssetActivity],
+) -> Vec<SpamAsset> {
+    let mut spam_assets: Vec<SpamAsset> = fungible_asset_metadata
+        .iter()
+        .filter_map(|metadata| {
+            spam_classifier.classify_asset(
+                &metadata.asset_type,
+                &metadata.name,
+                Some(&metadata.symbol),
+                metadata.last_transaction_version,
+            )
+        })
+        .collect();
+    spam_assets.extend(
+        spam_classifier.classify_airdrops(
+            fungible_asset_activities
+                .iter()
+                .filter(|activity| {
+                    activity.type_.ends_with("::Deposit")
+                        || activity.type_.ends_with("::DepositEvent")
+                })
+                .filter_map(|activity| {
+                    Some((
+                        activity.transaction_version,
+                        activity.asset_type.as_deref()?,
+                        activity.owner_address.as_deref()?,
+                    ))
+                }),
+        ),
+    );
+    SpamAsset::dedupe(spam_assets)
+}
+
 /// V2 coin is called fungible assets and this flow includes all data from V1 in coin_processor
 async fn parse_v2_coin(
     transactions: &[Transaction],
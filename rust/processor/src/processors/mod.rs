@@ -6,11 +6,13 @@
 
 pub mod account_transactions_processor;
 pub mod ans_processor;
+pub mod custom_event_processor;
 pub mod default_processor;
 pub mod events_processor;
 pub mod fungible_asset_processor;
 pub mod monitoring_processor;
 pub mod nft_metadata_processor;
+pub mod nft_points_processor;
 pub mod objects_processor;
 pub mod parquet_processors;
 pub mod stake_processor;
@@ -21,19 +23,24 @@ pub mod user_transaction_processor;
 use self::{
     account_transactions_processor::AccountTransactionsProcessor,
     ans_processor::{AnsProcessor, AnsProcessorConfig},
+    custom_event_processor::{CustomEventProcessor, CustomEventProcessorConfig},
     default_processor::DefaultProcessor,
-    events_processor::EventsProcessor,
-    fungible_asset_processor::FungibleAssetProcessor,
+    events_processor::{EventsProcessor, EventsProcessorConfig},
+    fungible_asset_processor::{FungibleAssetProcessor, FungibleAssetProcessorConfig},
     monitoring_processor::MonitoringProcessor,
     nft_metadata_processor::{NftMetadataProcessor, NftMetadataProcessorConfig},
+    nft_points_processor::{NftPointsProcessor, NftPointsProcessorConfig},
     objects_processor::{ObjectsProcessor, ObjectsProcessorConfig},
     stake_processor::{StakeProcessor, StakeProcessorConfig},
     token_v2_processor::{TokenV2Processor, TokenV2ProcessorConfig},
     transaction_metadata_processor::TransactionMetadataProcessor,
-    user_transaction_processor::UserTransactionProcessor,
+    user_transaction_processor::{UserTransactionProcessor, UserTransactionProcessorConfig},
 };
 use crate::{
-    db::common::models::processor_status::ProcessorStatus,
+    db::common::models::{
+        backfill_status::{BackfillJob, BackfillStatus},
+        processor_status::ProcessorStatus,
+    },
     gap_detectors::ProcessingResult,
     processors::parquet_processors::{
         parquet_ans_processor::{ParquetAnsProcessor, ParquetAnsProcessorConfig},
@@ -50,11 +57,13 @@ use crate::{
             ParquetTransactionMetadataProcessor, ParquetTransactionMetadataProcessorConfig,
         },
     },
-    schema::processor_status,
+    schema::{backfill_status, processor_status},
     utils::{
+        batch_transaction::ReadConnection,
         counters::{GOT_CONNECTION_COUNT, UNABLE_TO_GET_CONNECTION_COUNT},
         database::{execute_with_better_error, ArcDbPool, DbPoolConnection},
         util::parse_timestamp,
+        write_context,
     },
 };
 use aptos_protos::transaction::v1::Transaction as ProtoTransaction;
@@ -88,6 +97,12 @@ pub trait ProcessorTrait: Send + Sync + Debug {
         db_chain_id: Option<u64>,
     ) -> anyhow::Result<ProcessingResult>;
 
+    /// Uploads whatever is buffered across batches, called once the processor has no more
+    /// transactions to process. Only parquet processors buffer anything.
+    async fn flush(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     /// Gets a reference to the connection pool
     /// This is used by the `get_conn()` helper below
     fn connection_pool(&self) -> &ArcDbPool;
@@ -102,12 +117,10 @@ pub trait ProcessorTrait: Send + Sync + Debug {
 
     /// Gets the connection.
     /// If it was unable to do so (default timeout: 30s), it will keep retrying until it can.
-    #[allow(unknown_lints)]
-    #[allow(elided_named_lifetimes)]
-    async fn get_conn(&self) -> DbPoolConnection {
+    async fn get_conn(&self) -> DbPoolConnection<'static> {
         let pool = self.connection_pool();
         loop {
-            match pool.get().await {
+            match pool.get_owned().await {
                 Ok(conn) => {
                     GOT_CONNECTION_COUNT.inc();
                     return conn;
@@ -126,6 +139,15 @@ pub trait ProcessorTrait: Send + Sync + Debug {
         }
     }
 
+    /// Gets a connection that sees the rows already written for the current batch, i.e. the
+    /// batch's connection with `transactional_batch_writes`. Drop it before inserting
+    async fn get_read_conn(&self) -> ReadConnection {
+        match write_context::connection() {
+            Some(conn) => ReadConnection::Batch(conn.lock_owned().await),
+            None => ReadConnection::Pooled(self.get_conn().await),
+        }
+    }
+
     /// Store last processed version from database. We can assume that all previously processed
     /// versions are successful because any gap would cause the processor to panic
     async fn update_last_processed_version(
@@ -157,6 +179,36 @@ pub trait ProcessorTrait: Send + Sync + Debug {
         .await?;
         Ok(())
     }
+
+    /// Store last processed version of a backfill. This leaves the processor_status of the live
+    /// processor untouched.
+    async fn update_last_backfilled_version(
+        &self,
+        backfill_job: &BackfillJob,
+        version: u64,
+        last_transaction_timestamp: Option<aptos_protos::util::timestamp::Timestamp>,
+    ) -> anyhow::Result<()> {
+        let timestamp = last_transaction_timestamp.map(|t| parse_timestamp(&t, version as i64));
+        let status = BackfillStatus::new(backfill_job, self.name(), version, timestamp);
+        execute_with_better_error(
+            self.get_pool(),
+            diesel::insert_into(backfill_status::table)
+                .values(&status)
+                .on_conflict(backfill_status::backfill_alias)
+                .do_update()
+                .set((
+                    backfill_status::status.eq(excluded(backfill_status::status)),
+                    backfill_status::last_success_version
+                        .eq(excluded(backfill_status::last_success_version)),
+                    backfill_status::last_updated.eq(excluded(backfill_status::last_updated)),
+                    backfill_status::last_transaction_timestamp
+                        .eq(excluded(backfill_status::last_transaction_timestamp)),
+                )),
+            Some(" WHERE backfill_status.last_success_version <= EXCLUDED.last_success_version "),
+        )
+        .await?;
+        Ok(())
+    }
 }
 
 /// This enum captures the configs for all the different processors that are defined.
@@ -194,16 +246,18 @@ pub trait ProcessorTrait: Send + Sync + Debug {
 pub enum ProcessorConfig {
     AccountTransactionsProcessor,
     AnsProcessor(AnsProcessorConfig),
+    CustomEventProcessor(CustomEventProcessorConfig),
     DefaultProcessor,
-    EventsProcessor,
-    FungibleAssetProcessor,
+    EventsProcessor(EventsProcessorConfig),
+    FungibleAssetProcessor(FungibleAssetProcessorConfig),
     MonitoringProcessor,
     NftMetadataProcessor(NftMetadataProcessorConfig),
+    NftPointsProcessor(NftPointsProcessorConfig),
     ObjectsProcessor(ObjectsProcessorConfig),
     StakeProcessor(StakeProcessorConfig),
     TokenV2Processor(TokenV2ProcessorConfig),
     TransactionMetadataProcessor,
-    UserTransactionProcessor,
+    UserTransactionProcessor(UserTransactionProcessorConfig),
     ParquetDefaultProcessor(ParquetDefaultProcessorConfig),
     ParquetFungibleAssetActivitiesProcessor(ParquetFungibleAssetActivitiesProcessorConfig),
     ParquetFungibleAssetProcessor(ParquetFungibleAssetProcessorConfig),
@@ -232,6 +286,100 @@ impl ProcessorConfig {
                 | ProcessorConfig::ParquetFungibleAssetActivitiesProcessor(_)
         )
     }
+
+    /// Postgres tables the processor writes rows to. Keep in sync with the tables passed to
+    /// `execute_in_chunks` by the processor.
+    pub fn table_names(&self) -> Vec<String> {
+        let table_names: &[&str] = match self {
+            ProcessorConfig::AccountTransactionsProcessor => &["account_transactions"],
+            ProcessorConfig::AnsProcessor(_) => &[
+                "current_ans_lookup",
+                "ans_lookup",
+                "current_ans_primary_name",
+                "ans_primary_name",
+                "current_ans_lookup_v2",
+                "ans_lookup_v2",
+                "current_ans_primary_name_v2",
+                "ans_primary_name_v2",
+                "ans_name_events",
+                "address_display_names",
+            ],
+            ProcessorConfig::CustomEventProcessor(config) => {
+                return config
+                    .tables
+                    .iter()
+                    .map(|table| table.table_name.clone())
+                    .collect();
+            },
+            ProcessorConfig::DefaultProcessor => &[
+                "block_metadata_transactions",
+                "table_items",
+                "current_table_items",
+                "table_metadatas",
+                "epochs",
+                "validator_epoch_stats",
+            ],
+            ProcessorConfig::EventsProcessor(_) => &["events"],
+            ProcessorConfig::FungibleAssetProcessor(_) => &[
+                "fungible_asset_activities",
+                "fungible_asset_metadata",
+                "fungible_asset_balances",
+                "current_fungible_asset_balances_legacy",
+                "current_fungible_asset_balances",
+                "coin_supply",
+                "fungible_asset_balance_daily_snapshots",
+                "spam_assets",
+            ],
+            ProcessorConfig::NftPointsProcessor(_) => &["nft_points", "current_nft_points"],
+            ProcessorConfig::ObjectsProcessor(_) => &["objects", "current_objects"],
+            ProcessorConfig::StakeProcessor(_) => &[
+                "current_staking_pool_voter",
+                "proposal_votes",
+                "delegated_staking_activities",
+                "delegator_balances",
+                "current_delegator_balances",
+                "delegated_staking_pools",
+                "delegated_staking_pool_balances",
+                "current_delegated_staking_pool_balances",
+                "current_delegated_voter",
+                "delegated_staking_pool_epoch_rewards",
+                "governance_proposals",
+                "current_governance_proposal_tallies",
+                "current_delegator_staking_rewards",
+            ],
+            ProcessorConfig::TokenV2Processor(_) => &[
+                "collections_v2",
+                "token_datas_v2",
+                "token_ownerships_v2",
+                "current_collections_v2",
+                "current_token_datas_v2",
+                "current_token_ownerships_v2",
+                "token_activities_v2",
+                "current_token_v2_metadata",
+                "current_token_royalty_v1",
+                "current_token_pending_claims",
+                "spam_assets",
+            ],
+            ProcessorConfig::TransactionMetadataProcessor => &[
+                "transaction_size_info",
+                "event_size_info",
+                "write_set_size_info",
+            ],
+            ProcessorConfig::UserTransactionProcessor(_) => {
+                &["user_transactions", "signatures", "transaction_fees"]
+            },
+            ProcessorConfig::MonitoringProcessor
+            | ProcessorConfig::NftMetadataProcessor(_)
+            | ProcessorConfig::ParquetDefaultProcessor(_)
+            | ProcessorConfig::ParquetFungibleAssetActivitiesProcessor(_)
+            | ProcessorConfig::ParquetFungibleAssetProcessor(_)
+            | ProcessorConfig::ParquetTransactionMetadataProcessor(_)
+            | ProcessorConfig::ParquetAnsProcessor(_)
+            | ProcessorConfig::ParquetEventsProcessor(_)
+            | ProcessorConfig::ParquetTokenV2Processor(_) => &[],
+        };
+        table_names.iter().map(|name| name.to_string()).collect()
+    }
 }
 
 /// This enum contains all the processors defined in this crate.
@@ -256,11 +404,13 @@ impl ProcessorConfig {
 pub enum Processor {
     AccountTransactionsProcessor,
     AnsProcessor,
+    CustomEventProcessor,
     DefaultProcessor,
     EventsProcessor,
     FungibleAssetProcessor,
     MonitoringProcessor,
     NftMetadataProcessor,
+    NftPointsProcessor,
     ObjectsProcessor,
     StakeProcessor,
     TokenV2Processor,
@@ -0,0 +1,219 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    db::common::models::token_models::nft_points::{CurrentNftPoints, NftPoints},
+    gap_detectors::ProcessingResult,
+    schema,
+    utils::{
+        batch_transaction,
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+    },
+};
+use ahash::AHashMap;
+use anyhow::bail;
+use aptos_protos::transaction::v1::Transaction;
+use async_trait::async_trait;
+use diesel::{
+    pg::{upsert::excluded, Pg},
+    query_builder::QueryFragment,
+    ExpressionMethods,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tracing::error;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NftPointsProcessorConfig {
+    // Entry functions awarding points, e.g. "0x...::points::add_points". Their arguments must be
+    // (owner address, token name, amount, point type)
+    pub nft_points_contracts: Vec<String>,
+}
+
+pub struct NftPointsProcessor {
+    connection_pool: ArcDbPool,
+    config: NftPointsProcessorConfig,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+}
+
+impl NftPointsProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: NftPointsProcessorConfig,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        Self {
+            connection_pool,
+            config,
+            per_table_chunk_sizes,
+        }
+    }
+}
+
+impl Debug for NftPointsProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "NftPointsProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+async fn insert_to_db(
+    conn: ArcDbPool,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    nft_points: &[NftPoints],
+    per_table_chunk_sizes: &AHashMap<String, usize>,
+) -> Result<(), diesel::result::Error> {
+    tracing::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+    // The totals are summed from nft_points, so the history has to be written first
+    execute_in_chunks(
+        conn.clone(),
+        "nft_points",
+        insert_nft_points_query,
+        nft_points,
+        get_config_table_chunk_size::<NftPoints>("nft_points", per_table_chunk_sizes),
+    )
+    .await?;
+
+    // With transactional batch writes the new rows are only visible on the batch's connection
+    let current_nft_points = {
+        let mut db_conn = batch_transaction::read_connection(&conn).await?;
+        CurrentNftPoints::get_totals_from_db(nft_points, &mut db_conn).await?
+    };
+    execute_in_chunks(
+        conn,
+        "current_nft_points",
+        insert_current_nft_points_query,
+        &current_nft_points,
+        get_config_table_chunk_size::<CurrentNftPoints>(
+            "current_nft_points",
+            per_table_chunk_sizes,
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+fn insert_nft_points_query(
+    items_to_insert: Vec<NftPoints>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::nft_points::dsl::*;
+
+    (
+        diesel::insert_into(schema::nft_points::table)
+            .values(items_to_insert)
+            .on_conflict(transaction_version)
+            .do_nothing(),
+        None,
+    )
+}
+
+fn insert_current_nft_points_query(
+    items_to_insert: Vec<CurrentNftPoints>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_nft_points::dsl::*;
+
+    (
+        diesel::insert_into(schema::current_nft_points::table)
+            .values(items_to_insert)
+            .on_conflict((owner_address, point_type))
+            .do_update()
+            .set((
+                total_amount.eq(excluded(total_amount)),
+                num_transactions.eq(excluded(num_transactions)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE current_nft_points.num_transactions <= excluded.num_transactions "),
+    )
+}
+
+#[async_trait]
+impl ProcessorTrait for NftPointsProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::NftPointsProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let nft_points = transactions
+            .iter()
+            .filter_map(|txn| {
+                self.config
+                    .nft_points_contracts
+                    .iter()
+                    .find_map(|contract| NftPoints::from_transaction(txn, Some(contract.clone())))
+            })
+            .collect::<Vec<_>>();
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        let tx_result = if nft_points.is_empty() {
+            Ok(())
+        } else {
+            insert_to_db(
+                self.get_pool(),
+                self.name(),
+                start_version,
+                end_version,
+                &nft_points,
+                &self.per_table_chunk_sizes,
+            )
+            .await
+        };
+
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version,
+                    end_version,
+                    processing_duration_in_secs,
+                    db_insertion_duration_in_secs,
+                    last_transaction_timestamp,
+                },
+            )),
+            Err(e) => {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            },
+        }
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
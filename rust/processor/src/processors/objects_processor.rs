@@ -88,12 +88,14 @@ async fn insert_to_db(
 
     let io = execute_in_chunks(
         conn.clone(),
+        "objects",
         insert_objects_query,
         objects,
         get_config_table_chunk_size::<Object>("objects", per_table_chunk_sizes),
     );
     let co = execute_in_chunks(
         conn,
+        "current_objects",
         insert_current_objects_query,
         current_objects,
         get_config_table_chunk_size::<CurrentObject>("current_objects", per_table_chunk_sizes),
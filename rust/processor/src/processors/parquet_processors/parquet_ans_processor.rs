@@ -4,8 +4,10 @@
 use super::ParquetProcessorTrait;
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::ans_models::{
         ans_lookup::CurrentAnsPrimaryName,
@@ -30,8 +32,14 @@ use tracing::error;
 #[serde(deny_unknown_fields)]
 pub struct ParquetAnsProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub ans_v1_primary_names_table_handle: String,
@@ -49,7 +57,7 @@ impl ParquetProcessorTrait for ParquetAnsProcessorConfig {
 pub struct ParquetAnsProcessor {
     connection_pool: ArcDbPool,
     config: ParquetAnsProcessorConfig,
-    ans_primary_name_v2_sender: AsyncSender<ParquetDataGeneric<AnsPrimaryNameV2>>,
+    ans_primary_name_v2_sender: ParquetHandlerSender<AnsPrimaryNameV2>,
 }
 
 impl ParquetAnsProcessor {
@@ -65,6 +73,8 @@ impl ParquetAnsProcessor {
             ProcessorName::ParquetAnsProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -94,6 +104,11 @@ impl ProcessorTrait for ParquetAnsProcessor {
         ProcessorName::ParquetAnsProcessor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.ans_primary_name_v2_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
@@ -3,8 +3,10 @@
 
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::default_models::{
         parquet_move_modules::MoveModule,
@@ -32,8 +34,14 @@ use std::{
 #[serde(deny_unknown_fields)]
 pub struct ParquetDefaultProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -46,11 +54,11 @@ impl ParquetProcessorTrait for ParquetDefaultProcessorConfig {
 
 pub struct ParquetDefaultProcessor {
     connection_pool: ArcDbPool,
-    transaction_sender: AsyncSender<ParquetDataGeneric<ParquetTransaction>>,
-    move_resource_sender: AsyncSender<ParquetDataGeneric<MoveResource>>,
-    wsc_sender: AsyncSender<ParquetDataGeneric<WriteSetChangeModel>>,
-    table_item_sender: AsyncSender<ParquetDataGeneric<TableItem>>,
-    move_module_sender: AsyncSender<ParquetDataGeneric<MoveModule>>,
+    transaction_sender: ParquetHandlerSender<ParquetTransaction>,
+    move_resource_sender: ParquetHandlerSender<MoveResource>,
+    wsc_sender: ParquetHandlerSender<WriteSetChangeModel>,
+    table_item_sender: ParquetHandlerSender<TableItem>,
+    move_module_sender: ParquetHandlerSender<MoveModule>,
 }
 
 // TODO: Since each table item has different size allocated, the pace of being backfilled to PQ varies a lot.
@@ -68,6 +76,8 @@ impl ParquetDefaultProcessor {
             ProcessorName::ParquetDefaultProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -78,6 +88,8 @@ impl ParquetDefaultProcessor {
             ProcessorName::ParquetDefaultProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -88,6 +100,8 @@ impl ParquetDefaultProcessor {
             ProcessorName::ParquetDefaultProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -98,6 +112,8 @@ impl ParquetDefaultProcessor {
             ProcessorName::ParquetDefaultProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -107,6 +123,8 @@ impl ParquetDefaultProcessor {
             ProcessorName::ParquetDefaultProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -143,6 +161,15 @@ impl ProcessorTrait for ParquetDefaultProcessor {
         ProcessorName::ParquetDefaultProcessor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.transaction_sender.flush().await?;
+        self.move_resource_sender.flush().await?;
+        self.wsc_sender.flush().await?;
+        self.table_item_sender.flush().await?;
+        self.move_module_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
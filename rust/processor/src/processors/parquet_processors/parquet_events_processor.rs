@@ -3,8 +3,10 @@
 
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::events_models::parquet_events::{Event, ParquetEventModel},
     gap_detectors::ProcessingResult,
@@ -23,8 +25,14 @@ use tracing::warn;
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ParquetEventsProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -38,7 +46,7 @@ impl ParquetProcessorTrait for ParquetEventsProcessorConfig {
 
 pub struct ParquetEventsProcessor {
     connection_pool: ArcDbPool,
-    event_sender: AsyncSender<ParquetDataGeneric<Event>>,
+    event_sender: ParquetHandlerSender<Event>,
 }
 
 impl ParquetEventsProcessor {
@@ -54,6 +62,8 @@ impl ParquetEventsProcessor {
             ProcessorName::ParquetDefaultProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -82,6 +92,11 @@ impl ProcessorTrait for ParquetEventsProcessor {
         ProcessorName::ParquetEventsProcessor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.event_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
@@ -4,8 +4,10 @@
 use super::ParquetProcessorTrait;
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::{
         fungible_asset_models::{
@@ -40,8 +42,14 @@ use std::{fmt::Debug, time::Duration};
 #[serde(deny_unknown_fields)]
 pub struct ParquetFungibleAssetActivitiesProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -55,7 +63,7 @@ impl ParquetProcessorTrait for ParquetFungibleAssetActivitiesProcessorConfig {
 
 pub struct ParquetFungibleAssetActivitiesProcessor {
     connection_pool: ArcDbPool,
-    fungible_asset_activities_sender: AsyncSender<ParquetDataGeneric<FungibleAssetActivity>>,
+    fungible_asset_activities_sender: ParquetHandlerSender<FungibleAssetActivity>,
 }
 
 impl ParquetFungibleAssetActivitiesProcessor {
@@ -66,13 +74,13 @@ impl ParquetFungibleAssetActivitiesProcessor {
     ) -> Self {
         config.set_google_credentials(config.google_application_credentials.clone());
 
-        let fungible_asset_activities_sender: AsyncSender<
-            ParquetDataGeneric<FungibleAssetActivity>,
-        > = create_parquet_handler_loop::<FungibleAssetActivity>(
+        let fungible_asset_activities_sender = create_parquet_handler_loop::<FungibleAssetActivity>(
             new_gap_detector_sender.clone(),
             ProcessorName::ParquetFungibleAssetActivitiesProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -101,6 +109,11 @@ impl ProcessorTrait for ParquetFungibleAssetActivitiesProcessor {
         ProcessorName::ParquetFungibleAssetActivitiesProcessor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.fungible_asset_activities_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
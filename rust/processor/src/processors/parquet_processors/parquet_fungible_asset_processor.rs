@@ -4,8 +4,10 @@
 use super::ParquetProcessorTrait;
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::{
         fungible_asset_models::{
@@ -33,8 +35,14 @@ use std::{fmt::Debug, time::Duration};
 #[serde(deny_unknown_fields)]
 pub struct ParquetFungibleAssetProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -48,8 +56,8 @@ impl ParquetProcessorTrait for ParquetFungibleAssetProcessorConfig {
 
 pub struct ParquetFungibleAssetProcessor {
     connection_pool: ArcDbPool,
-    coin_supply_sender: AsyncSender<ParquetDataGeneric<CoinSupply>>,
-    fungible_asset_balances_sender: AsyncSender<ParquetDataGeneric<FungibleAssetBalance>>,
+    coin_supply_sender: ParquetHandlerSender<CoinSupply>,
+    fungible_asset_balances_sender: ParquetHandlerSender<FungibleAssetBalance>,
 }
 
 impl ParquetFungibleAssetProcessor {
@@ -65,6 +73,8 @@ impl ParquetFungibleAssetProcessor {
             ProcessorName::ParquetFungibleAssetProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -75,6 +85,8 @@ impl ParquetFungibleAssetProcessor {
             ProcessorName::ParquetFungibleAssetProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -105,6 +117,12 @@ impl ProcessorTrait for ParquetFungibleAssetProcessor {
         ProcessorName::ParquetFungibleAssetProcessor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.coin_supply_sender.flush().await?;
+        self.fungible_asset_balances_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
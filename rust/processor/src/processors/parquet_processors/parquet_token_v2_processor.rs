@@ -3,8 +3,10 @@
 
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::{
         fungible_asset_models::v2_fungible_asset_utils::FungibleAssetMetadata,
@@ -43,8 +45,14 @@ use std::{fmt::Debug, time::Duration};
 #[serde(deny_unknown_fields)]
 pub struct ParquetTokenV2ProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -57,8 +65,8 @@ impl ParquetProcessorTrait for ParquetTokenV2ProcessorConfig {
 
 pub struct ParquetTokenV2Processor {
     connection_pool: ArcDbPool,
-    v2_token_datas_sender: AsyncSender<ParquetDataGeneric<TokenDataV2>>,
-    v2_token_ownerships_sender: AsyncSender<ParquetDataGeneric<TokenOwnershipV2>>,
+    v2_token_datas_sender: ParquetHandlerSender<TokenDataV2>,
+    v2_token_ownerships_sender: ParquetHandlerSender<TokenOwnershipV2>,
 }
 
 impl ParquetTokenV2Processor {
@@ -74,6 +82,8 @@ impl ParquetTokenV2Processor {
             ProcessorName::ParquetTokenV2Processor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -84,6 +94,8 @@ impl ParquetTokenV2Processor {
             ProcessorName::ParquetTokenV2Processor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -114,6 +126,12 @@ impl ProcessorTrait for ParquetTokenV2Processor {
         ProcessorName::ParquetTokenV2Processor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.v2_token_datas_sender.flush().await?;
+        self.v2_token_ownerships_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
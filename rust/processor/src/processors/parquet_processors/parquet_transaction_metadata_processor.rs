@@ -3,8 +3,10 @@
 
 use crate::{
     bq_analytics::{
-        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        create_parquet_handler_loop,
+        generic_parquet_processor::{ParquetDataGeneric, ParquetFileConfig},
+        object_store::ObjectStoreConfig,
+        ParquetHandlerSender, ParquetProcessingResult,
     },
     db::common::models::transaction_metadata_model::parquet_write_set_size_info::WriteSetSize,
     gap_detectors::ProcessingResult,
@@ -24,8 +26,14 @@ use tracing::warn;
 #[serde(deny_unknown_fields)]
 pub struct ParquetTransactionMetadataProcessorConfig {
     pub google_application_credentials: Option<String>,
+    // Unused when writing to the local file system
+    #[serde(default)]
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub object_store: ObjectStoreConfig,
+    #[serde(default)]
+    pub parquet_file: ParquetFileConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -39,7 +47,7 @@ impl ParquetProcessorTrait for ParquetTransactionMetadataProcessorConfig {
 
 pub struct ParquetTransactionMetadataProcessor {
     connection_pool: ArcDbPool,
-    write_set_size_info_sender: AsyncSender<ParquetDataGeneric<WriteSetSize>>,
+    write_set_size_info_sender: ParquetHandlerSender<WriteSetSize>,
 }
 
 impl ParquetTransactionMetadataProcessor {
@@ -55,6 +63,8 @@ impl ParquetTransactionMetadataProcessor {
             ProcessorName::ParquetTransactionMetadataProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.object_store.clone(),
+            config.parquet_file.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -82,6 +92,11 @@ impl ProcessorTrait for ParquetTransactionMetadataProcessor {
         ProcessorName::ParquetTransactionMetadataProcessor.into()
     }
 
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.write_set_size_info_sender.flush().await?;
+        Ok(())
+    }
+
     async fn process_transactions(
         &self,
         transactions: Vec<Transaction>,
@@ -12,6 +12,12 @@ use crate::{
         delegator_pools::{
             CurrentDelegatorPoolBalance, DelegatorPool, DelegatorPoolBalance, DelegatorPoolMap,
         },
+        delegator_rewards::{
+            CurrentDelegatorStakingReward, DelegatorPoolEpochReward, DelegatorPoolEpochRewardMap,
+        },
+        governance_proposals::{
+            CurrentGovernanceProposalTally, GovernanceProposal, GovernanceProposalMap,
+        },
         proposal_votes::ProposalVote,
         stake_utils::DelegationVoteGovernanceRecordsResource,
         staking_pool_voter::{CurrentStakingPoolVoter, StakingPoolVoterMap},
@@ -19,6 +25,7 @@ use crate::{
     gap_detectors::ProcessingResult,
     schema,
     utils::{
+        batch_transaction,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
         util::{parse_timestamp, standardize_address},
     },
@@ -29,8 +36,10 @@ use anyhow::bail;
 use aptos_protos::transaction::v1::{write_set_change::Change, Transaction};
 use async_trait::async_trait;
 use diesel::{
+    dsl::sql,
     pg::{upsert::excluded, Pg},
     query_builder::QueryFragment,
+    sql_types::{BigInt, Bool, Nullable, Numeric, Text, Timestamp, VarChar},
     ExpressionMethods,
 };
 use serde::{Deserialize, Serialize};
@@ -77,6 +86,7 @@ impl Debug for StakeProcessor {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn insert_to_db(
     conn: ArcDbPool,
     name: &'static str,
@@ -91,6 +101,9 @@ async fn insert_to_db(
     delegator_pool_balances: &[DelegatorPoolBalance],
     current_delegator_pool_balances: &[CurrentDelegatorPoolBalance],
     current_delegated_voter: &[CurrentDelegatedVoter],
+    delegator_pool_epoch_rewards: &[DelegatorPoolEpochReward],
+    governance_proposals: &[GovernanceProposal],
+    current_governance_proposal_tallies: &[CurrentGovernanceProposalTally],
     per_table_chunk_sizes: &AHashMap<String, usize>,
 ) -> Result<(), diesel::result::Error> {
     tracing::trace!(
@@ -102,6 +115,7 @@ async fn insert_to_db(
 
     let cspv = execute_in_chunks(
         conn.clone(),
+        "current_staking_pool_voter",
         insert_current_stake_pool_voter_query,
         current_stake_pool_voters,
         get_config_table_chunk_size::<CurrentStakingPoolVoter>(
@@ -111,12 +125,14 @@ async fn insert_to_db(
     );
     let pv = execute_in_chunks(
         conn.clone(),
+        "proposal_votes",
         insert_proposal_votes_query,
         proposal_votes,
         get_config_table_chunk_size::<ProposalVote>("proposal_votes", per_table_chunk_sizes),
     );
     let da = execute_in_chunks(
         conn.clone(),
+        "delegated_staking_activities",
         insert_delegator_activities_query,
         delegator_actvities,
         get_config_table_chunk_size::<DelegatedStakingActivity>(
@@ -126,6 +142,7 @@ async fn insert_to_db(
     );
     let db = execute_in_chunks(
         conn.clone(),
+        "delegator_balances",
         insert_delegator_balances_query,
         delegator_balances,
         get_config_table_chunk_size::<DelegatorBalance>(
@@ -135,6 +152,7 @@ async fn insert_to_db(
     );
     let cdb = execute_in_chunks(
         conn.clone(),
+        "current_delegator_balances",
         insert_current_delegator_balances_query,
         current_delegator_balances,
         get_config_table_chunk_size::<CurrentDelegatorBalance>(
@@ -144,6 +162,7 @@ async fn insert_to_db(
     );
     let dp = execute_in_chunks(
         conn.clone(),
+        "delegated_staking_pools",
         insert_delegator_pools_query,
         delegator_pools,
         get_config_table_chunk_size::<DelegatorPool>(
@@ -153,6 +172,7 @@ async fn insert_to_db(
     );
     let dpb = execute_in_chunks(
         conn.clone(),
+        "delegated_staking_pool_balances",
         insert_delegator_pool_balances_query,
         delegator_pool_balances,
         get_config_table_chunk_size::<DelegatorPoolBalance>(
@@ -162,6 +182,7 @@ async fn insert_to_db(
     );
     let cdpb = execute_in_chunks(
         conn.clone(),
+        "current_delegated_staking_pool_balances",
         insert_current_delegator_pool_balances_query,
         current_delegator_pool_balances,
         get_config_table_chunk_size::<CurrentDelegatorPoolBalance>(
@@ -170,7 +191,8 @@ async fn insert_to_db(
         ),
     );
     let cdv = execute_in_chunks(
-        conn,
+        conn.clone(),
+        "current_delegated_voter",
         insert_current_delegated_voter_query,
         current_delegated_voter,
         get_config_table_chunk_size::<CurrentDelegatedVoter>(
@@ -178,15 +200,97 @@ async fn insert_to_db(
             per_table_chunk_sizes,
         ),
     );
+    let dper = execute_in_chunks(
+        conn.clone(),
+        "delegated_staking_pool_epoch_rewards",
+        insert_delegator_pool_epoch_rewards_query,
+        delegator_pool_epoch_rewards,
+        get_config_table_chunk_size::<DelegatorPoolEpochReward>(
+            "delegated_staking_pool_epoch_rewards",
+            per_table_chunk_sizes,
+        ),
+    );
+    let gp = execute_in_chunks(
+        conn.clone(),
+        "governance_proposals",
+        insert_governance_proposals_query,
+        governance_proposals,
+        get_config_table_chunk_size::<GovernanceProposal>(
+            "governance_proposals",
+            per_table_chunk_sizes,
+        ),
+    );
+    let cgpt = execute_in_chunks(
+        conn.clone(),
+        "current_governance_proposal_tallies",
+        insert_current_governance_proposal_tallies_query,
+        current_governance_proposal_tallies,
+        get_config_table_chunk_size::<CurrentGovernanceProposalTally>(
+            "current_governance_proposal_tallies",
+            per_table_chunk_sizes,
+        ),
+    );
 
-    let (cspv_res, pv_res, da_res, db_res, cdb_res, dp_res, dpb_res, cdpb_res, cdv_res) =
-        futures::join!(cspv, pv, da, db, cdb, dp, dpb, cdpb, cdv);
+    let (
+        cspv_res,
+        pv_res,
+        da_res,
+        db_res,
+        cdb_res,
+        dp_res,
+        dpb_res,
+        cdpb_res,
+        cdv_res,
+        dper_res,
+        gp_res,
+        cgpt_res,
+    ) = futures::join!(cspv, pv, da, db, cdb, dp, dpb, cdpb, cdv, dper, gp, cgpt);
     for res in [
-        cspv_res, pv_res, da_res, db_res, cdb_res, dp_res, dpb_res, cdpb_res, cdv_res,
+        cspv_res, pv_res, da_res, db_res, cdb_res, dp_res, dpb_res, cdpb_res, cdv_res, dper_res,
+        gp_res, cgpt_res,
     ] {
         res?;
     }
 
+    // Rewards are derived from the history in the tables rather than from the previous batch, so
+    // they come out the same whatever order batches are written in. They're read back once the
+    // batch is written, on the batch's connection with transactional batch writes.
+    let (relinked_delegator_pool_epoch_rewards, current_delegator_staking_rewards) = {
+        let mut db_conn = batch_transaction::read_connection(&conn).await?;
+        (
+            DelegatorPoolEpochReward::relink(delegator_pool_epoch_rewards, &mut db_conn).await?,
+            CurrentDelegatorStakingReward::from_history(
+                delegator_balances,
+                delegator_pool_balances,
+                &mut db_conn,
+            )
+            .await?,
+        )
+    };
+    let rdper = execute_in_chunks(
+        conn.clone(),
+        "delegated_staking_pool_epoch_rewards",
+        insert_delegator_pool_epoch_rewards_query,
+        &relinked_delegator_pool_epoch_rewards,
+        get_config_table_chunk_size::<DelegatorPoolEpochReward>(
+            "delegated_staking_pool_epoch_rewards",
+            per_table_chunk_sizes,
+        ),
+    );
+    let cdsr = execute_in_chunks(
+        conn,
+        "current_delegator_staking_rewards",
+        insert_current_delegator_staking_rewards_query,
+        &current_delegator_staking_rewards,
+        get_config_table_chunk_size::<CurrentDelegatorStakingReward>(
+            "current_delegator_staking_rewards",
+            per_table_chunk_sizes,
+        ),
+    );
+    let (rdper_res, cdsr_res) = futures::join!(rdper, cdsr);
+    rdper_res?;
+    cdsr_res?;
+
     Ok(())
 }
 
@@ -382,6 +486,153 @@ fn insert_current_delegated_voter_query(
     )
 }
 
+fn insert_delegator_pool_epoch_rewards_query(
+    items_to_insert: Vec<DelegatorPoolEpochReward>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::delegated_staking_pool_epoch_rewards::dsl::*;
+
+    (diesel::insert_into(schema::delegated_staking_pool_epoch_rewards::table)
+         .values(items_to_insert)
+         .on_conflict((staking_pool_address, epoch))
+         .do_update()
+         .set((
+             transaction_version.eq(excluded(transaction_version)),
+             transaction_timestamp.eq(excluded(transaction_timestamp)),
+             total_coins.eq(excluded(total_coins)),
+             total_shares.eq(excluded(total_shares)),
+             share_price.eq(excluded(share_price)),
+             operator_commission_percentage.eq(excluded(operator_commission_percentage)),
+             previous_transaction_version.eq(excluded(previous_transaction_version)),
+             previous_transaction_timestamp.eq(excluded(previous_transaction_timestamp)),
+             previous_share_price.eq(excluded(previous_share_price)),
+             rewards_amount.eq(excluded(rewards_amount)),
+             apr.eq(excluded(apr)),
+             inserted_at.eq(excluded(inserted_at)),
+         )),
+     // A row read back before another batch landed is linked to an older previous row
+     Some(
+         " WHERE delegated_staking_pool_epoch_rewards.transaction_version < EXCLUDED.transaction_version OR (delegated_staking_pool_epoch_rewards.transaction_version = EXCLUDED.transaction_version AND COALESCE(delegated_staking_pool_epoch_rewards.previous_transaction_version, -1) <= COALESCE(EXCLUDED.previous_transaction_version, -1)) ",
+     ),
+    )
+}
+
+fn insert_current_delegator_staking_rewards_query(
+    items_to_insert: Vec<CurrentDelegatorStakingReward>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_delegator_staking_rewards::dsl::*;
+
+    (diesel::insert_into(schema::current_delegator_staking_rewards::table)
+         .values(items_to_insert)
+         .on_conflict((delegator_address, pool_address))
+         .do_update()
+         .set((
+             active_shares.eq(excluded(active_shares)),
+             cost_basis.eq(excluded(cost_basis)),
+             realized_rewards.eq(excluded(realized_rewards)),
+             accrued_rewards.eq(excluded(accrued_rewards)),
+             share_price.eq(excluded(share_price)),
+             last_transaction_version.eq(excluded(last_transaction_version)),
+             num_share_changes.eq(excluded(num_share_changes)),
+             inserted_at.eq(excluded(inserted_at)),
+         )),
+     // A replay that missed another batch's history has applied fewer changes
+     Some(
+         " WHERE current_delegator_staking_rewards.num_share_changes <= EXCLUDED.num_share_changes ",
+     ),
+    )
+}
+
+fn insert_governance_proposals_query(
+    items_to_insert: Vec<GovernanceProposal>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::governance_proposals::dsl::*;
+
+    // Who created the proposal is only known in the creating transaction, so rows from later
+    // transactions keep it. The creating batch can be written after a later one, in which case it
+    // only fills in the creator and leaves the rest as of the later row.
+    (diesel::insert_into(schema::governance_proposals::table)
+         .values(items_to_insert)
+         .on_conflict(proposal_id)
+         .do_update()
+         .set((
+             proposer_address.eq(sql::<VarChar>(&latest_governance_proposal_value("proposer_address"))),
+             stake_pool_address.eq(sql::<Nullable<VarChar>>(
+                 "COALESCE(EXCLUDED.stake_pool_address, governance_proposals.stake_pool_address)",
+             )),
+             delegated_voter_address.eq(sql::<Nullable<VarChar>>(
+                 "COALESCE(EXCLUDED.delegated_voter_address, governance_proposals.delegated_voter_address)",
+             )),
+             execution_hash.eq(sql::<VarChar>(&latest_governance_proposal_value("execution_hash"))),
+             metadata_location.eq(sql::<Nullable<Text>>(&latest_governance_proposal_value("metadata_location"))),
+             metadata_hash.eq(sql::<Nullable<Text>>(&latest_governance_proposal_value("metadata_hash"))),
+             min_vote_threshold.eq(sql::<Numeric>(&latest_governance_proposal_value("min_vote_threshold"))),
+             early_resolution_vote_threshold.eq(sql::<Nullable<Numeric>>(&latest_governance_proposal_value("early_resolution_vote_threshold"))),
+             creation_timestamp.eq(sql::<Timestamp>(&latest_governance_proposal_value("creation_timestamp"))),
+             expiration_timestamp.eq(sql::<Timestamp>(&latest_governance_proposal_value("expiration_timestamp"))),
+             creation_transaction_version.eq(sql::<Nullable<BigInt>>(
+                 "COALESCE(EXCLUDED.creation_transaction_version, governance_proposals.creation_transaction_version)",
+             )),
+             is_resolved.eq(sql::<Bool>(&latest_governance_proposal_value("is_resolved"))),
+             resolved_early.eq(sql::<Nullable<Bool>>(&latest_governance_proposal_value("resolved_early"))),
+             resolution_timestamp.eq(sql::<Nullable<Timestamp>>(&latest_governance_proposal_value("resolution_timestamp"))),
+             resolution_transaction_version.eq(sql::<Nullable<BigInt>>(&latest_governance_proposal_value("resolution_transaction_version"))),
+             last_transaction_version.eq(sql::<BigInt>(
+                 "GREATEST(governance_proposals.last_transaction_version, EXCLUDED.last_transaction_version)",
+             )),
+             inserted_at.eq(excluded(inserted_at)),
+         )),
+     Some(
+         " WHERE governance_proposals.last_transaction_version <= EXCLUDED.last_transaction_version OR (governance_proposals.creation_transaction_version IS NULL AND EXCLUDED.creation_transaction_version IS NOT NULL) ",
+     ),
+    )
+}
+
+/// The column's value from the newer of the existing and the inserted governance proposal row
+fn latest_governance_proposal_value(column: &str) -> String {
+    format!(
+        "CASE WHEN governance_proposals.last_transaction_version <= EXCLUDED.last_transaction_version THEN EXCLUDED.{0} ELSE governance_proposals.{0} END",
+        column
+    )
+}
+
+fn insert_current_governance_proposal_tallies_query(
+    items_to_insert: Vec<CurrentGovernanceProposalTally>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_governance_proposal_tallies::dsl::*;
+
+    (diesel::insert_into(schema::current_governance_proposal_tallies::table)
+         .values(items_to_insert)
+         .on_conflict(proposal_id)
+         .do_update()
+         .set((
+             yes_votes.eq(excluded(yes_votes)),
+             no_votes.eq(excluded(no_votes)),
+             total_votes.eq(excluded(total_votes)),
+             min_vote_threshold.eq(excluded(min_vote_threshold)),
+             is_passing.eq(excluded(is_passing)),
+             is_resolved.eq(excluded(is_resolved)),
+             last_transaction_version.eq(excluded(last_transaction_version)),
+             last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+             inserted_at.eq(excluded(inserted_at)),
+         )),
+     Some(
+         " WHERE current_governance_proposal_tallies.last_transaction_version <= EXCLUDED.last_transaction_version ",
+     ),
+    )
+}
+
 #[async_trait]
 impl ProcessorTrait for StakeProcessor {
     fn name(&self) -> &'static str {
@@ -398,18 +649,21 @@ impl ProcessorTrait for StakeProcessor {
         let processing_start = std::time::Instant::now();
         let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
 
-        let mut conn = self.get_conn().await;
+        let mut conn = self.get_read_conn().await;
         let query_retries = self.config.query_retries;
         let query_retry_delay_ms = self.config.query_retry_delay_ms;
 
         let mut all_current_stake_pool_voters: StakingPoolVoterMap = AHashMap::new();
         let mut all_proposal_votes = vec![];
+        let mut all_governance_proposals: GovernanceProposalMap = AHashMap::new();
+        let mut all_current_governance_proposal_tallies = AHashMap::new();
         let mut all_delegator_activities = vec![];
         let mut all_delegator_balances = vec![];
         let mut all_current_delegator_balances: CurrentDelegatorBalanceMap = AHashMap::new();
         let mut all_delegator_pools: DelegatorPoolMap = AHashMap::new();
         let mut all_delegator_pool_balances = vec![];
         let mut all_current_delegator_pool_balances = AHashMap::new();
+        let mut all_delegator_pool_epoch_rewards: DelegatorPoolEpochRewardMap = AHashMap::new();
 
         let mut active_pool_to_staking_pool = AHashMap::new();
         // structs needed to get delegated voters
@@ -423,22 +677,49 @@ impl ProcessorTrait for StakeProcessor {
             let mut proposal_votes = ProposalVote::from_transaction(txn).unwrap();
             all_proposal_votes.append(&mut proposal_votes);
 
+            // Add governance proposals
+            let (governance_proposals, current_governance_proposal_tallies) =
+                GovernanceProposal::from_transaction(txn).unwrap();
+            for (proposal_id, mut proposal) in governance_proposals {
+                if let Some(previous) = all_governance_proposals.get(&proposal_id) {
+                    proposal.merge_previous(previous);
+                }
+                all_governance_proposals.insert(proposal_id, proposal);
+            }
+            all_current_governance_proposal_tallies.extend(current_governance_proposal_tallies);
+
             // Add delegator activities
             let mut delegator_activities = DelegatedStakingActivity::from_transaction(txn).unwrap();
             all_delegator_activities.append(&mut delegator_activities);
 
+            let txn_version = txn.version as i64;
+            let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+
             // Add delegator pools
             let (delegator_pools, mut delegator_pool_balances, current_delegator_pool_balances) =
                 DelegatorPool::from_transaction(txn).unwrap();
             all_delegator_pools.extend(delegator_pools);
+            // The last pool balance of each epoch is what its rewards are computed from
+            for pool_balance in &delegator_pool_balances {
+                let epoch_reward = DelegatorPoolEpochReward::from_pool_balance(
+                    pool_balance,
+                    txn.epoch as i64,
+                    txn_timestamp,
+                );
+                all_delegator_pool_epoch_rewards.insert(
+                    (
+                        epoch_reward.staking_pool_address.clone(),
+                        epoch_reward.epoch,
+                    ),
+                    epoch_reward,
+                );
+            }
             all_delegator_pool_balances.append(&mut delegator_pool_balances);
             all_current_delegator_pool_balances.extend(current_delegator_pool_balances);
 
             // Moving the transaction code here is the new paradigm to avoid redoing a lot of the duplicate work
             // Currently only delegator voting follows this paradigm
             // TODO: refactor all the other staking code to follow this paradigm
-            let txn_version = txn.version as i64;
-            let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
             let transaction_info = txn.info.as_ref().expect("Transaction info doesn't exist!");
             // adding some metadata for subsequent parsing
             for wsc in &transaction_info.changes {
@@ -525,6 +806,8 @@ impl ProcessorTrait for StakeProcessor {
             }
         }
 
+        drop(conn);
+
         // Getting list of values and sorting by pk in order to avoid postgres deadlock since we're doing multi threaded db writes
         let mut all_current_stake_pool_voters = all_current_stake_pool_voters
             .into_values()
@@ -541,6 +824,15 @@ impl ProcessorTrait for StakeProcessor {
         let mut all_current_delegated_voter = all_current_delegated_voter
             .into_values()
             .collect::<Vec<CurrentDelegatedVoter>>();
+        let mut all_delegator_pool_epoch_rewards = all_delegator_pool_epoch_rewards
+            .into_values()
+            .collect::<Vec<DelegatorPoolEpochReward>>();
+        let mut all_governance_proposals = all_governance_proposals
+            .into_values()
+            .collect::<Vec<GovernanceProposal>>();
+        let mut all_current_governance_proposal_tallies = all_current_governance_proposal_tallies
+            .into_values()
+            .collect::<Vec<CurrentGovernanceProposalTally>>();
 
         // Sort by PK
         all_current_stake_pool_voters
@@ -556,6 +848,11 @@ impl ProcessorTrait for StakeProcessor {
         all_current_delegator_pool_balances
             .sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
         all_current_delegated_voter.sort();
+        all_delegator_pool_epoch_rewards.sort_by(|a, b| {
+            (&a.staking_pool_address, a.epoch).cmp(&(&b.staking_pool_address, b.epoch))
+        });
+        all_governance_proposals.sort_by_key(|proposal| proposal.proposal_id);
+        all_current_governance_proposal_tallies.sort_by_key(|tally| tally.proposal_id);
 
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
@@ -574,6 +871,9 @@ impl ProcessorTrait for StakeProcessor {
             &all_delegator_pool_balances,
             &all_current_delegator_pool_balances,
             &all_current_delegated_voter,
+            &all_delegator_pool_epoch_rewards,
+            &all_governance_proposals,
+            &all_current_governance_proposal_tallies,
             &self.per_table_chunk_sizes,
         )
         .await;
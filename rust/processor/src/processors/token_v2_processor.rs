@@ -8,6 +8,9 @@ use crate::{
         object_models::v2_object_utils::{
             ObjectAggregatedData, ObjectAggregatedDataMapping, ObjectWithMetadata, Untransferable,
         },
+        spam_asset_models::spam_assets::{
+            insert_spam_assets_query, SpamAsset, SpamClassifier, SpamClassifierConfig,
+        },
         token_models::{
             token_claims::CurrentTokenPendingClaim,
             tokens::{CurrentTokenPendingClaimPK, TableHandleToOwner, TableMetadataForToken},
@@ -35,6 +38,7 @@ use crate::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool, DbPoolConnection},
         util::{get_entry_function_from_user_request, parse_timestamp, standardize_address},
+        webhook_sink::{send_processed_rows, WebhookSink, WebhookSinkConfig},
     },
     worker::TableFlags,
     IndexerGrpcProcessorConfig,
@@ -59,6 +63,12 @@ pub struct TokenV2ProcessorConfig {
     pub query_retries: u32,
     #[serde(default = "IndexerGrpcProcessorConfig::default_query_retry_delay_ms")]
     pub query_retry_delay_ms: u64,
+    // Also post the token activities to an HTTP endpoint
+    #[serde(default)]
+    pub webhook: Option<WebhookSinkConfig>,
+    // Also flag spam collections and tokens in spam_assets
+    #[serde(default)]
+    pub spam_classifier: Option<SpamClassifierConfig>,
 }
 
 pub struct TokenV2Processor {
@@ -66,6 +76,8 @@ pub struct TokenV2Processor {
     config: TokenV2ProcessorConfig,
     per_table_chunk_sizes: AHashMap<String, usize>,
     deprecated_tables: TableFlags,
+    webhook_sink: Option<WebhookSink>,
+    spam_classifier: Option<SpamClassifier>,
 }
 
 impl TokenV2Processor {
@@ -77,6 +89,10 @@ impl TokenV2Processor {
     ) -> Self {
         Self {
             connection_pool,
+            webhook_sink: config.webhook.clone().map(WebhookSink::new),
+            spam_classifier: config.spam_classifier.clone().map(|spam_classifier| {
+                SpamClassifier::new(spam_classifier).expect("Invalid spam classifier config")
+            }),
             config,
             per_table_chunk_sizes,
             deprecated_tables,
@@ -117,6 +133,7 @@ async fn insert_to_db(
     current_token_v2_metadata: &[CurrentTokenV2Metadata],
     current_token_royalties_v1: &[CurrentTokenRoyaltyV1],
     current_token_claims: &[CurrentTokenPendingClaim],
+    spam_assets: &[SpamAsset],
     per_table_chunk_sizes: &AHashMap<String, usize>,
 ) -> Result<(), diesel::result::Error> {
     tracing::trace!(
@@ -128,18 +145,21 @@ async fn insert_to_db(
 
     let coll_v2 = execute_in_chunks(
         conn.clone(),
+        "collections_v2",
         insert_collections_v2_query,
         collections_v2,
         get_config_table_chunk_size::<CollectionV2>("collections_v2", per_table_chunk_sizes),
     );
     let td_v2 = execute_in_chunks(
         conn.clone(),
+        "token_datas_v2",
         insert_token_datas_v2_query,
         token_datas_v2,
         get_config_table_chunk_size::<TokenDataV2>("token_datas_v2", per_table_chunk_sizes),
     );
     let to_v2 = execute_in_chunks(
         conn.clone(),
+        "token_ownerships_v2",
         insert_token_ownerships_v2_query,
         token_ownerships_v2,
         get_config_table_chunk_size::<TokenOwnershipV2>(
@@ -149,6 +169,7 @@ async fn insert_to_db(
     );
     let cc_v2 = execute_in_chunks(
         conn.clone(),
+        "current_collections_v2",
         insert_current_collections_v2_query,
         current_collections_v2,
         get_config_table_chunk_size::<CurrentCollectionV2>(
@@ -158,6 +179,7 @@ async fn insert_to_db(
     );
     let ctd_v2 = execute_in_chunks(
         conn.clone(),
+        "current_token_datas_v2",
         insert_current_token_datas_v2_query,
         current_token_datas_v2,
         get_config_table_chunk_size::<CurrentTokenDataV2>(
@@ -167,6 +189,7 @@ async fn insert_to_db(
     );
     let cdtd_v2 = execute_in_chunks(
         conn.clone(),
+        "current_token_datas_v2",
         insert_current_deleted_token_datas_v2_query,
         current_deleted_token_datas_v2,
         get_config_table_chunk_size::<CurrentTokenDataV2>(
@@ -176,6 +199,7 @@ async fn insert_to_db(
     );
     let cto_v2 = execute_in_chunks(
         conn.clone(),
+        "current_token_ownerships_v2",
         insert_current_token_ownerships_v2_query,
         current_token_ownerships_v2,
         get_config_table_chunk_size::<CurrentTokenOwnershipV2>(
@@ -185,6 +209,7 @@ async fn insert_to_db(
     );
     let cdto_v2 = execute_in_chunks(
         conn.clone(),
+        "current_token_ownerships_v2",
         insert_current_deleted_token_ownerships_v2_query,
         current_deleted_token_ownerships_v2,
         get_config_table_chunk_size::<CurrentTokenOwnershipV2>(
@@ -194,6 +219,7 @@ async fn insert_to_db(
     );
     let ta_v2 = execute_in_chunks(
         conn.clone(),
+        "token_activities_v2",
         insert_token_activities_v2_query,
         token_activities_v2,
         get_config_table_chunk_size::<TokenActivityV2>(
@@ -203,6 +229,7 @@ async fn insert_to_db(
     );
     let ct_v2 = execute_in_chunks(
         conn.clone(),
+        "current_token_v2_metadata",
         insert_current_token_v2_metadatas_query,
         current_token_v2_metadata,
         get_config_table_chunk_size::<CurrentTokenV2Metadata>(
@@ -212,6 +239,7 @@ async fn insert_to_db(
     );
     let ctr_v1 = execute_in_chunks(
         conn.clone(),
+        "current_token_royalty_v1",
         insert_current_token_royalties_v1_query,
         current_token_royalties_v1,
         get_config_table_chunk_size::<CurrentTokenRoyaltyV1>(
@@ -220,7 +248,8 @@ async fn insert_to_db(
         ),
     );
     let ctc_v1 = execute_in_chunks(
-        conn,
+        conn.clone(),
+        "current_token_pending_claims",
         insert_current_token_claims_query,
         current_token_claims,
         get_config_table_chunk_size::<CurrentTokenPendingClaim>(
@@ -228,6 +257,13 @@ async fn insert_to_db(
             per_table_chunk_sizes,
         ),
     );
+    let sa = execute_in_chunks(
+        conn,
+        "spam_assets",
+        insert_spam_assets_query,
+        spam_assets,
+        get_config_table_chunk_size::<SpamAsset>("spam_assets", per_table_chunk_sizes),
+    );
 
     let (
         coll_v2_res,
@@ -242,9 +278,10 @@ async fn insert_to_db(
         ct_v2_res,
         ctr_v1_res,
         ctc_v1_res,
+        sa_res,
     ) = tokio::join!(
         coll_v2, td_v2, to_v2, cc_v2, ctd_v2, cdtd_v2, cto_v2, cdto_v2, ta_v2, ct_v2, ctr_v1,
-        ctc_v1
+        ctc_v1, sa
     );
 
     for res in [
@@ -260,6 +297,7 @@ async fn insert_to_db(
         ct_v2_res,
         ctr_v1_res,
         ctc_v1_res,
+        sa_res,
     ] {
         res?;
     }
@@ -624,6 +662,25 @@ impl ProcessorTrait for TokenV2Processor {
         )
         .await;
 
+        let spam_assets = match &self.spam_classifier {
+            Some(spam_classifier) => {
+                let token_data_id_to_collection_id = get_token_data_id_to_collection_id(
+                    &current_token_datas_v2,
+                    &token_activities_v2,
+                    &mut conn,
+                )
+                .await?;
+                classify_spam_assets(
+                    spam_classifier,
+                    &current_collections_v2,
+                    &current_token_datas_v2,
+                    &token_activities_v2,
+                    &token_data_id_to_collection_id,
+                )
+            },
+            None => vec![],
+        };
+
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
@@ -664,21 +721,33 @@ impl ProcessorTrait for TokenV2Processor {
             &current_token_v2_metadata,
             &current_token_royalties_v1,
             &current_token_claims,
+            &spam_assets,
             &self.per_table_chunk_sizes,
         )
         .await;
 
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
         match tx_result {
-            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
-                DefaultProcessingResult {
+            Ok(_) => {
+                send_processed_rows(
+                    self.webhook_sink.as_ref(),
+                    self.name(),
+                    "token_activities_v2",
                     start_version,
                     end_version,
-                    processing_duration_in_secs,
-                    db_insertion_duration_in_secs,
-                    last_transaction_timestamp,
-                },
-            )),
+                    &token_activities_v2,
+                )
+                .await?;
+                Ok(ProcessingResult::DefaultProcessingResult(
+                    DefaultProcessingResult {
+                        start_version,
+                        end_version,
+                        processing_duration_in_secs,
+                        db_insertion_duration_in_secs,
+                        last_transaction_timestamp,
+                    },
+                ))
+            },
             Err(e) => {
                 error!(
                     start_version = start_version,
@@ -697,6 +766,78 @@ impl ProcessorTrait for TokenV2Processor {
     }
 }
 
+/// Collection of every token with an activity in the batch, from the batch's token datas or
+/// else from current_token_datas_v2
+async fn get_token_data_id_to_collection_id(
+    current_token_datas_v2: &[CurrentTokenDataV2],
+    token_activities_v2: &[TokenActivityV2],
+    conn: &mut DbPoolConnection<'_>,
+) -> diesel::QueryResult<AHashMap<String, String>> {
+    let mut token_data_id_to_collection_id: AHashMap<String, String> = current_token_datas_v2
+        .iter()
+        .map(|token_data| {
+            (
+                token_data.token_data_id.clone(),
+                token_data.collection_id.clone(),
+            )
+        })
+        .collect();
+    let missing_token_data_ids = token_activities_v2
+        .iter()
+        .filter(|activity| !token_data_id_to_collection_id.contains_key(&activity.token_data_id))
+        .map(|activity| activity.token_data_id.clone())
+        .collect::<AHashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    token_data_id_to_collection_id
+        .extend(CurrentTokenDataV2::get_collection_ids(&missing_token_data_ids, conn).await?);
+    Ok(token_data_id_to_collection_id)
+}
+
+/// Flags collections and tokens by name, and collections whose tokens fan out to many
+/// owners in one transaction.
+fn classify_spam_assets(
+    spam_classifier: &SpamClassifier,
+    current_collections_v2: &[CurrentCollectionV2],
+    current_token_datas_v2: &[CurrentTokenDataV2],
+    token_activities_v2: &[TokenActivityV2],
+    token_data_id_to_collection_id: &AHashMap<String, String>,
+) -> Vec<SpamAsset> {
+    let mut spam_assets: Vec<SpamAsset> = current_collections_v2
+        .iter()
+        .filter_map(|collection| {
+            spam_classifier.classify_asset(
+                &collection.collection_id,
+                &collection.collection_name,
+                None,
+                collection.last_transaction_version,
+            )
+        })
+        .collect();
+    spam_assets.extend(current_token_datas_v2.iter().filter_map(|token_data| {
+        spam_classifier.classify_asset(
+            &token_data.token_data_id,
+            &token_data.token_name,
+            None,
+            token_data.last_transaction_version,
+        )
+    }));
+
+    // Airdrops are counted per collection, so tokens whose collection isn't known are skipped
+    spam_assets.extend(
+        spam_classifier.classify_airdrops(token_activities_v2.iter().filter_map(|activity| {
+            Some((
+                activity.transaction_version,
+                token_data_id_to_collection_id
+                    .get(&activity.token_data_id)?
+                    .as_str(),
+                activity.to_address.as_deref()?,
+            ))
+        })),
+    );
+    SpamAsset::dedupe(spam_assets)
+}
+
 async fn parse_v2_token(
     transactions: &[Transaction],
     table_handle_to_owner: &TableHandleToOwner,
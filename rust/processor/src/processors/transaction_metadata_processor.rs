@@ -63,6 +63,7 @@ async fn insert_to_db(
 
     execute_in_chunks(
         conn.clone(),
+        "transaction_size_info",
         insert_transaction_sizes_query,
         transaction_sizes,
         get_config_table_chunk_size::<TransactionSize>(
@@ -73,6 +74,7 @@ async fn insert_to_db(
     .await?;
     execute_in_chunks(
         conn.clone(),
+        "event_size_info",
         insert_event_sizes_query,
         event_sizes,
         get_config_table_chunk_size::<EventSize>("event_size_info", per_table_chunk_sizes),
@@ -80,6 +82,7 @@ async fn insert_to_db(
     .await?;
     execute_in_chunks(
         conn,
+        "write_set_size_info",
         insert_write_set_sizes_query,
         write_set_sizes,
         get_config_table_chunk_size::<WriteSetSize>("write_set_size_info", per_table_chunk_sizes),
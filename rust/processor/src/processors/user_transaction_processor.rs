@@ -4,13 +4,15 @@
 use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
 use crate::{
     db::common::models::user_transactions_models::{
-        signatures::Signature, user_transactions::UserTransactionModel,
+        signatures::Signature, transaction_fees::TransactionFee,
+        user_transactions::UserTransactionModel,
     },
     gap_detectors::ProcessingResult,
     schema,
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        move_abi_decoder::MoveAbiDecoder,
     },
     worker::TableFlags,
 };
@@ -19,22 +21,36 @@ use anyhow::bail;
 use aptos_protos::transaction::v1::{transaction::TxnData, Transaction};
 use async_trait::async_trait;
 use diesel::{
+    dsl::sql,
     pg::{upsert::excluded, Pg},
     query_builder::QueryFragment,
+    sql_types::{Jsonb, Nullable},
     ExpressionMethods,
 };
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use tracing::error;
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserTransactionProcessorConfig {
+    // Decode entry function arguments against their module ABIs into
+    // user_transactions.decoded_arguments
+    #[serde(default)]
+    pub decode_with_move_abis: bool,
+}
+
 pub struct UserTransactionProcessor {
     connection_pool: ArcDbPool,
     per_table_chunk_sizes: AHashMap<String, usize>,
     deprecated_tables: TableFlags,
+    abi_decoder: Option<MoveAbiDecoder>,
 }
 
 impl UserTransactionProcessor {
     pub fn new(
         connection_pool: ArcDbPool,
+        config: UserTransactionProcessorConfig,
         per_table_chunk_sizes: AHashMap<String, usize>,
         deprecated_tables: TableFlags,
     ) -> Self {
@@ -42,6 +58,7 @@ impl UserTransactionProcessor {
             connection_pool,
             per_table_chunk_sizes,
             deprecated_tables,
+            abi_decoder: config.decode_with_move_abis.then(MoveAbiDecoder::default),
         }
     }
 }
@@ -64,6 +81,7 @@ async fn insert_to_db(
     end_version: u64,
     user_transactions: &[UserTransactionModel],
     signatures: &[Signature],
+    transaction_fees: &[TransactionFee],
     per_table_chunk_sizes: &AHashMap<String, usize>,
 ) -> Result<(), diesel::result::Error> {
     tracing::trace!(
@@ -75,6 +93,7 @@ async fn insert_to_db(
 
     let ut = execute_in_chunks(
         conn.clone(),
+        "user_transactions",
         insert_user_transactions_query,
         user_transactions,
         get_config_table_chunk_size::<UserTransactionModel>(
@@ -83,14 +102,22 @@ async fn insert_to_db(
         ),
     );
     let is = execute_in_chunks(
-        conn,
+        conn.clone(),
+        "signatures",
         insert_signatures_query,
         signatures,
         get_config_table_chunk_size::<Signature>("signatures", per_table_chunk_sizes),
     );
+    let tf = execute_in_chunks(
+        conn,
+        "transaction_fees",
+        insert_transaction_fees_query,
+        transaction_fees,
+        get_config_table_chunk_size::<TransactionFee>("transaction_fees", per_table_chunk_sizes),
+    );
 
-    let (ut_res, is_res) = futures::join!(ut, is);
-    for res in [ut_res, is_res] {
+    let (ut_res, is_res, tf_res) = futures::join!(ut, is, tf);
+    for res in [ut_res, is_res, tf_res] {
         res?;
     }
     Ok(())
@@ -111,6 +138,11 @@ fn insert_user_transactions_query(
             .set((
                 expiration_timestamp_secs.eq(excluded(expiration_timestamp_secs)),
                 inserted_at.eq(excluded(inserted_at)),
+                // Keeps values decoded earlier when reprocessing without decoding, or when the
+                // ABI wasn't available this time
+                decoded_arguments.eq(sql::<Nullable<Jsonb>>(
+                    "COALESCE(EXCLUDED.decoded_arguments, user_transactions.decoded_arguments)",
+                )),
             )),
         None,
     )
@@ -137,6 +169,22 @@ fn insert_signatures_query(
     )
 }
 
+fn insert_transaction_fees_query(
+    items_to_insert: Vec<TransactionFee>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::transaction_fees::dsl::*;
+    (
+        diesel::insert_into(schema::transaction_fees::table)
+            .values(items_to_insert)
+            .on_conflict(transaction_version)
+            .do_nothing(),
+        None,
+    )
+}
+
 #[async_trait]
 impl ProcessorTrait for UserTransactionProcessor {
     fn name(&self) -> &'static str {
@@ -153,8 +201,15 @@ impl ProcessorTrait for UserTransactionProcessor {
         let processing_start = std::time::Instant::now();
         let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
 
+        if let Some(abi_decoder) = &self.abi_decoder {
+            abi_decoder
+                .prepare(&mut self.get_conn().await, &transactions)
+                .await?;
+        }
+
         let mut signatures = vec![];
         let mut user_transactions = vec![];
+        let mut transaction_fees = vec![];
         for txn in &transactions {
             let txn_version = txn.version as i64;
             let block_height = txn.block_height as i64;
@@ -172,21 +227,38 @@ impl ProcessorTrait for UserTransactionProcessor {
                 },
             };
             if let TxnData::User(inner) = txn_data {
-                let (user_transaction, sigs) = UserTransactionModel::from_transaction(
+                let (mut user_transaction, sigs) = UserTransactionModel::from_transaction(
                     inner,
                     txn.timestamp.as_ref().unwrap(),
                     block_height,
                     txn.epoch as i64,
                     txn_version,
                 );
+                if let (Some(abi_decoder), Some(request)) = (&self.abi_decoder, &inner.request) {
+                    user_transaction.decoded_arguments =
+                        abi_decoder.decode_entry_function_arguments(request);
+                }
                 signatures.extend(sigs);
                 user_transactions.push(user_transaction);
+                transaction_fees.extend(TransactionFee::from_transaction(
+                    inner,
+                    txn.info.as_ref().expect("Transaction info doesn't exist!"),
+                    txn.timestamp.as_ref().unwrap(),
+                    block_height,
+                    txn_version,
+                ));
             }
         }
 
         if self.deprecated_tables.contains(TableFlags::SIGNATURES) {
             signatures.clear();
         }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::TRANSACTION_FEES)
+        {
+            transaction_fees.clear();
+        }
 
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
@@ -198,6 +270,7 @@ impl ProcessorTrait for UserTransactionProcessor {
             end_version,
             &user_transactions,
             &signatures,
+            &transaction_fees,
             &self.per_table_chunk_sizes,
         )
         .await;
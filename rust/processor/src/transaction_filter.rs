@@ -1,9 +1,11 @@
+use crate::utils::util::{get_entry_function_from_user_request, standardize_address};
 use aptos_protos::transaction::v1::{
     transaction::{TransactionType, TxnData},
     transaction_payload::Payload,
-    Transaction,
+    write_set_change::Change,
+    Event, Transaction,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// Allows filtering transactions based on various criteria
 /// The criteria are combined with `AND`
@@ -14,11 +16,54 @@ use serde::{Deserialize, Serialize};
 #[serde(default)]
 pub struct TransactionFilter {
     // Only allow transactions from these contract addresses
+    #[serde(deserialize_with = "deserialize_address_set")]
     focus_contract_addresses: Option<ahash::HashSet<String>>,
     // Skip transactions from these sender addresses
+    #[serde(deserialize_with = "deserialize_address_set")]
     skip_sender_addresses: Option<ahash::HashSet<String>>,
     // Skip all transactions that aren't user transactions
     focus_user_transactions: bool,
+    // Composable filter tree, applied to every transaction type
+    expression: Option<FilterExpression>,
+}
+
+/// A composable predicate over a transaction. In the config file each node is keyed by its
+/// snake_case name, e.g.
+///
+/// ```yaml
+/// expression:
+///   or:
+///     - entry_function: "0x1::aptos_account::transfer"
+///     - and:
+///         - event_type: "0x1::coin::DepositEvent"
+///         - not:
+///             success: false
+/// ```
+///
+/// Addresses in the patterns are standardized once when the expression is deserialized.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum FilterExpression {
+    /// Matches if every child matches
+    And(Vec<FilterExpression>),
+    /// Matches if at least one child matches
+    Or(Vec<FilterExpression>),
+    /// Matches if the child doesn't
+    Not(Box<FilterExpression>),
+    /// User transactions sent by this address
+    Sender(#[serde(deserialize_with = "deserialize_address")] String),
+    /// User transactions calling an entry function, as `address::module::function` or
+    /// `module::function` to match the function at any address
+    EntryFunction(#[serde(deserialize_with = "deserialize_entry_function")] String),
+    /// Transactions emitting an event of this type. Without generic parameters, events of
+    /// any instantiation of the type match. Addresses, including those in generic parameters,
+    /// can be short or long
+    EventType(#[serde(deserialize_with = "deserialize_type_str")] String),
+    /// Transactions writing or deleting a resource of this type. Generics are handled the
+    /// same way as for `event_type`
+    ResourceType(#[serde(deserialize_with = "deserialize_type_str")] String),
+    /// Transactions that were (or weren't) executed successfully
+    Success(bool),
 }
 
 impl TransactionFilter {
@@ -26,17 +71,24 @@ impl TransactionFilter {
         focus_contract_addresses: Option<ahash::HashSet<String>>,
         skip_sender_addresses: Option<ahash::HashSet<String>>,
         focus_user_transactions: bool,
+        expression: Option<FilterExpression>,
     ) -> Self {
-        // TODO: normalize addresses
         Self {
-            focus_contract_addresses,
-            skip_sender_addresses,
+            focus_contract_addresses: focus_contract_addresses.map(standardize_address_set),
+            skip_sender_addresses: skip_sender_addresses.map(standardize_address_set),
             focus_user_transactions,
+            expression,
         }
     }
 
     /// Returns true if the transaction should be included
     pub fn include(&self, transaction: &Transaction) -> bool {
+        if let Some(expression) = &self.expression {
+            if !expression.matches(transaction) {
+                return false;
+            }
+        }
+
         // If we're only focusing on user transactions, skip if it's not a user transaction
 
         let is_user_txn = transaction.r#type == TransactionType::User as i32;
@@ -53,7 +105,7 @@ impl TransactionFilter {
             if let Some(utr) = user_transaction.request.as_ref() {
                 // Skip if sender is in the skip list
                 if let Some(skip_sender_addresses) = &self.skip_sender_addresses {
-                    if skip_sender_addresses.contains(&utr.sender) {
+                    if skip_sender_addresses.contains(&standardize_address(&utr.sender)) {
                         return false;
                     }
                 }
@@ -64,7 +116,9 @@ impl TransactionFilter {
                         if let Some(Payload::EntryFunctionPayload(efp)) = payload.payload.as_ref() {
                             if let Some(function) = efp.function.as_ref() {
                                 if let Some(module) = function.module.as_ref() {
-                                    if !focus_contract_addresses.contains(&module.address) {
+                                    if !focus_contract_addresses
+                                        .contains(&standardize_address(&module.address))
+                                    {
                                         return false;
                                     }
                                 }
@@ -78,3 +132,327 @@ impl TransactionFilter {
         true
     }
 }
+
+impl FilterExpression {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        match self {
+            FilterExpression::And(children) => children.iter().all(|c| c.matches(transaction)),
+            FilterExpression::Or(children) => children.iter().any(|c| c.matches(transaction)),
+            FilterExpression::Not(child) => !child.matches(transaction),
+            FilterExpression::Sender(sender) => get_user_request(transaction)
+                .map(|utr| standardize_address(&utr.sender) == *sender)
+                .unwrap_or(false),
+            FilterExpression::EntryFunction(function) => get_user_request(transaction)
+                .and_then(get_entry_function_from_user_request)
+                .map(|entry_function| entry_function_matches(&entry_function, function))
+                .unwrap_or(false),
+            FilterExpression::EventType(event_type) => get_events(transaction)
+                .iter()
+                .any(|event| type_matches(&event.type_str, event_type)),
+            FilterExpression::ResourceType(resource_type) => transaction
+                .info
+                .as_ref()
+                .map(|info| {
+                    info.changes.iter().any(|wsc| match wsc.change.as_ref() {
+                        Some(Change::WriteResource(resource)) => {
+                            type_matches(&resource.type_str, resource_type)
+                        },
+                        Some(Change::DeleteResource(resource)) => {
+                            type_matches(&resource.type_str, resource_type)
+                        },
+                        _ => false,
+                    })
+                })
+                .unwrap_or(false),
+            FilterExpression::Success(success) => transaction
+                .info
+                .as_ref()
+                .map(|info| info.success == *success)
+                .unwrap_or(false),
+        }
+    }
+}
+
+fn get_user_request(
+    transaction: &Transaction,
+) -> Option<&aptos_protos::transaction::v1::UserTransactionRequest> {
+    match transaction.txn_data.as_ref() {
+        Some(TxnData::User(user_transaction)) => user_transaction.request.as_ref(),
+        _ => None,
+    }
+}
+
+fn get_events(transaction: &Transaction) -> &[Event] {
+    match transaction.txn_data.as_ref() {
+        Some(TxnData::BlockMetadata(tx_inner)) => &tx_inner.events,
+        Some(TxnData::Genesis(tx_inner)) => &tx_inner.events,
+        Some(TxnData::User(tx_inner)) => &tx_inner.events,
+        Some(TxnData::Validator(tx_inner)) => &tx_inner.events,
+        _ => &[],
+    }
+}
+
+/// Standardizes every address in a `address::module::name<...>` type string, including the ones
+/// in its generic type arguments, and drops whitespace so `<A, B>` and `<A,B>` compare equal
+fn standardize_type_str(type_str: &str) -> String {
+    let mut standardized = String::with_capacity(type_str.len());
+    let mut rest = type_str;
+    while let Some(index) = rest.find(['<', '>', ',']) {
+        push_standardized_type_name(&mut standardized, &rest[..index]);
+        standardized.push_str(&rest[index..index + 1]);
+        rest = &rest[index + 1..];
+    }
+    push_standardized_type_name(&mut standardized, rest);
+    standardized
+}
+
+fn push_standardized_type_name(standardized: &mut String, type_name: &str) {
+    let type_name = type_name.trim();
+    match type_name.split_once("::") {
+        Some((address, rest)) => {
+            standardized.push_str(&standardize_address(address));
+            standardized.push_str("::");
+            standardized.push_str(rest);
+        },
+        None => standardized.push_str(type_name),
+    }
+}
+
+/// Standardizes a fully qualified `address::module::function` entry function, leaving
+/// `module::function` patterns as they are
+fn standardize_entry_function(entry_function: &str) -> String {
+    if entry_function.split("::").count() == 3 {
+        standardize_type_str(entry_function)
+    } else {
+        entry_function.to_string()
+    }
+}
+
+/// `pattern` has to be standardized already
+fn type_matches(type_str: &str, pattern: &str) -> bool {
+    if pattern.contains('<') {
+        standardize_type_str(type_str) == pattern
+    } else {
+        // Only the type itself has to be standardized to compare it
+        let type_name = type_str.split('<').next().unwrap_or_default();
+        standardize_type_str(type_name) == pattern
+    }
+}
+
+/// `pattern` has to be standardized already
+fn entry_function_matches(entry_function: &str, pattern: &str) -> bool {
+    let parts: Vec<&str> = pattern.split("::").collect();
+    match parts.as_slice() {
+        [_, _, _] => standardize_type_str(entry_function) == pattern,
+        [module, function] => entry_function
+            .split_once("::")
+            .map(|(_, rest)| rest == format!("{}::{}", module, function))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn standardize_address_set(addresses: ahash::HashSet<String>) -> ahash::HashSet<String> {
+    addresses
+        .iter()
+        .map(|address| standardize_address(address))
+        .collect()
+}
+
+fn deserialize_address_set<'de, D>(
+    deserializer: D,
+) -> Result<Option<ahash::HashSet<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let addresses = Option::<ahash::HashSet<String>>::deserialize(deserializer)?;
+    Ok(addresses.map(standardize_address_set))
+}
+
+fn deserialize_address<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let address = String::deserialize(deserializer)?;
+    Ok(standardize_address(&address))
+}
+
+fn deserialize_entry_function<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entry_function = String::deserialize(deserializer)?;
+    Ok(standardize_entry_function(&entry_function))
+}
+
+fn deserialize_type_str<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let type_str = String::deserialize(deserializer)?;
+    Ok(standardize_type_str(&type_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{
+        EntryFunctionId, EntryFunctionPayload, MoveModuleId, TransactionInfo, TransactionPayload,
+        UserTransaction, UserTransactionRequest, WriteResource, WriteSetChange,
+    };
+    use serde_json::json;
+
+    fn user_transaction(sender: &str, function: &str, event_type: &str) -> Transaction {
+        let mut parts = function.split("::");
+        let (address, module, name) = (
+            parts.next().unwrap(),
+            parts.next().unwrap(),
+            parts.next().unwrap(),
+        );
+        Transaction {
+            r#type: TransactionType::User as i32,
+            info: Some(TransactionInfo {
+                success: true,
+                ..TransactionInfo::default()
+            }),
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    sender: sender.to_string(),
+                    payload: Some(TransactionPayload {
+                        payload: Some(Payload::EntryFunctionPayload(EntryFunctionPayload {
+                            function: Some(EntryFunctionId {
+                                module: Some(MoveModuleId {
+                                    address: address.to_string(),
+                                    name: module.to_string(),
+                                }),
+                                name: name.to_string(),
+                            }),
+                            entry_function_id_str: function.to_string(),
+                            ..EntryFunctionPayload::default()
+                        })),
+                        ..TransactionPayload::default()
+                    }),
+                    ..UserTransactionRequest::default()
+                }),
+                events: vec![Event {
+                    type_str: event_type.to_string(),
+                    ..Event::default()
+                }],
+                ..UserTransaction::default()
+            })),
+            ..Transaction::default()
+        }
+    }
+
+    fn parse_expression(json: serde_json::Value) -> FilterExpression {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_expression_predicates() {
+        let txn = user_transaction(
+            "0x7",
+            "0x1::aptos_account::transfer",
+            "0x1::coin::CoinDeposit<0x1::aptos_coin::AptosCoin>",
+        );
+
+        assert!(parse_expression(json!({"sender": "0x0007"})).matches(&txn));
+        assert!(
+            parse_expression(json!({"entry_function": "aptos_account::transfer"})).matches(&txn)
+        );
+        assert!(parse_expression(json!({
+            "entry_function": "0x0000000000000000000000000000000000000000000000000000000000000001::aptos_account::transfer"
+        }))
+        .matches(&txn));
+        assert!(!parse_expression(json!({"entry_function": "coin::transfer"})).matches(&txn));
+        assert!(parse_expression(json!({"event_type": "0x1::coin::CoinDeposit"})).matches(&txn));
+        assert!(!parse_expression(json!({
+            "event_type": "0x1::coin::CoinDeposit<0x1::other::Coin>"
+        }))
+        .matches(&txn));
+        assert!(!parse_expression(json!({"resource_type": "0x1::coin::CoinStore"})).matches(&txn));
+        assert!(parse_expression(json!({"success": true})).matches(&txn));
+    }
+
+    #[test]
+    fn test_expression_patterns_are_standardized_once() {
+        assert_eq!(
+            parse_expression(json!({"event_type": "0x1::coin::CoinStore<0xa::usdc::USDC>"})),
+            FilterExpression::EventType(format!(
+                "{}::coin::CoinStore<{}::usdc::USDC>",
+                standardize_address("0x1"),
+                standardize_address("0xa")
+            ))
+        );
+        assert_eq!(
+            parse_expression(json!({"entry_function": "aptos_account::transfer"})),
+            FilterExpression::EntryFunction("aptos_account::transfer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_expression_combinators() {
+        let txn = user_transaction("0x7", "0x1::aptos_account::transfer", "0x1::coin::Deposit");
+        let expression: FilterExpression = serde_json::from_str(
+            r#"{"or": [
+                {"sender": "0x8"},
+                {"and": [
+                    {"event_type": "0x1::coin::Deposit"},
+                    {"not": {"success": false}}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(expression.matches(&txn));
+
+        let filter = TransactionFilter::new(
+            None,
+            None,
+            false,
+            Some(FilterExpression::Not(Box::new(expression))),
+        );
+        assert!(!filter.include(&txn));
+    }
+
+    #[test]
+    fn test_generic_type_addresses_are_standardized() {
+        let mut txn = user_transaction(
+            "0x7",
+            "0x1::aptos_account::transfer",
+            "0x1::coin::CoinDeposit<0xa::pool::LP<0x1::aptos_coin::AptosCoin, 0xb::usdc::USDC>>",
+        );
+        txn.info.as_mut().unwrap().changes.push(WriteSetChange {
+            change: Some(Change::WriteResource(WriteResource {
+                type_str: "0x1::coin::CoinStore<0xa::pool::LP<u64,vector<0xb::usdc::USDC>>>"
+                    .to_string(),
+                ..WriteResource::default()
+            })),
+            ..WriteSetChange::default()
+        });
+
+        let expression: FilterExpression = serde_json::from_str(
+            r#"{"and": [
+                {"event_type": "0x01::coin::CoinDeposit<0x000a::pool::LP<0x1::aptos_coin::AptosCoin,0x0b::usdc::USDC>>"},
+                {"resource_type": "0x1::coin::CoinStore<0x00a::pool::LP<u64, vector<0x0000b::usdc::USDC>>>"}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(expression.matches(&txn));
+        assert!(!parse_expression(json!({
+            "event_type": "0x1::coin::CoinDeposit<0xa::pool::LP<0x1::aptos_coin::AptosCoin, 0xc::usdc::USDC>>"
+        }))
+        .matches(&txn));
+    }
+
+    #[test]
+    fn test_skip_sender_addresses_are_standardized() {
+        let txn = user_transaction(
+            "0x0007",
+            "0x1::aptos_account::transfer",
+            "0x1::coin::Deposit",
+        );
+        let filter: TransactionFilter =
+            serde_json::from_str(r#"{"skip_sender_addresses": ["0x7"]}"#).unwrap();
+        assert!(!filter.include(&txn));
+    }
+}
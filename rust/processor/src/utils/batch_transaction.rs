@@ -0,0 +1,145 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! With transactional batch writes, every write of a batch goes through one connection and is
+//! committed in a single transaction, so readers never see some tables updated and others not.
+//! Chunking is preserved, the chunks just run one after the other on the batch's connection.
+
+use super::{
+    database::{ArcDbPool, DbPoolConnection, MyDbConnection},
+    write_context,
+};
+use crate::schema::processor_status;
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::{AnsiTransactionManager, RunQueryDsl, TransactionManager};
+use std::{
+    future::Future,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+pub type BatchConnection = Arc<Mutex<DbPoolConnection<'static>>>;
+
+/// A connection for reads that have to see the rows the batch already wrote. With transactional
+/// batch writes those are only visible on the batch's connection, which stays locked until this is
+/// dropped, so it has to be dropped before the batch writes anything else.
+pub enum ReadConnection {
+    Batch(OwnedMutexGuard<DbPoolConnection<'static>>),
+    Pooled(DbPoolConnection<'static>),
+}
+
+impl Deref for ReadConnection {
+    type Target = DbPoolConnection<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ReadConnection::Batch(conn) => conn,
+            ReadConnection::Pooled(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for ReadConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            ReadConnection::Batch(conn) => conn,
+            ReadConnection::Pooled(conn) => conn,
+        }
+    }
+}
+
+/// The batch's connection if it's written in a single transaction, otherwise one from the pool
+pub async fn read_connection(pool: &ArcDbPool) -> diesel::QueryResult<ReadConnection> {
+    if let Some(conn) = write_context::connection() {
+        return Ok(ReadConnection::Batch(conn.lock_owned().await));
+    }
+    pool.get_owned()
+        .await
+        .map(ReadConnection::Pooled)
+        .map_err(|e| {
+            tracing::warn!("Error getting connection from pool: {:?}", e);
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UnableToSendCommand,
+                Box::new(e.to_string()),
+            )
+        })
+}
+
+/// Runs a batch's processing in a transaction, committed only if it succeeds. processor_status
+/// is moved to the end of the batch in the same transaction when the batch directly follows it.
+/// Batches processed out of order leave it to the gap detector, which keeps it contiguous.
+/// `f` gets the batch's connection to write with, or None when `enabled` is false and the batch
+/// is processed as is.
+pub async fn run<T, F, Fut>(
+    enabled: bool,
+    pool: ArcDbPool,
+    processor_name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    f: F,
+) -> anyhow::Result<T>
+where
+    F: FnOnce(Option<BatchConnection>) -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    if !enabled {
+        return f(None).await;
+    }
+    let mut conn = pool
+        .get_owned()
+        .await
+        .context("Failed to get connection for the batch transaction")?;
+    AnsiTransactionManager::begin_transaction(&mut *conn).await?;
+    let conn = Arc::new(Mutex::new(conn));
+    let result = f(Some(conn.clone())).await;
+
+    let mut conn = conn.lock().await;
+    let result = match result {
+        Ok(result) => advance_processor_status(
+            &mut conn,
+            processor_name,
+            start_version,
+            end_version,
+            last_transaction_timestamp,
+        )
+        .await
+        .map(|_| result)
+        .context("Failed to update processor status in the batch transaction"),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(result) => {
+            AnsiTransactionManager::commit_transaction(&mut **conn).await?;
+            Ok(result)
+        },
+        Err(e) => {
+            AnsiTransactionManager::rollback_transaction(&mut **conn).await?;
+            Err(e)
+        },
+    }
+}
+
+async fn advance_processor_status(
+    conn: &mut MyDbConnection,
+    processor_name: &str,
+    start_version: u64,
+    end_version: u64,
+    last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+) -> diesel::QueryResult<usize> {
+    diesel::update(
+        processor_status::table
+            .filter(processor_status::processor.eq(processor_name))
+            .filter(processor_status::last_success_version.ge(start_version as i64 - 1))
+            .filter(processor_status::last_success_version.lt(end_version as i64)),
+    )
+    .set((
+        processor_status::last_success_version.eq(end_version as i64),
+        processor_status::last_updated.eq(diesel::dsl::now),
+        processor_status::last_transaction_timestamp.eq(last_transaction_timestamp),
+    ))
+    .execute(conn)
+    .await
+}
@@ -157,6 +157,17 @@ pub static PB_CHANNEL_FETCH_WAIT_TIME_SECS: Lazy<GaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// How long copying the last batch into a processor's channel waited for the processor, when
+/// several processors share a transaction stream
+pub static FAN_OUT_SEND_WAIT_TIME_SECS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "indexer_processor_fan_out_send_wait_time_secs",
+        "Time spent waiting for room in a processor's channel",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
 /// Count of transactions processed.
 pub static NUM_TRANSACTIONS_PROCESSED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -245,6 +256,26 @@ pub static PARQUET_PROCESSOR_DATA_GAP_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of gap repair attempts, by result
+pub static PROCESSOR_GAP_REPAIR_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_processor_gap_repair_count",
+        "Number of times the gap detector tried to repair a gap",
+        &["processor_name", "result"]
+    )
+    .unwrap()
+});
+
+/// Number of webhook sink requests, by result
+pub static WEBHOOK_SINK_REQUEST_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_processor_webhook_sink_request_count",
+        "Number of requests the webhook sink made",
+        &["processor_name", "result"]
+    )
+    .unwrap()
+});
+
 /// GRPC latency.
 pub static GRPC_LATENCY_BY_PROCESSOR_IN_SECS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
@@ -1,7 +1,15 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils::util::remove_null_bytes;
+use crate::{
+    db::common::models::processor_table_status::ProcessorTableStatus,
+    utils::{
+        dry_run,
+        table_checkpoints::{self, Batch},
+        util::remove_null_bytes,
+        write_context::{self, TableWrite},
+    },
+};
 use ahash::AHashMap;
 use diesel::{
     query_builder::{AstPass, Query, QueryFragment},
@@ -12,7 +20,8 @@ use diesel_async::{
         bb8::{Pool, PooledConnection},
         AsyncDieselConnectionManager, ManagerConfig, PoolError,
     },
-    AsyncPgConnection, RunQueryDsl,
+    scoped_futures::ScopedFutureExt,
+    AsyncConnection, AsyncPgConnection, RunQueryDsl,
 };
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use futures_util::{future::BoxFuture, FutureExt};
@@ -123,8 +132,12 @@ pub async fn new_db_pool(
     Ok(Arc::new(pool))
 }
 
+/// Writes the rows to the table in chunks, as the current write context allows: rows of tables
+/// a backfill doesn't cover are dropped, dry runs capture them instead, and batches written in a
+/// single transaction or checkpointed per table write them on one connection.
 pub async fn execute_in_chunks<U, T>(
     conn: ArcDbPool,
+    table_name: &'static str,
     build_query: fn(Vec<T>) -> (U, Option<&'static str>),
     items_to_insert: &[T],
     chunk_size: usize,
@@ -133,6 +146,38 @@ where
     U: QueryFragment<Backend> + diesel::query_builder::QueryId + Send + 'static,
     T: serde::Serialize + for<'de> serde::Deserialize<'de> + Clone + Send + 'static,
 {
+    match write_context::table_write(table_name) {
+        TableWrite::Write => {},
+        TableWrite::Capture => {
+            dry_run::capture_items(table_name, items_to_insert);
+            return Ok(());
+        },
+        TableWrite::Skip => return Ok(()),
+    }
+    // Tables are checkpointed even when a batch has no rows for them
+    if let Some((batch, checkpoint_name)) = write_context::next_checkpoint(table_name) {
+        return execute_with_table_checkpoint(
+            conn,
+            build_query,
+            items_to_insert,
+            chunk_size,
+            checkpoint_name,
+            batch,
+        )
+        .await;
+    }
+    if let Some(batch_conn) = write_context::connection() {
+        let items = clean_data_for_db(items_to_insert.to_vec(), true);
+        return execute_chunks_in_transaction(
+            &mut batch_conn.lock().await,
+            build_query,
+            items,
+            chunk_size,
+            None,
+        )
+        .await;
+    }
+
     let tasks = items_to_insert
         .chunks(chunk_size)
         .map(|chunk| {
@@ -156,6 +201,10 @@ where
     Ok(())
 }
 
+/// Runs a single query, on the batch's connection if the batch is written in a single
+/// transaction. Table rows should be written with [`execute_in_chunks`], or after checking
+/// [`write_context::table_write`], so that backfills and dry runs apply to them. Only dry runs
+/// apply here, which also skip the processor's bookkeeping, e.g. processor_status.
 pub async fn execute_with_better_error<U>(
     pool: ArcDbPool,
     query: U,
@@ -164,6 +213,22 @@ pub async fn execute_with_better_error<U>(
 where
     U: QueryFragment<Backend> + diesel::query_builder::QueryId + Send,
 {
+    if write_context::is_dry_run() {
+        tracing::debug!(
+            "Dry run, skipping query: {:?}",
+            diesel::debug_query::<Backend, _>(&query).to_string()
+        );
+        return Ok(0);
+    }
+    // Writes of a batch committed as a whole go through the batch's connection
+    if let Some(batch_conn) = write_context::connection() {
+        return execute_with_better_error_conn(
+            &mut batch_conn.lock().await,
+            query,
+            additional_where_clause,
+        )
+        .await;
+    }
     let original_query = diesel::debug_query::<Backend, _>(&query).to_string();
     // This is needed because if we don't insert any row, then diesel makes a call like this
     // SELECT 1 FROM TABLE WHERE 1=0
@@ -176,13 +241,7 @@ where
     };
     let debug_string = diesel::debug_query::<Backend, _>(&final_query).to_string();
     tracing::debug!("Executing query: {:?}", debug_string);
-    let conn = &mut pool.get().await.map_err(|e| {
-        tracing::warn!("Error getting connection from pool: {:?}", e);
-        diesel::result::Error::DatabaseError(
-            diesel::result::DatabaseErrorKind::UnableToSendCommand,
-            Box::new(e.to_string()),
-        )
-    })?;
+    let conn = &mut get_connection(&pool).await?;
     let res = final_query.execute(conn).await;
     if let Err(ref e) = res {
         tracing::warn!("Error running query: {:?}\n{:?}", e, debug_string);
@@ -212,6 +271,13 @@ pub async fn execute_with_better_error_conn<U>(
 where
     U: QueryFragment<Backend> + diesel::query_builder::QueryId + Send,
 {
+    if write_context::is_dry_run() {
+        tracing::debug!(
+            "Dry run, skipping query: {:?}",
+            diesel::debug_query::<Backend, _>(&query).to_string()
+        );
+        return Ok(0);
+    }
     let original_query = diesel::debug_query::<Backend, _>(&query).to_string();
     // This is needed because if we don't insert any row, then diesel makes a call like this
     // SELECT 1 FROM TABLE WHERE 1=0
@@ -231,6 +297,96 @@ where
     res
 }
 
+/// Writes all chunks of a table in one transaction together with the checkpoint of this write,
+/// unless the table already got it from the batch before a restart.
+async fn execute_with_table_checkpoint<U, T>(
+    pool: ArcDbPool,
+    build_query: fn(Vec<T>) -> (U, Option<&'static str>),
+    items_to_insert: &[T],
+    chunk_size: usize,
+    checkpoint_name: String,
+    batch: Batch,
+) -> Result<(), diesel::result::Error>
+where
+    U: QueryFragment<Backend> + diesel::query_builder::QueryId + Send + 'static,
+    T: serde::Serialize + for<'de> serde::Deserialize<'de> + Clone + Send + 'static,
+{
+    if table_checkpoints::is_written(&batch, &checkpoint_name) {
+        table_checkpoints::mark_written(&batch, &checkpoint_name);
+        return Ok(());
+    }
+    // A failed statement aborts the transaction, so null bytes are removed up front rather than
+    // on retry
+    let items = clean_data_for_db(items_to_insert.to_vec(), true);
+    let checkpoint = ProcessorTableStatus {
+        processor: batch.processor_name.to_string(),
+        table_name: checkpoint_name.clone(),
+        last_success_version: table_checkpoints::version_after_write(&batch, &checkpoint_name),
+    };
+    match write_context::connection() {
+        Some(batch_conn) => {
+            execute_chunks_in_transaction(
+                &mut batch_conn.lock().await,
+                build_query,
+                items,
+                chunk_size,
+                Some(checkpoint),
+            )
+            .await?
+        },
+        None => {
+            execute_chunks_in_transaction(
+                &mut get_connection(&pool).await?,
+                build_query,
+                items,
+                chunk_size,
+                Some(checkpoint),
+            )
+            .await?
+        },
+    }
+    table_checkpoints::mark_written(&batch, &checkpoint_name);
+    Ok(())
+}
+
+/// Runs the chunks one after the other in a transaction, or a savepoint if one is already open
+async fn execute_chunks_in_transaction<U, T>(
+    conn: &mut MyDbConnection,
+    build_query: fn(Vec<T>) -> (U, Option<&'static str>),
+    items: Vec<T>,
+    chunk_size: usize,
+    checkpoint: Option<ProcessorTableStatus>,
+) -> Result<(), diesel::result::Error>
+where
+    U: QueryFragment<Backend> + diesel::query_builder::QueryId + Send + 'static,
+    T: serde::Serialize + for<'de> serde::Deserialize<'de> + Clone + Send + 'static,
+{
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        async move {
+            for chunk in items.chunks(chunk_size.max(1)) {
+                let (query, additional_where_clause) = build_query(chunk.to_vec());
+                execute_with_better_error_conn(conn, query, additional_where_clause).await?;
+            }
+            if let Some(checkpoint) = checkpoint {
+                table_checkpoints::write_checkpoint(conn, &checkpoint).await?;
+            }
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+async fn get_connection(pool: &ArcDbPool) -> QueryResult<DbPoolConnection<'_>> {
+    pool.get().await.map_err(|e| {
+        tracing::warn!("Error getting connection from pool: {:?}", e);
+        diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UnableToSendCommand,
+            Box::new(e.to_string()),
+        )
+    })
+}
+
 async fn execute_or_retry_cleaned<U, T>(
     conn: ArcDbPool,
     build_query: fn(Vec<T>) -> (U, Option<&'static str>),
@@ -0,0 +1,295 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! In dry run mode processors run as usual, but every row they would write is captured here
+//! (per processor and table) instead of being sent to the database. Once the version range is
+//! processed the captured rows are either written out as JSON or diffed against the DB.
+//!
+//! Reads aren't captured: processors that build on rows written by earlier batches see the DB
+//! as it was before the dry run, see the README for the affected tables.
+
+use super::{
+    database::{ArcDbPool, DbPoolConnection},
+    write_context,
+};
+use ahash::AHashMap;
+use anyhow::Context;
+use diesel::{
+    sql_types::{Array, Jsonb, Nullable, Text},
+    QueryableByName,
+};
+use diesel_async::RunQueryDsl;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+const DIFF_CHUNK_SIZE: usize = 1000;
+// Used when rows are written outside a processor task, which shouldn't happen in practice
+const UNKNOWN_PROCESSOR: &str = "unknown";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DryRunConfig {
+    #[serde(default)]
+    pub output: DryRunOutput,
+    // File to write the JSON report to. Defaults to stdout.
+    #[serde(default)]
+    pub output_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DryRunOutput {
+    /// Every captured row, keyed by processor and table.
+    #[default]
+    Rows,
+    /// Captured rows compared by primary key against the current DB contents.
+    Diff,
+}
+
+/// processor name -> table name -> rows, in the order they were written
+type CapturedRows = BTreeMap<String, BTreeMap<String, Vec<Value>>>;
+
+static CAPTURED_ROWS: OnceCell<Mutex<CapturedRows>> = OnceCell::new();
+
+pub fn capture_items<T: Serialize>(table_name: &str, items: &[T]) {
+    let rows = items
+        .iter()
+        .map(|item| {
+            serde_json::to_value(item)
+                .map(normalize_column_names)
+                .expect("Failed to serialize row for dry run")
+        })
+        .collect();
+    capture_rows(table_name, rows);
+}
+
+/// Captures rows written to the table by the processor of the current write context
+pub fn capture_rows(table_name: &str, rows: Vec<Value>) {
+    if rows.is_empty() {
+        return;
+    }
+    let processor_name = write_context::processor_name().unwrap_or(UNKNOWN_PROCESSOR);
+    CAPTURED_ROWS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(processor_name.to_string())
+        .or_default()
+        .entry(table_name.to_string())
+        .or_default()
+        .extend(rows);
+}
+
+/// Diesel maps the `type` column to a `type_` field, so the serialized field doesn't match
+fn normalize_column_names(mut row: Value) -> Value {
+    if let Value::Object(map) = &mut row {
+        if let Some(value) = map.remove("type_") {
+            map.insert("type".to_string(), value);
+        }
+    }
+    row
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TableDiff {
+    pub inserted: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    // Primary keys of the rows that don't exist yet
+    pub inserted_rows: Vec<Map<String, Value>>,
+    pub changed_rows: Vec<ChangedRow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedRow {
+    pub primary_key: Map<String, Value>,
+    pub columns: BTreeMap<String, ColumnChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnChange {
+    pub current: Value,
+    pub captured: Value,
+}
+
+#[derive(QueryableByName)]
+struct PrimaryKeyColumn {
+    #[diesel(sql_type = Text)]
+    column_name: String,
+}
+
+#[derive(QueryableByName)]
+struct RowDiff {
+    #[diesel(sql_type = Jsonb)]
+    captured: Value,
+    #[diesel(sql_type = Nullable<Jsonb>)]
+    current: Option<Value>,
+    #[diesel(sql_type = Array<Text>)]
+    changed_columns: Vec<String>,
+}
+
+/// Writes the captured rows, or their diff against the DB, as JSON. Called once all processor
+/// tasks are done.
+pub async fn write_report(pool: ArcDbPool, config: &DryRunConfig) -> anyhow::Result<()> {
+    let captured =
+        std::mem::take(&mut *CAPTURED_ROWS.get_or_init(Default::default).lock().unwrap());
+    let report = match config.output {
+        DryRunOutput::Rows => serde_json::to_value(&captured)?,
+        DryRunOutput::Diff => {
+            let mut conn = pool.get().await?;
+            let mut report = BTreeMap::new();
+            for (processor_name, tables) in captured {
+                let mut table_diffs = BTreeMap::new();
+                for (table_name, rows) in tables {
+                    let table_diff = diff_table(&mut conn, &table_name, rows)
+                        .await
+                        .with_context(|| format!("Failed to diff table {}", table_name))?;
+                    table_diffs.insert(table_name, table_diff);
+                }
+                report.insert(processor_name, table_diffs);
+            }
+            serde_json::to_value(&report)?
+        },
+    };
+    let output = serde_json::to_string_pretty(&report)?;
+    match &config.output_path {
+        Some(path) => std::fs::write(path, output)
+            .with_context(|| format!("Failed to write dry run report to {:?}", path))?,
+        None => println!("{}", output),
+    }
+    Ok(())
+}
+
+async fn diff_table(
+    conn: &mut DbPoolConnection<'_>,
+    table_name: &str,
+    rows: Vec<Value>,
+) -> anyhow::Result<TableDiff> {
+    let primary_key = diesel::sql_query(
+        "SELECT a.attname::text AS column_name \
+         FROM pg_index i JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+         WHERE i.indrelid = $1::regclass AND i.indisprimary \
+         ORDER BY array_position(i.indkey::int2[], a.attnum)",
+    )
+    .bind::<Text, _>(quote_identifier(table_name))
+    .load::<PrimaryKeyColumn>(conn)
+    .await?
+    .into_iter()
+    .map(|column| column.column_name)
+    .collect::<Vec<_>>();
+    anyhow::ensure!(!primary_key.is_empty(), "Table has no primary key");
+
+    // Upserts in later batches overwrite earlier ones, so only the last row per key counts
+    let mut index_by_key = AHashMap::new();
+    let mut deduped_rows: Vec<Value> = vec![];
+    for row in rows {
+        let key = primary_key
+            .iter()
+            .map(|column| row.get(column).map(Value::to_string).unwrap_or_default())
+            .collect::<Vec<_>>();
+        match index_by_key.get(&key) {
+            Some(&index) => deduped_rows[index] = row,
+            None => {
+                index_by_key.insert(key, deduped_rows.len());
+                deduped_rows.push(row);
+            },
+        }
+    }
+    // Only compare the columns the processor writes, not e.g. inserted_at
+    let mut written_columns = deduped_rows
+        .iter()
+        .filter_map(|row| row.as_object())
+        .flat_map(|row| row.keys().cloned())
+        .collect::<Vec<_>>();
+    written_columns.sort();
+    written_columns.dedup();
+
+    // Both sides go through the table's column types so that e.g. numerics compare by value
+    let table = quote_identifier(table_name);
+    let join_condition = primary_key
+        .iter()
+        .map(|column| {
+            let column = quote_identifier(column);
+            format!("t.{column} = c.{column}")
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let diff_query = format!(
+        "SELECT to_jsonb(c) AS captured, \
+         CASE WHEN t.{first_key} IS NULL THEN NULL ELSE to_jsonb(t) END AS current, \
+         ARRAY(SELECT e.key FROM jsonb_each(to_jsonb(c)) e \
+               WHERE e.key = ANY($2) AND e.value IS DISTINCT FROM to_jsonb(t) -> e.key) AS changed_columns \
+         FROM jsonb_populate_recordset(NULL::{table}, $1) c \
+         LEFT JOIN {table} t ON {join_condition}",
+        first_key = quote_identifier(&primary_key[0]),
+    );
+
+    let mut table_diff = TableDiff::default();
+    for chunk in deduped_rows.chunks(DIFF_CHUNK_SIZE) {
+        let row_diffs = diesel::sql_query(diff_query.as_str())
+            .bind::<Jsonb, _>(Value::Array(chunk.to_vec()))
+            .bind::<Array<Text>, _>(written_columns.clone())
+            .load::<RowDiff>(conn)
+            .await?;
+        for row_diff in row_diffs {
+            let primary_key_values = primary_key
+                .iter()
+                .map(|column| {
+                    let value = row_diff.captured.get(column).cloned().unwrap_or_default();
+                    (column.clone(), value)
+                })
+                .collect::<Map<_, _>>();
+            match row_diff.current {
+                None => {
+                    table_diff.inserted += 1;
+                    table_diff.inserted_rows.push(primary_key_values);
+                },
+                Some(_) if row_diff.changed_columns.is_empty() => table_diff.unchanged += 1,
+                Some(current) => {
+                    table_diff.changed += 1;
+                    let columns = row_diff
+                        .changed_columns
+                        .into_iter()
+                        .map(|column| {
+                            let change = ColumnChange {
+                                current: current.get(&column).cloned().unwrap_or_default(),
+                                captured: row_diff
+                                    .captured
+                                    .get(&column)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                            };
+                            (column, change)
+                        })
+                        .collect();
+                    table_diff.changed_rows.push(ChangedRow {
+                        primary_key: primary_key_values,
+                        columns,
+                    });
+                },
+            }
+        }
+    }
+    Ok(table_diff)
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_column_names() {
+        let row = normalize_column_names(json!({"type_": "0x1::coin::CoinDeposit", "amount": "1"}));
+        assert_eq!(
+            row,
+            json!({"type": "0x1::coin::CoinDeposit", "amount": "1"})
+        );
+    }
+}
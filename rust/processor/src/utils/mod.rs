@@ -1,6 +1,12 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod batch_transaction;
 pub mod counters;
 pub mod database;
+pub mod dry_run;
+pub mod move_abi_decoder;
+pub mod table_checkpoints;
 pub mod util;
+pub mod webhook_sink;
+pub mod write_context;
@@ -0,0 +1,627 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::utils::{database::DbPoolConnection, util::standardize_address};
+use ahash::{AHashMap, AHashSet};
+use aptos_protos::transaction::v1::{
+    move_type::Content, multisig_transaction_payload::Payload as MultisigPayloadType,
+    transaction::TxnData, transaction_payload::Payload as PayloadType, write_set_change::Change,
+    EntryFunctionPayload, Event as EventPB, MoveFunction, MoveModule as MoveModulePB, MoveStruct,
+    MoveStructTag, MoveType, MoveTypes, Transaction, UserTransactionRequest,
+};
+use diesel::{
+    sql_query,
+    sql_types::{Array, Jsonb, Nullable, Text},
+    QueryableByName,
+};
+use diesel_async::RunQueryDsl;
+use lru::LruCache;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+// Number of module ABIs kept in memory, and of modules remembered as missing
+const MODULE_CACHE_SIZE: usize = 10_000;
+// How long a module missing from move_modules isn't looked up again
+const MISSING_MODULE_TTL: Duration = Duration::from_secs(600);
+const QUERY_CHUNK_SIZE: usize = 1000;
+
+// (standardized address, module name)
+type ModuleId = (String, String);
+type ModuleCache = LruCache<ModuleId, Arc<ModuleAbi>>;
+// When each module was last found missing
+type MissingModuleCache = LruCache<ModuleId, Instant>;
+
+#[derive(QueryableByName)]
+struct ModuleAbiRow {
+    #[diesel(sql_type = Text)]
+    address: String,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Nullable<Jsonb>)]
+    exposed_functions: Option<Value>,
+    #[diesel(sql_type = Nullable<Jsonb>)]
+    structs: Option<Value>,
+}
+
+/// Exposed functions and structs of a published module.
+#[derive(Debug, Default)]
+pub struct ModuleAbi {
+    functions: AHashMap<String, MoveFunction>,
+    structs: AHashMap<String, MoveStruct>,
+}
+
+impl ModuleAbi {
+    fn new(functions: Vec<MoveFunction>, structs: Vec<MoveStruct>) -> Self {
+        Self {
+            functions: functions
+                .into_iter()
+                .map(|function| (function.name.clone(), function))
+                .collect(),
+            structs: structs
+                .into_iter()
+                .map(|move_struct| (move_struct.name.clone(), move_struct))
+                .collect(),
+        }
+    }
+
+    fn from_move_module(move_module: &MoveModulePB) -> Self {
+        Self::new(
+            move_module.exposed_functions.clone(),
+            move_module.structs.clone(),
+        )
+    }
+
+    /// Latest ABIs of the modules in `move_modules`, as stored by
+    /// `MoveModule::convert_move_module`. Modules that aren't there are left out.
+    async fn get_from_db(
+        conn: &mut DbPoolConnection<'_>,
+        module_ids: &[ModuleId],
+    ) -> diesel::QueryResult<AHashMap<ModuleId, Self>> {
+        let mut abis = AHashMap::new();
+        for module_ids in module_ids.chunks(QUERY_CHUNK_SIZE) {
+            let (addresses, names): (Vec<&str>, Vec<&str>) = module_ids
+                .iter()
+                .map(|(address, name)| (address.as_str(), name.as_str()))
+                .unzip();
+            let rows: Vec<ModuleAbiRow> = sql_query(
+                "SELECT DISTINCT ON (address, name) address, name, exposed_functions, structs \
+                 FROM move_modules \
+                 WHERE (address, name) IN (SELECT * FROM UNNEST($1::varchar[], $2::text[])) \
+                 AND NOT is_deleted \
+                 ORDER BY address, name, transaction_version DESC",
+            )
+            .bind::<Array<Text>, _>(addresses)
+            .bind::<Array<Text>, _>(names)
+            .load(conn)
+            .await?;
+            abis.extend(rows.into_iter().map(|row| {
+                (
+                    (row.address, row.name),
+                    Self::new(from_json(row.exposed_functions), from_json(row.structs)),
+                )
+            }));
+        }
+        Ok(abis)
+    }
+}
+
+/// Decodes entry function arguments and event payloads into `[{name, type, value}]` JSON using
+/// module ABIs. ABIs are taken from modules published in the transactions being processed and
+/// otherwise loaded from `move_modules`, so modules published before the starting version are
+/// only decoded once the default processor has indexed them.
+///
+/// Compiled modules don't keep parameter names, so entry function arguments are named by
+/// position (`arg0`, `arg1`, ...). Event fields keep their struct field names.
+pub struct MoveAbiDecoder {
+    modules: RwLock<ModuleCache>,
+    // A module missing from move_modules may be indexed later, or never be if it's from another
+    // network, so it's only looked up again once missing_module_ttl has passed
+    missing_modules: RwLock<MissingModuleCache>,
+    missing_module_ttl: Duration,
+}
+
+impl Default for MoveAbiDecoder {
+    fn default() -> Self {
+        Self::new(MISSING_MODULE_TTL)
+    }
+}
+
+impl MoveAbiDecoder {
+    fn new(missing_module_ttl: Duration) -> Self {
+        Self {
+            modules: RwLock::new(new_module_cache()),
+            missing_modules: RwLock::new(LruCache::new(
+                NonZeroUsize::new(MODULE_CACHE_SIZE).unwrap(),
+            )),
+            missing_module_ttl,
+        }
+    }
+
+    /// Caches the modules published in `transactions`, then loads the ABIs of the modules their
+    /// entry functions and events refer to. Must be called before decoding those transactions.
+    pub async fn prepare(
+        &self,
+        conn: &mut DbPoolConnection<'_>,
+        transactions: &[Transaction],
+    ) -> diesel::QueryResult<()> {
+        let modules_to_load = self.cache_published_modules(transactions);
+        if modules_to_load.is_empty() {
+            return Ok(());
+        }
+        let abis = ModuleAbi::get_from_db(conn, &modules_to_load).await?;
+        self.cache_loaded_modules(modules_to_load, abis);
+        Ok(())
+    }
+
+    /// Caches the modules published in `transactions` and returns the modules they refer to that
+    /// have to be loaded from the DB, i.e. aren't cached and weren't recently found missing.
+    fn cache_published_modules(&self, transactions: &[Transaction]) -> Vec<ModuleId> {
+        let mut modules = self.modules.write().unwrap();
+        let mut missing_modules = self.missing_modules.write().unwrap();
+        let mut modules_to_load = AHashSet::new();
+        for txn in transactions {
+            let changes = txn.info.iter().flat_map(|info| &info.changes);
+            for change in changes {
+                if let Some(Change::WriteModule(write_module)) = &change.change {
+                    if let Some(abi) = write_module.data.as_ref().and_then(|d| d.abi.as_ref()) {
+                        // Compatible upgrades only add to the ABI, so the latest one can decode
+                        // calls made against earlier versions too
+                        let module_id = module_id(&abi.address, &abi.name);
+                        missing_modules.pop(&module_id);
+                        modules.put(module_id, Arc::new(ModuleAbi::from_move_module(abi)));
+                    }
+                }
+            }
+            // Marks the modules as recently used so they aren't evicted before decoding
+            modules_to_load.extend(get_referenced_modules(txn).filter(|id| {
+                modules.get(id).is_none()
+                    && missing_modules.peek(id).map_or(true, |looked_up_at| {
+                        looked_up_at.elapsed() >= self.missing_module_ttl
+                    })
+            }));
+        }
+        modules_to_load.into_iter().collect()
+    }
+
+    /// Caches the ABIs loaded for `modules_to_load`, and remembers the ones that weren't found
+    fn cache_loaded_modules(
+        &self,
+        modules_to_load: Vec<ModuleId>,
+        mut abis: AHashMap<ModuleId, ModuleAbi>,
+    ) {
+        let mut modules = self.modules.write().unwrap();
+        let mut missing_modules = self.missing_modules.write().unwrap();
+        let now = Instant::now();
+        for module_id in modules_to_load {
+            match abis.remove(&module_id) {
+                Some(abi) => {
+                    missing_modules.pop(&module_id);
+                    modules.put(module_id, Arc::new(abi));
+                },
+                None => {
+                    missing_modules.put(module_id, now);
+                },
+            }
+        }
+    }
+
+    /// None if the transaction isn't an entry function call or its ABI is unknown.
+    pub fn decode_entry_function_arguments(
+        &self,
+        user_request: &UserTransactionRequest,
+    ) -> Option<Value> {
+        let payload = get_entry_function_payload(user_request)?;
+        let function_id = payload.function.as_ref()?;
+        let module = function_id.module.as_ref()?;
+        let modules = self.modules.read().unwrap();
+        let function = get_module(&modules, &module.address, &module.name)?
+            .functions
+            .get(&function_id.name)?;
+        // Signers are taken from the transaction rather than passed as arguments
+        let params = function
+            .params
+            .iter()
+            .filter(|param| !is_signer(param))
+            .collect::<Vec<_>>();
+        if params.len() != payload.arguments.len() {
+            return None;
+        }
+        let arguments = params
+            .into_iter()
+            .zip(&payload.arguments)
+            .enumerate()
+            .map(|(index, (param, argument))| {
+                let param = substitute(param, &payload.type_arguments);
+                let value = serde_json::from_str(argument)
+                    .unwrap_or_else(|_| Value::String(argument.clone()));
+                json!({
+                    "name": format!("arg{}", index),
+                    "type": type_to_string(&param),
+                    "value": decode_value(&modules, &param, value),
+                })
+            })
+            .collect();
+        Some(Value::Array(arguments))
+    }
+
+    /// None if the event's struct ABI is unknown.
+    pub fn decode_event(&self, event: &EventPB) -> Option<Value> {
+        let tag = match event.r#type.as_ref()?.content.as_ref()? {
+            Content::Struct(tag) => tag,
+            _ => return None,
+        };
+        let modules = self.modules.read().unwrap();
+        let move_struct = get_module(&modules, &tag.address, &tag.module)?
+            .structs
+            .get(&tag.name)?;
+        let mut data: Map<String, Value> = serde_json::from_str(&event.data).ok()?;
+        let fields = move_struct
+            .fields
+            .iter()
+            .map(|field| {
+                let field_type = field
+                    .r#type
+                    .as_ref()
+                    .map(|t| substitute(t, &tag.generic_type_params))
+                    .unwrap_or_default();
+                let value = data.remove(&field.name).unwrap_or(Value::Null);
+                json!({
+                    "name": field.name,
+                    "type": type_to_string(&field_type),
+                    "value": decode_value(&modules, &field_type, value),
+                })
+            })
+            .collect();
+        Some(Value::Array(fields))
+    }
+}
+
+fn module_id(address: &str, name: &str) -> ModuleId {
+    (standardize_address(address), name.to_string())
+}
+
+fn new_module_cache() -> ModuleCache {
+    LruCache::new(NonZeroUsize::new(MODULE_CACHE_SIZE).unwrap())
+}
+
+fn get_module<'a>(modules: &'a ModuleCache, address: &str, name: &str) -> Option<&'a ModuleAbi> {
+    modules
+        .peek(&module_id(address, name))
+        .map(|abi| abi.as_ref())
+}
+
+fn from_json<T: DeserializeOwned>(value: Option<Value>) -> Vec<T> {
+    value
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn get_entry_function_payload(
+    user_request: &UserTransactionRequest,
+) -> Option<&EntryFunctionPayload> {
+    match user_request.payload.as_ref()?.payload.as_ref()? {
+        PayloadType::EntryFunctionPayload(payload) => Some(payload),
+        PayloadType::MultisigPayload(payload) => {
+            match payload.transaction_payload.as_ref()?.payload.as_ref()? {
+                MultisigPayloadType::EntryFunctionPayload(payload) => Some(payload),
+            }
+        },
+        _ => None,
+    }
+}
+
+fn get_events(txn: &Transaction) -> &[EventPB] {
+    match txn.txn_data.as_ref() {
+        Some(TxnData::BlockMetadata(inner)) => &inner.events,
+        Some(TxnData::Genesis(inner)) => &inner.events,
+        Some(TxnData::User(inner)) => &inner.events,
+        Some(TxnData::Validator(inner)) => &inner.events,
+        _ => &[],
+    }
+}
+
+/// Modules of the transaction's entry function and of its events' structs.
+fn get_referenced_modules(txn: &Transaction) -> impl Iterator<Item = ModuleId> + '_ {
+    let entry_function_module = match txn.txn_data.as_ref() {
+        Some(TxnData::User(inner)) => inner
+            .request
+            .as_ref()
+            .and_then(get_entry_function_payload)
+            .and_then(|payload| payload.function.as_ref())
+            .and_then(|function| function.module.as_ref())
+            .map(|module| module_id(&module.address, &module.name)),
+        _ => None,
+    };
+    let event_modules = get_events(txn).iter().filter_map(|event| {
+        match event.r#type.as_ref()?.content.as_ref()? {
+            Content::Struct(tag) => Some(module_id(&tag.address, &tag.module)),
+            _ => None,
+        }
+    });
+    entry_function_module.into_iter().chain(event_modules)
+}
+
+fn is_signer(move_type: &MoveType) -> bool {
+    match &move_type.content {
+        Some(Content::Reference(reference)) => reference
+            .to
+            .as_ref()
+            .is_some_and(|to| to.r#type() == MoveTypes::Signer),
+        _ => move_type.r#type() == MoveTypes::Signer,
+    }
+}
+
+/// Replaces generic type parameters with the concrete type arguments.
+fn substitute(move_type: &MoveType, type_args: &[MoveType]) -> MoveType {
+    match &move_type.content {
+        Some(Content::GenericTypeParamIndex(index)) => type_args
+            .get(*index as usize)
+            .cloned()
+            .unwrap_or_else(|| move_type.clone()),
+        Some(Content::Vector(inner)) => MoveType {
+            r#type: move_type.r#type,
+            content: Some(Content::Vector(Box::new(substitute(inner, type_args)))),
+        },
+        Some(Content::Struct(tag)) => MoveType {
+            r#type: move_type.r#type,
+            content: Some(Content::Struct(MoveStructTag {
+                generic_type_params: tag
+                    .generic_type_params
+                    .iter()
+                    .map(|param| substitute(param, type_args))
+                    .collect(),
+                ..tag.clone()
+            })),
+        },
+        _ => move_type.clone(),
+    }
+}
+
+fn type_to_string(move_type: &MoveType) -> String {
+    match &move_type.content {
+        Some(Content::Vector(inner)) => format!("vector<{}>", type_to_string(inner)),
+        Some(Content::Struct(tag)) => {
+            let name = format!("{}::{}::{}", tag.address, tag.module, tag.name);
+            if tag.generic_type_params.is_empty() {
+                name
+            } else {
+                let params = tag
+                    .generic_type_params
+                    .iter()
+                    .map(type_to_string)
+                    .collect::<Vec<_>>();
+                format!("{}<{}>", name, params.join(", "))
+            }
+        },
+        Some(Content::GenericTypeParamIndex(index)) => format!("T{}", index),
+        Some(Content::Reference(reference)) => format!(
+            "&{}{}",
+            if reference.mutable { "mut " } else { "" },
+            reference
+                .to
+                .as_deref()
+                .map(type_to_string)
+                .unwrap_or_default()
+        ),
+        Some(Content::Unparsable(unparsable)) => unparsable.clone(),
+        None => match move_type.r#type() {
+            MoveTypes::Bool => "bool",
+            MoveTypes::U8 => "u8",
+            MoveTypes::U16 => "u16",
+            MoveTypes::U32 => "u32",
+            MoveTypes::U64 => "u64",
+            MoveTypes::U128 => "u128",
+            MoveTypes::U256 => "u256",
+            MoveTypes::Address => "address",
+            MoveTypes::Signer => "signer",
+            _ => "unknown",
+        }
+        .to_string(),
+    }
+}
+
+/// Converts a JSON value to its Move type: small integers become numbers, large ones strings,
+/// addresses are standardized and strings, options and objects are unwrapped. Values that don't
+/// match their type are left as is.
+fn decode_value(modules: &ModuleCache, move_type: &MoveType, value: Value) -> Value {
+    match &move_type.content {
+        Some(Content::Vector(inner)) => match value {
+            Value::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| decode_value(modules, inner, value))
+                    .collect(),
+            ),
+            // vector<u8> is hex encoded
+            value => value,
+        },
+        Some(Content::Struct(tag)) => decode_struct(modules, tag, value),
+        Some(_) => value,
+        None => match (move_type.r#type(), value) {
+            (MoveTypes::U8 | MoveTypes::U16 | MoveTypes::U32, Value::String(s)) => s
+                .parse::<u32>()
+                .map(Value::from)
+                .unwrap_or(Value::String(s)),
+            (MoveTypes::U64 | MoveTypes::U128 | MoveTypes::U256, Value::Number(n)) => {
+                Value::String(n.to_string())
+            },
+            (MoveTypes::Address, Value::String(s)) => Value::String(standardize_address(&s)),
+            (_, value) => value,
+        },
+    }
+}
+
+fn decode_struct(modules: &ModuleCache, tag: &MoveStructTag, value: Value) -> Value {
+    let is_framework = standardize_address(&tag.address) == standardize_address("0x1");
+    match (
+        is_framework,
+        tag.module.as_str(),
+        tag.name.as_str(),
+        tag.generic_type_params.first(),
+        value,
+    ) {
+        (true, "option", "Option", Some(inner), Value::Object(mut option)) => {
+            match option.remove("vec") {
+                Some(Value::Array(mut vec)) if vec.len() == 1 => {
+                    decode_value(modules, inner, vec.remove(0))
+                },
+                Some(Value::Array(vec)) if vec.is_empty() => Value::Null,
+                Some(vec) => json!({ "vec": vec }),
+                None => Value::Object(option),
+            }
+        },
+        (true, "object", "Object", _, Value::Object(mut object)) => match object.remove("inner") {
+            Some(Value::String(inner)) => Value::String(standardize_address(&inner)),
+            Some(inner) => json!({ "inner": inner }),
+            None => Value::Object(object),
+        },
+        (_, _, _, _, Value::Object(mut fields)) => {
+            let move_struct = match get_module(modules, &tag.address, &tag.module)
+                .and_then(|abi| abi.structs.get(&tag.name))
+            {
+                Some(move_struct) => move_struct,
+                None => return Value::Object(fields),
+            };
+            let mut decoded = Map::new();
+            for field in &move_struct.fields {
+                if let (Some(field_type), Some(value)) =
+                    (field.r#type.as_ref(), fields.remove(&field.name))
+                {
+                    let field_type = substitute(field_type, &tag.generic_type_params);
+                    decoded.insert(
+                        field.name.clone(),
+                        decode_value(modules, &field_type, value),
+                    );
+                }
+            }
+            Value::Object(decoded)
+        },
+        // e.g. 0x1::string::String, which is already a string
+        (_, _, _, _, value) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{
+        EntryFunctionId, MoveModuleId, TransactionPayload, UserTransaction,
+    };
+
+    fn primitive(move_type: MoveTypes) -> MoveType {
+        MoveType {
+            r#type: move_type as i32,
+            content: None,
+        }
+    }
+
+    fn framework_struct(module: &str, name: &str, generic_type_params: Vec<MoveType>) -> MoveType {
+        MoveType {
+            r#type: MoveTypes::Struct as i32,
+            content: Some(Content::Struct(MoveStructTag {
+                address: "0x1".to_string(),
+                module: module.to_string(),
+                name: name.to_string(),
+                generic_type_params,
+            })),
+        }
+    }
+
+    fn entry_function_call(address: &str, module: &str) -> Transaction {
+        let payload = EntryFunctionPayload {
+            function: Some(EntryFunctionId {
+                module: Some(MoveModuleId {
+                    address: address.to_string(),
+                    name: module.to_string(),
+                }),
+                name: "run".to_string(),
+            }),
+            ..EntryFunctionPayload::default()
+        };
+        Transaction {
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    payload: Some(TransactionPayload {
+                        payload: Some(PayloadType::EntryFunctionPayload(payload)),
+                        ..TransactionPayload::default()
+                    }),
+                    ..UserTransactionRequest::default()
+                }),
+                ..UserTransaction::default()
+            })),
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_modules_are_not_looked_up_until_expired() {
+        let transactions = [entry_function_call("0xa", "market")];
+        let market = module_id("0xa", "market");
+
+        let decoder = MoveAbiDecoder::default();
+        let modules_to_load = decoder.cache_published_modules(&transactions);
+        assert_eq!(modules_to_load, vec![market.clone()]);
+        decoder.cache_loaded_modules(modules_to_load, AHashMap::new());
+        assert!(decoder.cache_published_modules(&transactions).is_empty());
+
+        let decoder = MoveAbiDecoder::new(Duration::ZERO);
+        let modules_to_load = decoder.cache_published_modules(&transactions);
+        decoder.cache_loaded_modules(modules_to_load, AHashMap::new());
+        assert_eq!(decoder.cache_published_modules(&transactions), vec![
+            market.clone()
+        ]);
+
+        // Found modules stay cached
+        decoder.cache_loaded_modules(
+            vec![market.clone()],
+            AHashMap::from_iter([(market, ModuleAbi::default())]),
+        );
+        assert!(decoder.cache_published_modules(&transactions).is_empty());
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let modules = new_module_cache();
+        let option_t0 = framework_struct("option", "Option", vec![MoveType {
+            r#type: MoveTypes::GenericTypeParam as i32,
+            content: Some(Content::GenericTypeParamIndex(0)),
+        }]);
+        let option_address = substitute(&option_t0, &[primitive(MoveTypes::Address)]);
+        assert_eq!(type_to_string(&option_t0), "0x1::option::Option<T0>");
+        assert_eq!(
+            type_to_string(&option_address),
+            "0x1::option::Option<address>"
+        );
+
+        assert_eq!(
+            decode_value(&modules, &option_address, json!({ "vec": ["0x1"] })),
+            json!(standardize_address("0x1"))
+        );
+        assert_eq!(
+            decode_value(&modules, &option_address, json!({ "vec": [] })),
+            Value::Null
+        );
+        assert_eq!(
+            decode_value(&modules, &primitive(MoveTypes::U8), json!("7")),
+            json!(7)
+        );
+        assert_eq!(
+            decode_value(&modules, &primitive(MoveTypes::U64), json!(7)),
+            json!("7")
+        );
+        let object = framework_struct("object", "Object", vec![framework_struct(
+            "fungible_asset",
+            "Metadata",
+            vec![],
+        )]);
+        assert_eq!(
+            decode_value(&modules, &object, json!({ "inner": "0xa" })),
+            json!(standardize_address("0xa"))
+        );
+    }
+}
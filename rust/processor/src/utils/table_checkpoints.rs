@@ -0,0 +1,299 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per table checkpoints for Postgres processors. Every table write of a batch commits in the
+//! same transaction as the table's row in processor_table_status, so after a crash each table
+//! resumes from its own version: batches a table has already written are skipped for that table.
+//!
+//! Batches can commit out of order when they're processed concurrently, so a table's checkpoint
+//! only moves past a batch once every batch before it has been written to the table too.
+//!
+//! A batch can write the same table more than once, e.g. current and deleted rows. Each of those
+//! writes has its own checkpoint, so a crash between them doesn't leave the table marked as
+//! written without the rows of the later ones.
+
+use super::database::{execute_with_better_error_conn, MyDbConnection};
+use crate::{
+    db::common::models::processor_table_status::{ProcessorTableStatus, ProcessorTableStatusQuery},
+    schema::processor_table_status,
+};
+use ahash::AHashMap;
+use diesel::{pg::upsert::excluded, ExpressionMethods, QueryResult};
+use once_cell::sync::OnceCell;
+use std::{collections::BTreeMap, sync::Mutex};
+
+static PROGRESS: OnceCell<Mutex<AHashMap<&'static str, ProcessorProgress>>> = OnceCell::new();
+
+/// The batch a processor task is currently writing
+#[derive(Clone, Copy, Debug)]
+pub struct Batch {
+    pub processor_name: &'static str,
+    pub start_version: u64,
+    pub end_version: u64,
+}
+
+#[derive(Debug)]
+struct ProcessorProgress {
+    // Every batch up to this version has been fully processed, i.e. written to all tables
+    version: i64,
+    // Batches fully processed past `version`, start -> end
+    processed_batches: BTreeMap<u64, u64>,
+    // Checkpoints in processor_table_status when the processor started
+    checkpoints_on_start: AHashMap<String, i64>,
+    tables: AHashMap<String, TableProgress>,
+}
+
+#[derive(Debug, Default)]
+struct TableProgress {
+    version: i64,
+    // Batches written to the table past `version`, start -> end
+    written_batches: BTreeMap<u64, u64>,
+}
+
+/// Turns on checkpointing for a processor starting at `starting_version`, given its checkpoints
+/// in processor_table_status.
+pub fn enable(
+    processor_name: &'static str,
+    starting_version: u64,
+    checkpoints: Vec<ProcessorTableStatusQuery>,
+) {
+    let progress = ProcessorProgress {
+        version: starting_version as i64 - 1,
+        processed_batches: BTreeMap::new(),
+        checkpoints_on_start: checkpoints
+            .into_iter()
+            .map(|checkpoint| (checkpoint.table_name, checkpoint.last_success_version))
+            .collect(),
+        tables: AHashMap::new(),
+    };
+    PROGRESS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .insert(processor_name, progress);
+}
+
+/// Whether the processor has checkpointing enabled
+pub fn is_enabled(processor_name: &str) -> bool {
+    with_progress(processor_name, |_| ()).is_some()
+}
+
+/// Name the checkpoint of the `write_number`th write to the table in a batch is stored under: the
+/// table name for the first write, then `<table>#2`, `<table>#3`, ... Writes are numbered in the
+/// order they start, which is the same every time a batch is processed.
+pub fn checkpoint_name(table_name: &str, write_number: usize) -> String {
+    match write_number {
+        1 => table_name.to_string(),
+        n => format!("{}#{}", table_name, n),
+    }
+}
+
+/// Whether the table's rows for the batch were already written before the processor restarted
+pub fn is_written(batch: &Batch, table_name: &str) -> bool {
+    with_progress(batch.processor_name, |progress| {
+        progress
+            .checkpoints_on_start
+            .get(table_name)
+            .is_some_and(|version| batch.end_version as i64 <= *version)
+    })
+    .unwrap_or_default()
+}
+
+/// The table's checkpoint once the batch has been written to it
+pub fn version_after_write(batch: &Batch, table_name: &str) -> i64 {
+    with_progress(batch.processor_name, |progress| {
+        let table_version = progress.table_version(table_name);
+        let written_batches = progress
+            .tables
+            .get(table_name)
+            .map(|table| &table.written_batches);
+        let mut version = table_version;
+        loop {
+            let next_version = (version + 1) as u64;
+            let end_version = written_batches
+                .and_then(|batches| covering_end_version(batches, next_version))
+                .or_else(|| covering_end_version(&progress.processed_batches, next_version))
+                .or_else(|| {
+                    (batch.start_version <= next_version && next_version <= batch.end_version)
+                        .then_some(batch.end_version)
+                });
+            match end_version {
+                Some(end_version) => version = end_version as i64,
+                None => return version,
+            }
+        }
+    })
+    .unwrap_or(batch.end_version as i64)
+}
+
+/// Records that the batch's rows have been committed to the table
+pub fn mark_written(batch: &Batch, table_name: &str) {
+    with_progress(batch.processor_name, |progress| {
+        let version = progress.table_version(table_name);
+        let table = progress.tables.entry(table_name.to_string()).or_default();
+        table.version = version;
+        table
+            .written_batches
+            .insert(batch.start_version, batch.end_version);
+    });
+}
+
+/// Records that the processor has finished the batch, whether or not it wrote anything
+pub fn mark_processed(processor_name: &'static str, start_version: u64, end_version: u64) {
+    with_progress(processor_name, |progress| {
+        progress
+            .processed_batches
+            .insert(start_version, end_version);
+        while let Some(end_version) =
+            covering_end_version(&progress.processed_batches, (progress.version + 1) as u64)
+        {
+            progress.version = end_version as i64;
+        }
+        // Everything up to the processor's version is covered, no need to keep it around
+        let first_pending_version = (progress.version + 1) as u64;
+        progress.processed_batches = progress.processed_batches.split_off(&first_pending_version);
+        for table in progress.tables.values_mut() {
+            table.written_batches = table.written_batches.split_off(&first_pending_version);
+        }
+    });
+}
+
+/// Upserts the table's checkpoint, meant to run in the transaction writing the table's rows
+pub async fn write_checkpoint(
+    conn: &mut MyDbConnection,
+    checkpoint: &ProcessorTableStatus,
+) -> QueryResult<usize> {
+    execute_with_better_error_conn(
+        conn,
+        diesel::insert_into(processor_table_status::table)
+            .values(checkpoint)
+            .on_conflict((
+                processor_table_status::processor,
+                processor_table_status::table_name,
+            ))
+            .do_update()
+            .set((
+                processor_table_status::last_success_version
+                    .eq(excluded(processor_table_status::last_success_version)),
+                processor_table_status::last_updated
+                    .eq(excluded(processor_table_status::last_updated)),
+            )),
+        Some(
+            " WHERE processor_table_status.last_success_version <= EXCLUDED.last_success_version ",
+        ),
+    )
+    .await
+}
+
+impl ProcessorProgress {
+    /// A table is at least as far as the processor, and tables written before the restart are
+    /// at least at their checkpoint
+    fn table_version(&self, table_name: &str) -> i64 {
+        let checkpoint_on_start = self
+            .checkpoints_on_start
+            .get(table_name)
+            .copied()
+            .unwrap_or(-1);
+        let table_version = self
+            .tables
+            .get(table_name)
+            .map(|table| table.version)
+            .unwrap_or(-1);
+        self.version.max(checkpoint_on_start).max(table_version)
+    }
+}
+
+fn with_progress<R>(
+    processor_name: &str,
+    f: impl FnOnce(&mut ProcessorProgress) -> R,
+) -> Option<R> {
+    let mut progress = PROGRESS.get()?.lock().unwrap();
+    progress.get_mut(processor_name).map(f)
+}
+
+/// The end of the batch containing `version`, if any
+fn covering_end_version(batches: &BTreeMap<u64, u64>, version: u64) -> Option<u64> {
+    batches
+        .range(..=version)
+        .next_back()
+        .map(|(_, end_version)| *end_version)
+        .filter(|end_version| *end_version >= version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::write_context::{self, WriteContext, WriteMode};
+
+    async fn in_batch<F: std::future::Future>(batch: Batch, f: F) -> F::Output {
+        let context = WriteContext::new(batch.processor_name, WriteMode::default());
+        write_context::scope(context.with_batch(batch, None), f).await
+    }
+
+    fn batch(start_version: u64, end_version: u64) -> Batch {
+        Batch {
+            processor_name: "test_processor",
+            start_version,
+            end_version,
+        }
+    }
+
+    #[test]
+    fn test_checkpoints_wait_for_earlier_batches() {
+        enable("test_processor", 100, vec![]);
+
+        // The second batch commits first, so the table can't move past the first one yet
+        let second = batch(200, 299);
+        assert_eq!(version_after_write(&second, "events"), 99);
+        mark_written(&second, "events");
+
+        let first = batch(100, 199);
+        assert_eq!(version_after_write(&first, "events"), 299);
+        mark_written(&first, "events");
+
+        // A batch without rows for another table still counts once it's processed
+        mark_processed("test_processor", 100, 199);
+        mark_processed("test_processor", 200, 299);
+        assert_eq!(version_after_write(&batch(300, 399), "transactions"), 399);
+        assert!(!is_written(&batch(300, 399), "events"));
+    }
+
+    #[tokio::test]
+    async fn test_each_write_to_a_table_has_its_own_checkpoint() {
+        let processor_name = "test_processor_with_two_writes";
+        let batch = Batch {
+            processor_name,
+            start_version: 100,
+            end_version: 199,
+        };
+        enable(processor_name, 100, vec![]);
+
+        // The batch crashes after the first of its two writes to the table has committed
+        let written = in_batch(batch, async {
+            let (_, name) = write_context::next_checkpoint("current_token_ownerships_v2").unwrap();
+            assert_eq!(name, "current_token_ownerships_v2");
+            let version = version_after_write(&batch, &name);
+            mark_written(&batch, &name);
+            (name, version)
+        })
+        .await;
+        assert_eq!(written.1, 199);
+
+        // After the restart only the first write is skipped
+        enable(processor_name, 100, vec![ProcessorTableStatusQuery {
+            processor: processor_name.to_string(),
+            table_name: written.0,
+            last_success_version: written.1,
+            last_updated: chrono::NaiveDateTime::default(),
+        }]);
+        in_batch(batch, async {
+            let (_, first) = write_context::next_checkpoint("current_token_ownerships_v2").unwrap();
+            assert!(is_written(&batch, &first));
+            let (_, second) =
+                write_context::next_checkpoint("current_token_ownerships_v2").unwrap();
+            assert_eq!(second, "current_token_ownerships_v2#2");
+            assert!(!is_written(&batch, &second));
+        })
+        .await;
+    }
+}
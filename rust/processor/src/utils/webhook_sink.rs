@@ -0,0 +1,300 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::utils::{
+    counters::WEBHOOK_SINK_REQUEST_COUNT,
+    write_context::{self, TableWrite},
+};
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the request body>` when a secret is configured.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// Posts the rows a processor produced to an HTTP endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookSinkConfig {
+    pub url: Url,
+    // Secret used to sign each request body. Requests are unsigned if unset
+    pub hmac_secret: Option<String>,
+    // Maximum number of rows per request
+    #[serde(default = "WebhookSinkConfig::default_max_batch_size")]
+    pub max_batch_size: usize,
+    // Number of times a failed request is retried before the batch fails
+    #[serde(default = "WebhookSinkConfig::default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "WebhookSinkConfig::default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+    #[serde(default = "WebhookSinkConfig::default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+impl WebhookSinkConfig {
+    pub const fn default_max_batch_size() -> usize {
+        1000
+    }
+
+    pub const fn default_max_retries() -> u32 {
+        5
+    }
+
+    pub const fn default_retry_delay_ms() -> u64 {
+        500
+    }
+
+    pub const fn default_request_timeout_secs() -> u64 {
+        30
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a, T> {
+    // Stable across retries and restarts so receivers can deduplicate
+    batch_id: String,
+    processor: &'a str,
+    table: &'a str,
+    start_version: u64,
+    end_version: u64,
+    rows: &'a [T],
+}
+
+/// Delivery is at least once: `send` only returns once every request has been acknowledged
+/// with a 2xx, so a processor that calls it before reporting a batch as processed won't
+/// advance `processor_status` past rows the endpoint hasn't seen.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    config: WebhookSinkConfig,
+}
+
+impl WebhookSink {
+    pub fn new(config: WebhookSinkConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .expect("Failed to build webhook HTTP client");
+        Self { client, config }
+    }
+
+    pub async fn send<T: Serialize>(
+        &self,
+        processor_name: &str,
+        table_name: &str,
+        start_version: u64,
+        end_version: u64,
+        rows: &[T],
+    ) -> Result<()> {
+        // Rows go to the sink like they go to the table: not in dry runs, which must not have side
+        // effects outside of the process, nor in backfills that don't cover the table
+        if write_context::table_write(table_name) != TableWrite::Write {
+            return Ok(());
+        }
+        for (chunk_index, chunk) in rows.chunks(self.config.max_batch_size.max(1)).enumerate() {
+            let body = serde_json::to_vec(&WebhookPayload {
+                batch_id: format!(
+                    "{}:{}:{}-{}:{}",
+                    processor_name, table_name, start_version, end_version, chunk_index
+                ),
+                processor: processor_name,
+                table: table_name,
+                start_version,
+                end_version,
+                rows: chunk,
+            })
+            .context("Failed to serialize webhook payload")?;
+            self.post_with_retries(processor_name, body).await?;
+        }
+        Ok(())
+    }
+
+    async fn post_with_retries(&self, processor_name: &str, body: Vec<u8>) -> Result<()> {
+        let mut retries = 0;
+        loop {
+            match self.post(body.clone()).await {
+                Ok(()) => {
+                    WEBHOOK_SINK_REQUEST_COUNT
+                        .with_label_values(&[processor_name, "success"])
+                        .inc();
+                    return Ok(());
+                },
+                Err(e) if retries < self.config.max_retries => {
+                    retries += 1;
+                    WEBHOOK_SINK_REQUEST_COUNT
+                        .with_label_values(&[processor_name, "retry"])
+                        .inc();
+                    warn!(
+                        processor_name = processor_name,
+                        url = self.config.url.as_str(),
+                        retries,
+                        error = ?e,
+                        "[Webhook Sink] Request failed, retrying"
+                    );
+                    tokio::time::sleep(Duration::from_millis(
+                        self.config.retry_delay_ms * retries as u64,
+                    ))
+                    .await;
+                },
+                Err(e) => {
+                    WEBHOOK_SINK_REQUEST_COUNT
+                        .with_label_values(&[processor_name, "failure"])
+                        .inc();
+                    return Err(e.context(format!(
+                        "Webhook request to {} failed after {} retries",
+                        self.config.url, retries
+                    )));
+                },
+            }
+        }
+    }
+
+    async fn post(&self, body: Vec<u8>) -> Result<()> {
+        let mut request = self
+            .client
+            .post(self.config.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.config.hmac_secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, &body));
+        }
+        let response = request.body(body).send().await?;
+        let status = response.status();
+        anyhow::ensure!(status.is_success(), "Webhook responded with {}", status);
+        Ok(())
+    }
+}
+
+/// Sends the rows of a processed batch to the processor's sink, if it has one. The batch only
+/// counts as processed once the endpoint has acknowledged it, so processors call this before
+/// returning their processing result.
+pub async fn send_processed_rows<T: Serialize>(
+    webhook_sink: Option<&WebhookSink>,
+    processor_name: &str,
+    table_name: &str,
+    start_version: u64,
+    end_version: u64,
+    rows: &[T],
+) -> Result<()> {
+    match webhook_sink {
+        Some(webhook_sink) => {
+            webhook_sink
+                .send(processor_name, table_name, start_version, end_version, rows)
+                .await
+        },
+        None => Ok(()),
+    }
+}
+
+/// Value of the `X-Signature-256` header for a request body.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Answers the n-th request with the n-th status (the last one once they run out), and
+    /// returns the server's URL and the number of requests it received
+    async fn serve_statuses(statuses: Vec<u16>) -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let request_count = Arc::new(AtomicUsize::new(0));
+        let counter = request_count.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                // Reads the whole request so the client doesn't see a reset connection
+                let mut request = vec![];
+                let mut buf = [0; 4096];
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    if n == 0 || is_complete_request(&request) {
+                        break;
+                    }
+                }
+                let index = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[index.min(statuses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        (url, request_count)
+    }
+
+    fn is_complete_request(request: &[u8]) -> bool {
+        let request = String::from_utf8_lossy(request);
+        let Some((headers, body)) = request.split_once("\r\n\r\n") else {
+            return false;
+        };
+        let content_length = headers
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .map_or(0, |(_, value)| value.trim().parse().unwrap());
+        body.len() >= content_length
+    }
+
+    fn webhook_sink(url: Url, max_retries: u32) -> WebhookSink {
+        WebhookSink::new(WebhookSinkConfig {
+            url,
+            hmac_secret: None,
+            max_batch_size: WebhookSinkConfig::default_max_batch_size(),
+            max_retries,
+            retry_delay_ms: 1,
+            request_timeout_secs: 5,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_are_retried_until_acknowledged() {
+        let (url, request_count) = serve_statuses(vec![500, 200]).await;
+        let webhook_sink = webhook_sink(url, 2);
+
+        send_processed_rows(Some(&webhook_sink), "processor", "events", 1, 2, &[1, 2])
+            .await
+            .unwrap();
+        assert_eq!(request_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_fails_without_acknowledgement() {
+        let (url, request_count) = serve_statuses(vec![500]).await;
+        let webhook_sink = webhook_sink(url, 2);
+
+        // The processor returns this error instead of a processing result, so the batch isn't
+        // reported as processed and processor_status doesn't advance
+        let result =
+            send_processed_rows(Some(&webhook_sink), "processor", "events", 1, 2, &[1, 2]).await;
+        assert!(result.is_err());
+        // The first attempt and 2 retries
+        assert_eq!(request_count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Every write of a processor task goes through the write context the task runs in. It decides
+//! per table whether rows are written, captured for a dry run or dropped because a backfill
+//! doesn't cover the table. Batches also carry what their writes need: the numbering of their
+//! table checkpoints and, with transactional batch writes, the connection of their transaction.
+//!
+//! Writes outside of a context, e.g. while setting up the DB, are always written.
+
+use super::{
+    batch_transaction::BatchConnection,
+    table_checkpoints::{self, Batch},
+};
+use ahash::AHashMap;
+use std::{cell::RefCell, collections::HashSet, future::Future, sync::Arc};
+
+tokio::task_local! {
+    static WRITE_CONTEXT: WriteContext;
+}
+
+/// How a processor writes, set once when the worker starts
+#[derive(Clone, Debug, Default)]
+pub struct WriteMode {
+    /// Rows are captured for the dry run report instead of written
+    pub dry_run: bool,
+    /// Only these tables are written, e.g. by a backfill
+    pub writable_tables: Option<Arc<HashSet<String>>>,
+    /// Every write of a batch is committed in a single transaction
+    pub transactional_batch_writes: bool,
+}
+
+pub struct WriteContext {
+    processor_name: &'static str,
+    mode: WriteMode,
+    batch: Option<BatchWrites>,
+}
+
+struct BatchWrites {
+    batch: Batch,
+    // Number of writes started so far per table
+    table_writes: RefCell<AHashMap<String, usize>>,
+    connection: Option<BatchConnection>,
+}
+
+/// What happens to rows written to a table
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TableWrite {
+    Write,
+    /// Captured for the dry run report
+    Capture,
+    /// Dropped, the table isn't written by this run
+    Skip,
+}
+
+impl WriteContext {
+    pub fn new(processor_name: &'static str, mode: WriteMode) -> Self {
+        Self {
+            processor_name,
+            mode,
+            batch: None,
+        }
+    }
+
+    /// Attaches the batch being written and, if it's written in a single transaction, its
+    /// connection
+    pub fn with_batch(mut self, batch: Batch, connection: Option<BatchConnection>) -> Self {
+        self.batch = Some(BatchWrites {
+            batch,
+            table_writes: RefCell::default(),
+            connection,
+        });
+        self
+    }
+}
+
+/// Runs `f` with its writes going through `context`
+pub async fn scope<F: Future>(context: WriteContext, f: F) -> F::Output {
+    WRITE_CONTEXT.scope(context, f).await
+}
+
+fn with_context<R>(f: impl FnOnce(&WriteContext) -> R) -> Option<R> {
+    WRITE_CONTEXT.try_with(f).ok()
+}
+
+/// The processor whose writes are running
+pub fn processor_name() -> Option<&'static str> {
+    with_context(|context| context.processor_name)
+}
+
+/// Whether nothing is written at all, including the processor's bookkeeping
+pub fn is_dry_run() -> bool {
+    with_context(|context| context.mode.dry_run).unwrap_or_default()
+}
+
+/// What happens to rows written to `table_name`. Every table write has to go through this.
+pub fn table_write(table_name: &str) -> TableWrite {
+    with_context(|context| {
+        let is_writable = context
+            .mode
+            .writable_tables
+            .as_ref()
+            .map_or(true, |tables| tables.contains(table_name));
+        if !is_writable {
+            TableWrite::Skip
+        } else if context.mode.dry_run {
+            TableWrite::Capture
+        } else {
+            TableWrite::Write
+        }
+    })
+    .unwrap_or(TableWrite::Write)
+}
+
+/// The connection of the batch being written, if it's written in a single transaction
+pub fn connection() -> Option<BatchConnection> {
+    with_context(|context| {
+        context
+            .batch
+            .as_ref()
+            .and_then(|batch| batch.connection.clone())
+    })
+    .flatten()
+}
+
+/// The batch being written and the name the checkpoint of the next write to the table is stored
+/// under, if its processor has table checkpoints enabled. See
+/// [`table_checkpoints::checkpoint_name`].
+pub fn next_checkpoint(table_name: &str) -> Option<(Batch, String)> {
+    with_context(|context| {
+        let batch = context.batch.as_ref()?;
+        if !table_checkpoints::is_enabled(batch.batch.processor_name) {
+            return None;
+        }
+        let mut table_writes = batch.table_writes.borrow_mut();
+        let write_number = table_writes.entry(table_name.to_string()).or_default();
+        *write_number += 1;
+        Some((
+            batch.batch,
+            table_checkpoints::checkpoint_name(table_name, *write_number),
+        ))
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_backfills_only_write_their_tables() {
+        let mode = WriteMode {
+            dry_run: true,
+            writable_tables: Some(Arc::new(HashSet::from(["events".to_string()]))),
+            transactional_batch_writes: false,
+        };
+        scope(WriteContext::new("events_processor", mode), async {
+            assert_eq!(table_write("events"), TableWrite::Capture);
+            assert_eq!(table_write("custom_events"), TableWrite::Skip);
+            assert!(is_dry_run());
+        })
+        .await;
+        assert_eq!(table_write("custom_events"), TableWrite::Write);
+        assert!(!is_dry_run());
+    }
+}
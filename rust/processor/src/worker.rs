@@ -2,21 +2,31 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::IndexerGrpcHttp2Config,
-    db::common::models::{ledger_info::LedgerInfo, processor_status::ProcessorStatusQuery},
+    config::{BackfillConfig, IndexerGrpcHttp2Config, TransactionSourceConfig},
+    db::common::models::{
+        backfill_status::{BackfillJob, BackfillStatusQuery},
+        ledger_info::LedgerInfo,
+        processor_status::ProcessorStatusQuery,
+        processor_table_status::ProcessorTableStatusQuery,
+    },
     gap_detectors::{
-        create_gap_detector_status_tracker_loop, gap_detector::DefaultGapDetector,
-        parquet_gap_detector::ParquetFileGapDetectorInner, GapDetector, ProcessingResult,
+        create_gap_detector_status_tracker_loop,
+        gap_detector::DefaultGapDetector,
+        gap_repair::{GapRepairConfig, GapRepairer},
+        parquet_gap_detector::ParquetFileGapDetectorInner,
+        GapDetector, ProcessingResult,
     },
     grpc_stream::TransactionsPBResponse,
     processors::{
         account_transactions_processor::AccountTransactionsProcessor,
         ans_processor::AnsProcessor,
+        custom_event_processor::CustomEventProcessor,
         default_processor::DefaultProcessor,
         events_processor::EventsProcessor,
         fungible_asset_processor::FungibleAssetProcessor,
         monitoring_processor::MonitoringProcessor,
         nft_metadata_processor::NftMetadataProcessor,
+        nft_points_processor::NftPointsProcessor,
         objects_processor::ObjectsProcessor,
         parquet_processors::{
             parquet_ans_processor::ParquetAnsProcessor,
@@ -36,19 +46,26 @@ use crate::{
     schema::ledger_infos,
     transaction_filter::TransactionFilter,
     utils::{
+        batch_transaction,
         counters::{
-            ProcessorStep, GRPC_LATENCY_BY_PROCESSOR_IN_SECS, LATEST_PROCESSED_VERSION,
-            NUM_TRANSACTIONS_PROCESSED_COUNT, PB_CHANNEL_FETCH_WAIT_TIME_SECS,
-            PROCESSED_BYTES_COUNT, PROCESSOR_DATA_PROCESSED_LATENCY_IN_SECS,
-            PROCESSOR_DATA_RECEIVED_LATENCY_IN_SECS, PROCESSOR_ERRORS_COUNT,
-            PROCESSOR_INVOCATIONS_COUNT, PROCESSOR_SUCCESSES_COUNT,
+            ProcessorStep, FAN_OUT_SEND_WAIT_TIME_SECS, GRPC_LATENCY_BY_PROCESSOR_IN_SECS,
+            LATEST_PROCESSED_VERSION, NUM_TRANSACTIONS_PROCESSED_COUNT,
+            PB_CHANNEL_FETCH_WAIT_TIME_SECS, PROCESSED_BYTES_COUNT,
+            PROCESSOR_DATA_PROCESSED_LATENCY_IN_SECS, PROCESSOR_DATA_RECEIVED_LATENCY_IN_SECS,
+            PROCESSOR_ERRORS_COUNT, PROCESSOR_INVOCATIONS_COUNT, PROCESSOR_SUCCESSES_COUNT,
             SINGLE_BATCH_DB_INSERTION_TIME_IN_SECS, SINGLE_BATCH_PARSING_TIME_IN_SECS,
             SINGLE_BATCH_PROCESSING_TIME_IN_SECS, TRANSACTION_UNIX_TIMESTAMP,
         },
         database::{
             execute_with_better_error_conn, new_db_pool, run_pending_migrations, ArcDbPool,
         },
-        util::{time_diff_since_pb_timestamp_in_secs, timestamp_to_iso, timestamp_to_unixtime},
+        dry_run::{self, DryRunConfig},
+        table_checkpoints::{self, Batch},
+        util::{
+            parse_timestamp, time_diff_since_pb_timestamp_in_secs, timestamp_to_iso,
+            timestamp_to_unixtime,
+        },
+        write_context::{self, WriteContext, WriteMode},
     },
 };
 use ahash::AHashMap;
@@ -60,7 +77,7 @@ use std::{
     collections::HashSet,
     sync::{Arc, Mutex},
 };
-use tokio::task::JoinHandle;
+use tokio::{sync::oneshot, task::JoinHandle};
 use tracing::{debug, error, info};
 use url::Url;
 // this is how large the fetch queue should be. Each bucket should have a max of 80MB or so, so a batch
@@ -69,6 +86,8 @@ use url::Url;
 
 pub const BUFFER_SIZE: usize = 300;
 pub const PROCESSOR_SERVICE_TYPE: &str = "processor";
+// How long the gap detectors get to commit their last version once the processors are done
+const GAP_DETECTOR_SHUTDOWN_TIMEOUT_SECS: u64 = 60;
 
 bitflags! {
     #[derive(Debug, Clone, Copy)]
@@ -110,12 +129,57 @@ bitflags! {
 
         // User transaction
         const SIGNATURES = 1 << 23;
+        const TRANSACTION_FEES = 1 << 25;
+
+        // Validator analytics
+        const EPOCHS = 1 << 26;
+        const VALIDATOR_EPOCH_STATS = 1 << 27;
+    }
+}
+
+impl TableFlags {
+    /// The flag gating writes to a table, if any. Flag names don't always match the table,
+    /// e.g. current_fungible_asset_balances is gated by CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES.
+    pub fn from_table_name(table_name: &str) -> Option<Self> {
+        let flags = match table_name {
+            "transactions" => Self::TRANSACTIONS,
+            "write_set_changes" => Self::WRITE_SET_CHANGES,
+            "move_resources" => Self::MOVE_RESOURCES,
+            "table_items" => Self::TABLE_ITEMS,
+            "table_metadatas" => Self::TABLE_METADATAS,
+            "move_modules" => Self::MOVE_MODULES,
+            "fungible_asset_balances" => Self::FUNGIBLE_ASSET_BALANCES,
+            "current_fungible_asset_balances_legacy" => Self::CURRENT_FUNGIBLE_ASSET_BALANCES,
+            "coin_supply" => Self::COIN_SUPPLY,
+            "current_fungible_asset_balances" => Self::CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES,
+            "objects" => Self::OBJECTS,
+            "current_ans_lookup" => Self::CURRENT_ANS_LOOKUP,
+            "current_ans_primary_name" => Self::CURRENT_ANS_PRIMARY_NAME,
+            "ans_primary_name_v2" => Self::ANS_PRIMARY_NAME_V2,
+            "ans_lookup" => Self::ANS_LOOKUP,
+            "ans_primary_name" => Self::ANS_PRIMARY_NAME,
+            "coin_activities" => Self::COIN_ACTIVITIES,
+            "coin_balances" => Self::COIN_BALANCES,
+            "current_coin_balances" => Self::CURRENT_COIN_BALANCES,
+            "coin_infos" => Self::COIN_INFOS,
+            "token_ownerships_v2" => Self::TOKEN_OWNERSHIPS_V2,
+            "token_datas_v2" => Self::TOKEN_DATAS_V2,
+            "collections_v2" => Self::COLLECTIONS_V2,
+            "current_token_v2_metadata" => Self::CURRENT_TOKEN_V2_METADATA,
+            "signatures" => Self::SIGNATURES,
+            "transaction_fees" => Self::TRANSACTION_FEES,
+            "epochs" => Self::EPOCHS,
+            "validator_epoch_stats" => Self::VALIDATOR_EPOCH_STATS,
+            _ => return None,
+        };
+        Some(flags)
     }
 }
 
 pub struct Worker {
     pub db_pool: ArcDbPool,
     pub processor_config: ProcessorConfig,
+    pub additional_processor_configs: Vec<ProcessorConfig>,
     pub postgres_connection_string: String,
     pub indexer_grpc_data_service_address: Url,
     pub grpc_http2_config: IndexerGrpcHttp2Config,
@@ -125,6 +189,7 @@ pub struct Worker {
     pub number_concurrent_processing_tasks: usize,
     pub gap_detection_batch_size: u64,
     pub parquet_gap_detection_batch_size: u64,
+    pub gap_repair: Option<GapRepairConfig>,
     pub grpc_chain_id: Option<u64>,
     pub pb_channel_txn_chunk_size: usize,
     pub per_table_chunk_sizes: AHashMap<String, usize>,
@@ -132,12 +197,18 @@ pub struct Worker {
     pub transaction_filter: TransactionFilter,
     pub grpc_response_item_timeout_in_secs: u64,
     pub deprecated_tables: TableFlags,
+    pub transaction_source: TransactionSourceConfig,
+    pub dry_run: Option<DryRunConfig>,
+    pub backfill: Option<BackfillConfig>,
+    pub enable_table_checkpoints: bool,
+    pub transactional_batch_writes: bool,
 }
 
 impl Worker {
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
         processor_config: ProcessorConfig,
+        additional_processor_configs: Vec<ProcessorConfig>,
         postgres_connection_string: String,
         indexer_grpc_data_service_address: Url,
         grpc_http2_config: IndexerGrpcHttp2Config,
@@ -148,6 +219,7 @@ impl Worker {
         db_pool_size: Option<u32>,
         gap_detection_batch_size: u64,
         parquet_gap_detection_batch_size: u64,
+        gap_repair: Option<GapRepairConfig>,
         // The number of transactions per protobuf batch
         pb_channel_txn_chunk_size: usize,
         per_table_chunk_sizes: AHashMap<String, usize>,
@@ -155,10 +227,86 @@ impl Worker {
         transaction_filter: TransactionFilter,
         grpc_response_item_timeout_in_secs: u64,
         deprecated_tables: HashSet<String>,
+        transaction_source: TransactionSourceConfig,
+        dry_run: Option<DryRunConfig>,
+        backfill: Option<BackfillConfig>,
+        enable_table_checkpoints: bool,
+        transactional_batch_writes: bool,
     ) -> Result<Self> {
         let processor_name = processor_config.name();
         info!(processor_name = processor_name, "[Parser] Kicking off");
 
+        // Each processor tracks its own processor_status row, keyed by name
+        let mut processor_names = HashSet::new();
+        for config in std::iter::once(&processor_config).chain(additional_processor_configs.iter())
+        {
+            anyhow::ensure!(
+                processor_names.insert(config.name()),
+                "[Parser] Processor {} is configured more than once",
+                config.name()
+            );
+            if let ProcessorConfig::CustomEventProcessor(config) = config {
+                config.validate()?;
+            }
+            if dry_run.is_some() {
+                anyhow::ensure!(
+                    !config.is_parquet_processor()
+                        && !matches!(config, ProcessorConfig::NftMetadataProcessor(_)),
+                    "[Parser] Processor {} doesn't write to the DB and can't be dry run",
+                    config.name()
+                );
+            }
+        }
+        if dry_run.is_some() {
+            anyhow::ensure!(
+                ending_version.is_some(),
+                "[Parser] Dry run needs an ending_version"
+            );
+        }
+        if let Some(backfill) = &backfill {
+            anyhow::ensure!(
+                additional_processor_configs.is_empty(),
+                "[Parser] A backfill runs a single processor"
+            );
+            anyhow::ensure!(
+                !processor_config.is_parquet_processor(),
+                "[Parser] Parquet processors can't be backfilled"
+            );
+            anyhow::ensure!(
+                ending_version.is_some(),
+                "[Parser] Backfill {} needs an ending_version",
+                backfill.backfill_alias
+            );
+            anyhow::ensure!(
+                !backfill.tables.is_empty(),
+                "[Parser] Backfill {} has no tables to write",
+                backfill.backfill_alias
+            );
+            // A misspelled table would otherwise silently backfill nothing
+            let processor_tables = processor_config.table_names();
+            for table in backfill.tables.iter() {
+                anyhow::ensure!(
+                    processor_tables.contains(table),
+                    "[Parser] Backfill {} can't write table {}, {} only writes {}",
+                    backfill.backfill_alias,
+                    table,
+                    processor_config.name(),
+                    processor_tables.join(", ")
+                );
+            }
+            // Table checkpoints and processor_status belong to the live processor
+            anyhow::ensure!(
+                !enable_table_checkpoints && !transactional_batch_writes,
+                "[Parser] Backfills can't use table checkpoints or transactional batch writes"
+            );
+        }
+        if transactional_batch_writes {
+            anyhow::ensure!(
+                dry_run.is_none(),
+                "[Parser] Dry runs don't write and can't use transactional batch writes"
+            );
+        }
+
         info!(
             processor_name = processor_name,
             service_type = PROCESSOR_SERVICE_TYPE,
@@ -172,7 +320,11 @@ impl Worker {
             service_type = PROCESSOR_SERVICE_TYPE,
             "[Parser] Finish creating the connection pool"
         );
-        let number_concurrent_processing_tasks = number_concurrent_processing_tasks.unwrap_or(10);
+        let number_concurrent_processing_tasks = concurrent_processing_tasks(
+            number_concurrent_processing_tasks,
+            dry_run.is_some(),
+            transactional_batch_writes,
+        );
 
         let mut deprecated_tables_flags = TableFlags::empty();
         for table in deprecated_tables.iter() {
@@ -180,10 +332,22 @@ impl Worker {
                 deprecated_tables_flags |= flags;
             }
         }
+        if let Some(backfill) = &backfill {
+            // Processors skip the flagged tables that aren't backfilled, everything else is
+            // dropped at write time
+            let mut backfilled_tables_flags = TableFlags::empty();
+            for table in backfill.tables.iter() {
+                if let Some(flags) = TableFlags::from_table_name(table) {
+                    backfilled_tables_flags |= flags;
+                }
+            }
+            deprecated_tables_flags |= TableFlags::all() - backfilled_tables_flags;
+        }
 
         Ok(Self {
             db_pool: conn_pool,
             processor_config,
+            additional_processor_configs,
             postgres_connection_string,
             indexer_grpc_data_service_address,
             grpc_http2_config,
@@ -193,6 +357,7 @@ impl Worker {
             number_concurrent_processing_tasks,
             gap_detection_batch_size,
             parquet_gap_detection_batch_size,
+            gap_repair,
             grpc_chain_id: None,
             pb_channel_txn_chunk_size,
             per_table_chunk_sizes,
@@ -200,6 +365,11 @@ impl Worker {
             transaction_filter,
             grpc_response_item_timeout_in_secs,
             deprecated_tables: deprecated_tables_flags,
+            transaction_source,
+            dry_run,
+            backfill,
+            enable_table_checkpoints,
+            transactional_batch_writes,
         })
     }
 
@@ -211,25 +381,32 @@ impl Worker {
     /// 4. We will keep track of the last processed version and monitoring things like TPS
     pub async fn run(&mut self) {
         let processor_name = self.processor_config.name();
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            "[Parser] Running migrations"
-        );
-        let migration_time = std::time::Instant::now();
-        self.run_migrations().await;
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            duration_in_secs = migration_time.elapsed().as_secs_f64(),
-            "[Parser] Finished migrations"
-        );
+        if self.dry_run.is_some() {
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                "[Parser] Dry run, skipping migrations and nothing will be written to the DB"
+            );
+        } else {
+            self.prepare_db().await;
+        }
 
-        let starting_version_from_db = self
-            .get_start_version()
-            .await
-            .expect("[Parser] Database error when getting starting version")
-            .unwrap_or_else(|| {
+        // Each processor resumes from its own processor_status. The shared stream starts from
+        // the one furthest behind and the others skip the versions they've already processed.
+        let mut processors = vec![];
+        for processor_config in
+            std::iter::once(&self.processor_config).chain(self.additional_processor_configs.iter())
+        {
+            let processor_name = processor_config.name();
+            let maybe_starting_version_from_db = match &self.backfill {
+                Some(backfill) => {
+                    self.get_backfill_start_version(&backfill.backfill_alias, processor_name)
+                        .await
+                },
+                None => self.get_start_version(processor_name).await,
+            }
+            .expect("[Parser] Database error when getting starting version");
+            let starting_version_from_db = maybe_starting_version_from_db.unwrap_or_else(|| {
                 info!(
                     processor_name = processor_name,
                     service_type = PROCESSOR_SERVICE_TYPE,
@@ -238,33 +415,73 @@ impl Worker {
                 0
             });
 
-        let starting_version = self.starting_version.unwrap_or(starting_version_from_db);
+            let starting_version = match (&self.backfill, maybe_starting_version_from_db) {
+                // A backfill resumes from its own progress, starting_version only applies to its
+                // first run
+                (Some(_), Some(starting_version_from_db)) => starting_version_from_db,
+                _ => self.starting_version.unwrap_or(starting_version_from_db),
+            };
+            let starting_version =
+                if self.enable_table_checkpoints && !processor_config.is_parquet_processor() {
+                    self.load_table_checkpoints(processor_name, starting_version)
+                        .await
+                        .expect("[Parser] Database error when getting table checkpoints")
+                } else {
+                    starting_version
+                };
 
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            stream_address = self.indexer_grpc_data_service_address.to_string(),
-            final_start_version = starting_version,
-            start_version_from_config = self.starting_version,
-            start_version_from_db = starting_version_from_db,
-            "[Parser] Building processor",
-        );
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                stream_address = self.indexer_grpc_data_service_address.to_string(),
+                final_start_version = starting_version,
+                start_version_from_config = self.starting_version,
+                start_version_from_db = starting_version_from_db,
+                "[Parser] Building processor",
+            );
+            processors.push((processor_config.clone(), starting_version));
+        }
+        let starting_version = processors
+            .iter()
+            .map(|(_, starting_version)| *starting_version)
+            .min()
+            .unwrap_or_default();
+        if let (Some(backfill), Some(ending_version)) = (&self.backfill, self.ending_version) {
+            if starting_version > ending_version {
+                info!(
+                    processor_name = processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    backfill_alias = backfill.backfill_alias,
+                    "[Parser] Backfill is already complete"
+                );
+                return;
+            }
+        }
 
         let concurrent_tasks = self.number_concurrent_processing_tasks;
 
         // get the chain id
-        let chain_id = crate::grpc_stream::get_chain_id(
-            self.indexer_grpc_data_service_address.clone(),
-            self.grpc_http2_config.grpc_http2_ping_interval_in_secs(),
-            self.grpc_http2_config.grpc_http2_ping_timeout_in_secs(),
-            self.grpc_http2_config.grpc_connection_timeout_secs(),
-            self.auth_token.clone(),
-            processor_name.to_string(),
+        let chain_id = match &self.transaction_source {
+            TransactionSourceConfig::Grpc => {
+                crate::grpc_stream::get_chain_id(
+                    self.indexer_grpc_data_service_address.clone(),
+                    self.grpc_http2_config.grpc_http2_ping_interval_in_secs(),
+                    self.grpc_http2_config.grpc_http2_ping_timeout_in_secs(),
+                    self.grpc_http2_config.grpc_connection_timeout_secs(),
+                    self.auth_token.clone(),
+                    processor_name.to_string(),
+                )
+                .await
+            },
+            TransactionSourceConfig::LocalFiles(config) => config.chain_id,
+        };
+        // Not written in a dry run
+        write_context::scope(
+            WriteContext::new(processor_name, self.write_mode(&self.processor_config)),
+            self.check_or_update_chain_id(chain_id as i64),
         )
-        .await;
-        self.check_or_update_chain_id(chain_id as i64)
-            .await
-            .unwrap();
+        .await
+        .unwrap();
 
         self.grpc_chain_id = Some(chain_id);
 
@@ -287,6 +504,7 @@ impl Worker {
         let transaction_filter = self.transaction_filter.clone();
         let grpc_response_item_timeout =
             std::time::Duration::from_secs(self.grpc_response_item_timeout_in_secs);
+        let transaction_source = self.transaction_source.clone();
         let fetcher_task = tokio::spawn(async move {
             info!(
                 processor_name = processor_name,
@@ -296,28 +514,216 @@ impl Worker {
                 "[Parser] Starting fetcher thread"
             );
 
-            crate::grpc_stream::create_fetcher_loop(
-                tx.clone(),
-                indexer_grpc_data_service_address.clone(),
-                indexer_grpc_http2_ping_interval,
-                indexer_grpc_http2_ping_timeout,
-                indexer_grpc_reconnection_timeout_secs,
-                grpc_response_item_timeout,
-                starting_version,
-                request_ending_version,
-                auth_token.clone(),
-                processor_name.to_string(),
-                transaction_filter,
-                pb_channel_txn_chunk_size,
+            let fetcher_loop = async move {
+                match transaction_source {
+                    TransactionSourceConfig::Grpc => {
+                        crate::grpc_stream::create_fetcher_loop(
+                            tx.clone(),
+                            indexer_grpc_data_service_address.clone(),
+                            indexer_grpc_http2_ping_interval,
+                            indexer_grpc_http2_ping_timeout,
+                            indexer_grpc_reconnection_timeout_secs,
+                            grpc_response_item_timeout,
+                            starting_version,
+                            request_ending_version,
+                            auth_token.clone(),
+                            processor_name.to_string(),
+                            transaction_filter,
+                            pb_channel_txn_chunk_size,
+                        )
+                        .await
+                    },
+                    TransactionSourceConfig::LocalFiles(config) => {
+                        crate::local_stream::create_local_fetcher_loop(
+                            tx.clone(),
+                            config,
+                            starting_version,
+                            request_ending_version,
+                            processor_name.to_string(),
+                            transaction_filter,
+                            pb_channel_txn_chunk_size,
+                        )
+                        .await
+                    },
+                }
+            };
+
+            // Dropping the fetcher on shutdown drops its sender, so the processor tasks drain
+            // whatever is left in the channel and then exit.
+            tokio::select! {
+                _ = fetcher_loop => {},
+                _ = shutdown_signal() => {
+                    info!(
+                        processor_name = processor_name,
+                        service_type = PROCESSOR_SERVICE_TYPE,
+                        "[Parser] Received shutdown signal, stopping fetcher thread"
+                    );
+                },
+            }
+        });
+
+        // With several processors, one task copies each batch from the fetcher into every
+        // processor's channel so the stream is only downloaded once
+        let mut processor_tasks = vec![fetcher_task];
+        let processor_receivers = if processors.len() == 1 {
+            vec![receiver]
+        } else {
+            let (senders, receivers): (Vec<_>, Vec<_>) = processors
+                .iter()
+                .map(|(processor_config, _)| {
+                    let (sender, receiver) =
+                        kanal::bounded_async::<TransactionsPBResponse>(BUFFER_SIZE);
+                    ((processor_config.name(), sender), receiver)
+                })
+                .unzip();
+            processor_tasks.push(tokio::spawn(fan_out_transactions(receiver, senders)));
+            receivers
+        };
+
+        // This is the consumer side of the channel. These are the major states:
+        // 1. We're backfilling so we should expect many concurrent threads to process transactions
+        // 2. We're caught up so we should expect a single thread to process transactions
+        // 3. We have received either an empty batch or a batch with a gap. We should panic.
+        // 4. We have not received anything in X seconds, we should panic.
+        // 5. If it's the wrong chain, panic.
+
+        let mut gap_detector_tasks = vec![];
+        for ((processor_config, processor_starting_version), receiver) in
+            processors.into_iter().zip(processor_receivers)
+        {
+            let processor_name = processor_config.name();
+            let (gap_detector, gap_detector_sender, close_gap_detector, gap_detector_task) =
+                self.launch_gap_detector(&processor_config, processor_starting_version, chain_id);
+
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                stream_address = self.indexer_grpc_data_service_address.as_str(),
+                concurrent_tasks,
+                "[Parser] Spawning concurrent parallel processor tasks",
+            );
+
+            for task_index in 0..concurrent_tasks {
+                let join_handle: JoinHandle<()> = self
+                    .launch_processor_task(
+                        &processor_config,
+                        processor_starting_version,
+                        task_index,
+                        receiver.clone(),
+                        gap_detector_sender.clone(),
+                        gap_detector.clone(),
+                    )
+                    .await;
+                processor_tasks.push(join_handle);
+            }
+
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                stream_address = self.indexer_grpc_data_service_address.as_str(),
+                concurrent_tasks,
+                "[Parser] Processor tasks spawned",
+            );
+
+            gap_detector_tasks.push((processor_name, close_gap_detector, gap_detector_task));
+        }
+
+        // Await the processor tasks: this is forever unless we reach the ending version or
+        // receive a shutdown signal
+        futures::future::try_join_all(processor_tasks)
+            .await
+            .expect("[Processor] Processor tasks have died");
+
+        // Every processor task has flushed its parquet handlers by now, so nothing new reaches
+        // the gap detectors. Let them commit the last contiguous version before exiting.
+        for (gap_processor_name, close_gap_detector, gap_detector_task) in gap_detector_tasks {
+            // The gap detector may have exited already, in which case there is nothing to wait for
+            let _ = close_gap_detector.send(());
+            match tokio::time::timeout(
+                std::time::Duration::from_secs(GAP_DETECTOR_SHUTDOWN_TIMEOUT_SECS),
+                gap_detector_task,
             )
             .await
-        });
+            {
+                Ok(result) => result
+                    .expect("[Processor] Gap detector task has died")
+                    .expect("[Processor] Gap detector has failed"),
+                Err(_) => error!(
+                    processor_name = gap_processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    "[Parser] Gap detector didn't finish within {}s, the versions it hasn't \
+                     committed will be processed again on restart",
+                    GAP_DETECTOR_SHUTDOWN_TIMEOUT_SECS
+                ),
+            }
+        }
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            "[Parser] All processor tasks have finished, shutting down"
+        );
 
+        if let Some(dry_run_config) = &self.dry_run {
+            dry_run::write_report(self.db_pool.clone(), dry_run_config)
+                .await
+                .expect("[Parser] Failed to write dry run report");
+        }
+    }
+
+    /// Runs the migrations and creates the tables declared in processor configs
+    async fn prepare_db(&self) {
+        let processor_name = self.processor_config.name();
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            "[Parser] Running migrations"
+        );
+        let migration_time = std::time::Instant::now();
+        self.run_migrations().await;
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            duration_in_secs = migration_time.elapsed().as_secs_f64(),
+            "[Parser] Finished migrations"
+        );
+
+        // Custom event tables are declared in the config rather than in the migrations
+        for processor_config in
+            std::iter::once(&self.processor_config).chain(self.additional_processor_configs.iter())
+        {
+            if let ProcessorConfig::CustomEventProcessor(config) = processor_config {
+                let mut conn = self
+                    .db_pool
+                    .get()
+                    .await
+                    .expect("[Parser] Failed to get connection");
+                config
+                    .create_tables(&mut conn)
+                    .await
+                    .expect("[Parser] Failed to create custom event tables");
+            }
+        }
+    }
+
+    /// Spawns the gap detector for one processor, which also keeps its processor_status up to date.
+    fn launch_gap_detector(
+        &self,
+        processor_config: &ProcessorConfig,
+        starting_version: u64,
+        chain_id: u64,
+    ) -> (
+        GapDetector,
+        AsyncSender<ProcessingResult>,
+        oneshot::Sender<()>,
+        JoinHandle<Result<()>>,
+    ) {
         // Create a gap detector task that will panic if there is a gap in the processing
         let (gap_detector_sender, gap_detector_receiver) =
             kanal::bounded_async::<ProcessingResult>(BUFFER_SIZE);
+        // Fired once every processor task is done, see create_gap_detector_status_tracker_loop
+        let (close_gap_detector, input_closed) = oneshot::channel();
 
-        let is_parquet_processor = self.processor_config.is_parquet_processor();
+        let is_parquet_processor = processor_config.is_parquet_processor();
         let (maybe_gap_detector_sender, gap_detection_batch_size) = if is_parquet_processor {
             let gap_detection_batch_size: u64 = self.parquet_gap_detection_batch_size;
             (Some(gap_detector_sender.clone()), gap_detection_batch_size)
@@ -326,13 +732,13 @@ impl Worker {
             (None, gap_detection_batch_size)
         };
 
-        let processor = build_processor(
-            &self.processor_config,
+        let processor = Arc::new(build_processor(
+            processor_config,
             self.per_table_chunk_sizes.clone(),
             self.deprecated_tables,
             self.db_pool.clone(),
             maybe_gap_detector_sender,
-        );
+        ));
 
         let gap_detector = if is_parquet_processor {
             GapDetector::ParquetFileGapDetector(Arc::new(Mutex::new(
@@ -343,74 +749,74 @@ impl Worker {
         };
         let gap_detector_clone = gap_detector.clone();
 
-        tokio::spawn(async move {
+        // Gaps can only be re-fetched from the data service
+        let gap_repairer = match (&self.gap_repair, &self.transaction_source) {
+            (Some(gap_repair), TransactionSourceConfig::Grpc) => Some(GapRepairer::new(
+                gap_repair.clone(),
+                self.indexer_grpc_data_service_address.clone(),
+                self.grpc_http2_config.clone(),
+                std::time::Duration::from_secs(self.grpc_response_item_timeout_in_secs),
+                self.auth_token.clone(),
+                chain_id,
+                self.transaction_filter.clone(),
+                self.pb_channel_txn_chunk_size,
+                self.write_mode(processor_config),
+                gap_detector_sender.clone(),
+            )),
+            _ => None,
+        };
+
+        // Backfills report their progress under their own alias instead of processor_status
+        let backfill_job = self.backfill.as_ref().map(|backfill| BackfillJob {
+            backfill_alias: backfill.backfill_alias.clone(),
+            start_version: self.starting_version.unwrap_or_default(),
+            end_version: self
+                .ending_version
+                .expect("Backfills are validated to have an ending version"),
+        });
+
+        // processor_status and backfill_status aren't written in a dry run either
+        let write_context =
+            WriteContext::new(processor_config.name(), self.write_mode(processor_config));
+        let gap_detector_task = tokio::spawn(write_context::scope(write_context, async move {
             create_gap_detector_status_tracker_loop(
                 gap_detector_clone,
                 gap_detector_receiver,
+                input_closed,
                 processor,
                 gap_detection_batch_size,
+                gap_repairer,
+                backfill_job,
             )
-            .await;
-        });
-
-        // This is the consumer side of the channel. These are the major states:
-        // 1. We're backfilling so we should expect many concurrent threads to process transactions
-        // 2. We're caught up so we should expect a single thread to process transactions
-        // 3. We have received either an empty batch or a batch with a gap. We should panic.
-        // 4. We have not received anything in X seconds, we should panic.
-        // 5. If it's the wrong chain, panic.
-
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            stream_address = self.indexer_grpc_data_service_address.as_str(),
-            concurrent_tasks,
-            "[Parser] Spawning concurrent parallel processor tasks",
-        );
-
-        let mut processor_tasks = vec![fetcher_task];
-        for task_index in 0..concurrent_tasks {
-            let join_handle: JoinHandle<()> = self
-                .launch_processor_task(
-                    task_index,
-                    receiver.clone(),
-                    gap_detector_sender.clone(),
-                    gap_detector.clone(),
-                )
-                .await;
-            processor_tasks.push(join_handle);
-        }
-
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            stream_address = self.indexer_grpc_data_service_address.as_str(),
-            concurrent_tasks,
-            "[Parser] Processor tasks spawned",
-        );
-
-        // Await the processor tasks: this is forever
-        futures::future::try_join_all(processor_tasks)
             .await
-            .expect("[Processor] Processor tasks have died");
+        }));
+
+        (
+            gap_detector,
+            gap_detector_sender,
+            close_gap_detector,
+            gap_detector_task,
+        )
     }
 
     async fn launch_processor_task(
         &self,
+        processor_config: &ProcessorConfig,
+        starting_version: u64,
         task_index: usize,
         receiver: kanal::AsyncReceiver<TransactionsPBResponse>,
         gap_detector_sender: AsyncSender<ProcessingResult>,
         mut gap_detector: GapDetector,
     ) -> JoinHandle<()> {
-        let processor_name = self.processor_config.name();
+        let processor_name = processor_config.name();
         let stream_address = self.indexer_grpc_data_service_address.to_string();
         let receiver_clone = receiver.clone();
         let auth_token = self.auth_token.clone();
 
         // Build the processor based on the config.
-        let processor = if self.processor_config.is_parquet_processor() {
+        let processor = if processor_config.is_parquet_processor() {
             build_processor(
-                &self.processor_config,
+                processor_config,
                 self.per_table_chunk_sizes.clone(),
                 self.deprecated_tables,
                 self.db_pool.clone(),
@@ -418,7 +824,7 @@ impl Worker {
             )
         } else {
             build_processor(
-                &self.processor_config,
+                processor_config,
                 self.per_table_chunk_sizes.clone(),
                 self.deprecated_tables,
                 self.db_pool.clone(),
@@ -427,6 +833,7 @@ impl Worker {
         };
 
         let concurrent_tasks = self.number_concurrent_processing_tasks;
+        let write_mode = self.write_mode(processor_config);
 
         let chain_id = self
             .grpc_chain_id
@@ -450,6 +857,12 @@ impl Worker {
                 {
                     // Fetched transactions from channel
                     Ok(transactions_pb) => {
+                        // A stream shared with other processors can start before this one's checkpoint
+                        let transactions_pb =
+                            match skip_processed_versions(transactions_pb, starting_version) {
+                                Some(transactions_pb) => transactions_pb,
+                                None => continue,
+                            };
                         let size_in_bytes = transactions_pb.size_in_bytes as f64;
                         let first_txn_version = transactions_pb
                             .transactions
@@ -525,6 +938,7 @@ impl Worker {
                             processor_name,
                             &auth_token,
                             false, // enable_verbose_logging
+                            write_mode.clone(),
                         )
                         .await;
 
@@ -693,6 +1107,22 @@ impl Worker {
                     },
                 }
             }
+
+            // Parquet handlers upload on their own schedule, so upload what they still buffer
+            // while the gap detector is there to record it
+            if let Err(e) = processor.flush().await {
+                error!(
+                    processor_name = processor_name,
+                    stream_address = stream_address.as_str(),
+                    error = ?e,
+                    task_index,
+                    "[Parser][T#{}] Error flushing processor", task_index
+                );
+                panic!(
+                    "[Parser][T#{}] Error flushing '{:}': {:?}",
+                    task_index, processor_name, e
+                );
+            }
         })
     }
 
@@ -736,18 +1166,83 @@ impl Worker {
         .expect("[Parser] Failed to run migrations");
     }
 
+    /// How the processor writes. Parquet processors don't write to the DB, so their batches
+    /// can't be written in a transaction.
+    fn write_mode(&self, processor_config: &ProcessorConfig) -> WriteMode {
+        WriteMode {
+            dry_run: self.dry_run.is_some(),
+            writable_tables: self
+                .backfill
+                .as_ref()
+                .map(|backfill| Arc::new(backfill.tables.clone())),
+            transactional_batch_writes: self.transactional_batch_writes
+                && !processor_config.is_parquet_processor(),
+        }
+    }
+
     /// Gets the start version for the processor. If not found, start from 0.
-    pub async fn get_start_version(&self) -> Result<Option<u64>> {
+    pub async fn get_start_version(&self, processor_name: &str) -> Result<Option<u64>> {
         let mut conn = self.db_pool.get().await?;
 
-        match ProcessorStatusQuery::get_by_processor(self.processor_config.name(), &mut conn)
-            .await?
-        {
+        match ProcessorStatusQuery::get_by_processor(processor_name, &mut conn).await? {
             Some(status) => Ok(Some(status.last_success_version as u64 + 1)),
             None => Ok(None),
         }
     }
 
+    /// Turns on table checkpoints for the processor and returns where to start from. Tables behind
+    /// the processor, e.g. a new table given a processor_table_status row to catch up from, move
+    /// the start back unless starting_version is configured. The other tables skip the batches
+    /// they already have.
+    async fn load_table_checkpoints(
+        &self,
+        processor_name: &'static str,
+        starting_version: u64,
+    ) -> Result<u64> {
+        let mut conn = self.db_pool.get().await?;
+        let checkpoints =
+            ProcessorTableStatusQuery::get_by_processor(processor_name, &mut conn).await?;
+        let starting_version = match self.starting_version {
+            Some(_) => starting_version,
+            None => checkpoints
+                .iter()
+                .map(|checkpoint| (checkpoint.last_success_version + 1).max(0) as u64)
+                .fold(starting_version, u64::min),
+        };
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            num_tables = checkpoints.len(),
+            starting_version,
+            "[Parser] Loaded table checkpoints"
+        );
+        table_checkpoints::enable(processor_name, starting_version, checkpoints);
+        Ok(starting_version)
+    }
+
+    /// Where to resume a backfill from, based on its backfill_status row
+    pub async fn get_backfill_start_version(
+        &self,
+        backfill_alias: &str,
+        processor_name: &str,
+    ) -> Result<Option<u64>> {
+        let mut conn = self.db_pool.get().await?;
+
+        match BackfillStatusQuery::get_by_alias(backfill_alias, &mut conn).await? {
+            Some(status) => {
+                anyhow::ensure!(
+                    status.processor == processor_name,
+                    "[Parser] Backfill {} was started by processor {}, not {}",
+                    backfill_alias,
+                    status.processor,
+                    processor_name
+                );
+                Ok(Some(status.last_success_version as u64 + 1))
+            },
+            None => Ok(None),
+        }
+    }
+
     /// Verify the chain id from GRPC against the database.
     pub async fn check_or_update_chain_id(&self, grpc_chain_id: i64) -> Result<u64> {
         let processor_name = self.processor_config.name();
@@ -792,6 +1287,82 @@ impl Worker {
     }
 }
 
+/// Resolves once the process receives SIGINT or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("[Parser] Failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("[Parser] Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Copies every batch from the fetcher into each processor's channel, sending to all of them at
+/// once. Each channel holds up to BUFFER_SIZE batches, so processors advance together: once the
+/// slowest processor's channel is full the next batch waits for it, and the others catch up
+/// and wait too. FAN_OUT_SEND_WAIT_TIME_SECS shows which processor that is. Closing the
+/// fetcher's channel closes all of them.
+async fn fan_out_transactions(
+    receiver: kanal::AsyncReceiver<TransactionsPBResponse>,
+    senders: Vec<(&'static str, AsyncSender<TransactionsPBResponse>)>,
+) {
+    while let Ok(transactions_pb) = receiver.recv().await {
+        futures::future::join_all(senders.iter().map(|(processor_name, sender)| {
+            let transactions_pb = transactions_pb.clone();
+            async move {
+                let send_time = std::time::Instant::now();
+                sender
+                    .send(transactions_pb)
+                    .await
+                    .expect("[Parser] Failed to send transactions to processor channel");
+                FAN_OUT_SEND_WAIT_TIME_SECS
+                    .with_label_values(&[*processor_name])
+                    .set(send_time.elapsed().as_secs_f64());
+            }
+        }))
+        .await;
+    }
+}
+
+/// Drops the transactions before `starting_version` from a batch, or the whole batch if it
+/// ends before it.
+fn skip_processed_versions(
+    mut transactions_pb: TransactionsPBResponse,
+    starting_version: u64,
+) -> Option<TransactionsPBResponse> {
+    if transactions_pb.end_version < starting_version {
+        return None;
+    }
+    if transactions_pb.start_version < starting_version {
+        transactions_pb
+            .transactions
+            .retain(|txn| txn.version >= starting_version);
+        transactions_pb.start_version = starting_version;
+        if let Some(timestamp) = transactions_pb
+            .transactions
+            .first()
+            .and_then(|txn| txn.timestamp.clone())
+        {
+            transactions_pb.start_txn_timestamp = Some(timestamp);
+        }
+    }
+    Some(transactions_pb)
+}
+
 async fn fetch_transactions(
     processor_name: &str,
     stream_address: &str,
@@ -823,6 +1394,23 @@ async fn fetch_transactions(
     }
 }
 
+/// Number of tasks processing batches at the same time. Dry runs process batches in order so
+/// that later upserts win, like they would in the DB. With transactional batch writes a batch's
+/// rows stay invisible to other batches until it commits, so processors that derive rows from
+/// what earlier batches wrote, e.g. nft points totals, ANS names or delegator rewards, would miss
+/// the rows of batches processed at the same time. Batches are processed one at a time there too.
+pub fn concurrent_processing_tasks(
+    configured_tasks: Option<usize>,
+    dry_run: bool,
+    transactional_batch_writes: bool,
+) -> usize {
+    if dry_run || transactional_batch_writes {
+        1
+    } else {
+        configured_tasks.unwrap_or(10)
+    }
+}
+
 pub async fn do_processor(
     transactions_pb: TransactionsPBResponse,
     processor: &Processor,
@@ -830,6 +1418,7 @@ pub async fn do_processor(
     processor_name: &str,
     auth_token: &str,
     enable_verbose_logging: bool,
+    write_mode: WriteMode,
 ) -> Result<ProcessingResult> {
     // We use the value passed from the `transactions_pb` as it may have been filtered
     let start_version = transactions_pb.start_version;
@@ -837,6 +1426,7 @@ pub async fn do_processor(
 
     // Fake this as it's possible we have filtered out all of the txns in this batch
     if transactions_pb.transactions.is_empty() {
+        table_checkpoints::mark_processed(processor.name(), start_version, end_version);
         return Ok(ProcessingResult::DefaultProcessingResult(
             DefaultProcessingResult {
                 start_version,
@@ -870,14 +1460,40 @@ pub async fn do_processor(
         );
     }
 
-    let processed_result = processor
-        .process_transactions(
-            transactions_pb.transactions,
-            start_version,
-            end_version,
-            Some(db_chain_id),
-        )
-        .await;
+    let batch = Batch {
+        processor_name: processor.name(),
+        start_version,
+        end_version,
+    };
+    let last_transaction_timestamp = transactions_pb
+        .end_txn_timestamp
+        .as_ref()
+        .map(|t| parse_timestamp(t, end_version as i64));
+    let processed_result = batch_transaction::run(
+        write_mode.transactional_batch_writes,
+        processor.get_pool(),
+        processor.name(),
+        start_version,
+        end_version,
+        last_transaction_timestamp,
+        |connection| {
+            let write_context =
+                WriteContext::new(processor.name(), write_mode).with_batch(batch, connection);
+            write_context::scope(
+                write_context,
+                processor.process_transactions(
+                    transactions_pb.transactions,
+                    start_version,
+                    end_version,
+                    Some(db_chain_id),
+                ),
+            )
+        },
+    )
+    .await;
+    if processed_result.is_ok() {
+        table_checkpoints::mark_processed(processor.name(), start_version, end_version);
+    }
 
     if let Some(ref t) = txn_time {
         PROCESSOR_DATA_PROCESSED_LATENCY_IN_SECS
@@ -925,23 +1541,36 @@ pub fn build_processor(
             per_table_chunk_sizes,
             deprecated_tables,
         )),
+        ProcessorConfig::CustomEventProcessor(config) => Processor::from(
+            CustomEventProcessor::new(db_pool, config.clone(), per_table_chunk_sizes),
+        ),
         ProcessorConfig::DefaultProcessor => Processor::from(DefaultProcessor::new(
             db_pool,
             per_table_chunk_sizes,
             deprecated_tables,
         )),
-        ProcessorConfig::EventsProcessor => {
-            Processor::from(EventsProcessor::new(db_pool, per_table_chunk_sizes))
-        },
-        ProcessorConfig::FungibleAssetProcessor => Processor::from(FungibleAssetProcessor::new(
+        ProcessorConfig::EventsProcessor(config) => Processor::from(EventsProcessor::new(
             db_pool,
+            config.clone(),
             per_table_chunk_sizes,
-            deprecated_tables,
         )),
+        ProcessorConfig::FungibleAssetProcessor(config) => {
+            Processor::from(FungibleAssetProcessor::new(
+                db_pool,
+                config.clone(),
+                per_table_chunk_sizes,
+                deprecated_tables,
+            ))
+        },
         ProcessorConfig::MonitoringProcessor => Processor::from(MonitoringProcessor::new(db_pool)),
         ProcessorConfig::NftMetadataProcessor(config) => {
             Processor::from(NftMetadataProcessor::new(db_pool, config.clone()))
         },
+        ProcessorConfig::NftPointsProcessor(config) => Processor::from(NftPointsProcessor::new(
+            db_pool,
+            config.clone(),
+            per_table_chunk_sizes,
+        )),
         ProcessorConfig::ObjectsProcessor(config) => Processor::from(ObjectsProcessor::new(
             db_pool,
             config.clone(),
@@ -962,9 +1591,14 @@ pub fn build_processor(
         ProcessorConfig::TransactionMetadataProcessor => Processor::from(
             TransactionMetadataProcessor::new(db_pool, per_table_chunk_sizes),
         ),
-        ProcessorConfig::UserTransactionProcessor => Processor::from(
-            UserTransactionProcessor::new(db_pool, per_table_chunk_sizes, deprecated_tables),
-        ),
+        ProcessorConfig::UserTransactionProcessor(config) => {
+            Processor::from(UserTransactionProcessor::new(
+                db_pool,
+                config.clone(),
+                per_table_chunk_sizes,
+                deprecated_tables,
+            ))
+        },
         ProcessorConfig::ParquetDefaultProcessor(config) => {
             Processor::from(ParquetDefaultProcessor::new(
                 db_pool,
@@ -1014,3 +1648,41 @@ pub fn build_processor(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::Transaction;
+
+    fn batch(start_version: u64, end_version: u64) -> TransactionsPBResponse {
+        TransactionsPBResponse {
+            transactions: (start_version..=end_version)
+                .map(|version| Transaction {
+                    version,
+                    ..Transaction::default()
+                })
+                .collect(),
+            chain_id: 1,
+            start_version,
+            end_version,
+            start_txn_timestamp: None,
+            end_txn_timestamp: None,
+            size_in_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn skip_processed_versions_test() {
+        assert!(skip_processed_versions(batch(0, 99), 100).is_none());
+
+        let trimmed = skip_processed_versions(batch(0, 99), 50).unwrap();
+        assert_eq!(trimmed.start_version, 50);
+        assert_eq!(trimmed.end_version, 99);
+        assert_eq!(trimmed.transactions.len(), 50);
+        assert_eq!(trimmed.transactions[0].version, 50);
+
+        let untouched = skip_processed_versions(batch(100, 199), 50).unwrap();
+        assert_eq!(untouched.start_version, 100);
+        assert_eq!(untouched.transactions.len(), 100);
+    }
+}